@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Date, DateRange, Money, Weekday};
+
+/// How often a spending limit starts over
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    #[default]
+    Monthly,
+    /// Weeks start on the day chosen in preferences
+    Weekly,
+    /// Every `days` days, counted from `start`
+    Custom { start: Date, days: u32 },
+}
+
+impl BudgetPeriod {
+    /// Period that `date` falls in
+    pub fn range_containing(&self, date: Date, first_day: Weekday) -> DateRange {
+        match *self {
+            BudgetPeriod::Monthly => DateRange::month_of(date),
+            BudgetPeriod::Weekly => DateRange::week_of(date, first_day),
+            BudgetPeriod::Custom { start, days } => {
+                let days = days.max(1) as i64;
+                let elapsed = date.days_since(start).div_euclid(days);
+                let period_start = start.add_days(elapsed * days);
+
+                DateRange::new(period_start, period_start.add_days(days))
+            }
+        }
+    }
+}
+
+/// What happens to the money left at the end of a budget period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rollover {
+    /// Every period starts with just the limit
+    #[default]
+    Reset,
+    /// Unspent money is added to the next period
+    Surplus,
+    /// Overspending is taken from the next period
+    Deficit,
+    /// Both unspent money and overspending carry over
+    Both,
+}
+
+impl Rollover {
+    /// Part of what was `left` at the end of a period that goes to the next one
+    pub fn carry(&self, left: Money) -> Money {
+        match self {
+            Rollover::Reset => Money::ZERO,
+            Rollover::Surplus if left.is_negative() => Money::ZERO,
+            Rollover::Deficit if left.is_positive() => Money::ZERO,
+            _ => left,
+        }
+    }
+}
+
+/// Spending limit of a group
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Budget {
+    /// Maximum expenses per period, in the group's currency
+    pub limit: Money,
+    pub period: BudgetPeriod,
+    pub rollover: Rollover,
+    /// Day the budget was set up. Earlier periods carry nothing over.
+    pub since: Option<Date>,
+}
+
+/// Spending of a group within one budget period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetStatus {
+    pub range: DateRange,
+    pub limit: Money,
+    /// Amount carried over from previous periods, negative for a deficit
+    pub carried: Money,
+    pub spent: Money,
+}
+
+impl BudgetStatus {
+    /// Limit of the period adjusted by what carried over
+    pub fn available(&self) -> Money {
+        self.limit + self.carried
+    }
+
+    /// What can still be spent, negative once over the limit
+    pub fn remaining(&self) -> Money {
+        self.available() - self.spent
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.spent > self.available()
+    }
+
+    /// Share of the available amount already spent, 1.0 when it is reached
+    pub fn fraction(&self) -> f64 {
+        let available = self.available();
+
+        if available.is_positive() {
+            self.spent.to_f64() / available.to_f64()
+        } else if self.spent.is_positive() {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        Date::parse_iso(text).unwrap()
+    }
+
+    #[test]
+    fn custom_periods_repeat_from_start() {
+        let period = BudgetPeriod::Custom {
+            start: date("2022-07-01"),
+            days: 14,
+        };
+
+        let range = period.range_containing(date("2022-07-20"), Weekday::Monday);
+        assert_eq!(
+            range,
+            DateRange::new(date("2022-07-15"), date("2022-07-29"))
+        );
+
+        let before = period.range_containing(date("2022-06-30"), Weekday::Monday);
+        assert_eq!(
+            before,
+            DateRange::new(date("2022-06-17"), date("2022-07-01"))
+        );
+    }
+
+    #[test]
+    fn weekly_periods_follow_first_day() {
+        let range = BudgetPeriod::Weekly.range_containing(date("2022-07-06"), Weekday::Sunday);
+
+        assert_eq!(
+            range,
+            DateRange::new(date("2022-07-03"), date("2022-07-10"))
+        );
+    }
+
+    #[test]
+    fn status_reports_overspending() {
+        let status = BudgetStatus {
+            range: DateRange::month_of(date("2022-07-01")),
+            limit: "100".parse().unwrap(),
+            carried: Money::ZERO,
+            spent: "125".parse().unwrap(),
+        };
+
+        assert!(status.is_over());
+        assert_eq!(status.remaining(), "-25".parse().unwrap());
+        assert_eq!(status.fraction(), 1.25);
+
+        let with_surplus = BudgetStatus {
+            carried: "50".parse().unwrap(),
+            ..status
+        };
+
+        assert!(!with_surplus.is_over());
+        assert_eq!(with_surplus.remaining(), "25".parse().unwrap());
+    }
+
+    #[test]
+    fn rollover_carries_only_chosen_side() {
+        let surplus: Money = "20".parse().unwrap();
+        let deficit: Money = "-20".parse().unwrap();
+
+        assert_eq!(Rollover::Reset.carry(surplus), Money::ZERO);
+        assert_eq!(Rollover::Surplus.carry(surplus), surplus);
+        assert_eq!(Rollover::Surplus.carry(deficit), Money::ZERO);
+        assert_eq!(Rollover::Deficit.carry(surplus), Money::ZERO);
+        assert_eq!(Rollover::Deficit.carry(deficit), deficit);
+        assert_eq!(Rollover::Both.carry(deficit), deficit);
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let budget = Budget {
+            limit: "500".parse().unwrap(),
+            period: BudgetPeriod::Custom {
+                start: date("2022-07-01"),
+                days: 10,
+            },
+            rollover: Rollover::Surplus,
+            since: Some(date("2022-07-01")),
+        };
+
+        let value = serde_json::to_value(budget).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "limit": "500.00",
+                "period": { "kind": "custom", "start": "2022-07-01", "days": 10 },
+                "rollover": "surplus",
+                "since": "2022-07-01"
+            })
+        );
+        assert_eq!(serde_json::from_value::<Budget>(value).unwrap(), budget);
+    }
+}
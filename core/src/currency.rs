@@ -0,0 +1,167 @@
+use anyhow::{bail, Result};
+
+use crate::{Money, NumberFormat};
+
+/// Currency used by groups created before currencies existed
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// ISO 4217 currency with display information
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Currency {
+    pub code: &'static str,
+    pub symbol: &'static str,
+    pub name: &'static str,
+    /// Number of minor unit digits, e.g. 2 for cents
+    pub decimals: u32,
+}
+
+macro_rules! currency {
+    ($code:literal, $symbol:literal, $name:literal, $decimals:literal) => {
+        Currency {
+            code: $code,
+            symbol: $symbol,
+            name: $name,
+            decimals: $decimals,
+        }
+    };
+}
+
+static CURRENCIES: &[Currency] = &[
+    currency!("ARS", "$", "Argentine Peso", 2),
+    currency!("AUD", "A$", "Australian Dollar", 2),
+    currency!("BHD", "BD", "Bahraini Dinar", 3),
+    currency!("BRL", "R$", "Brazilian Real", 2),
+    currency!("CAD", "CA$", "Canadian Dollar", 2),
+    currency!("CHF", "CHF", "Swiss Franc", 2),
+    currency!("CLP", "$", "Chilean Peso", 0),
+    currency!("CNY", "¥", "Chinese Yuan", 2),
+    currency!("COP", "$", "Colombian Peso", 2),
+    currency!("CZK", "Kč", "Czech Koruna", 2),
+    currency!("DKK", "kr", "Danish Krone", 2),
+    currency!("EUR", "€", "Euro", 2),
+    currency!("GBP", "£", "British Pound", 2),
+    currency!("HKD", "HK$", "Hong Kong Dollar", 2),
+    currency!("HUF", "Ft", "Hungarian Forint", 2),
+    currency!("INR", "₹", "Indian Rupee", 2),
+    currency!("JPY", "¥", "Japanese Yen", 0),
+    currency!("KRW", "₩", "South Korean Won", 0),
+    currency!("KWD", "KD", "Kuwaiti Dinar", 3),
+    currency!("MXN", "MX$", "Mexican Peso", 2),
+    currency!("NOK", "kr", "Norwegian Krone", 2),
+    currency!("NZD", "NZ$", "New Zealand Dollar", 2),
+    currency!("PLN", "zł", "Polish Złoty", 2),
+    currency!("PYG", "₲", "Paraguayan Guaraní", 0),
+    currency!("SEK", "kr", "Swedish Krona", 2),
+    currency!("TRY", "₺", "Turkish Lira", 2),
+    currency!("USD", "$", "US Dollar", 2),
+    currency!("UYU", "$U", "Uruguayan Peso", 2),
+    currency!("ZAR", "R", "South African Rand", 2),
+];
+
+impl Currency {
+    /// Every supported currency, sorted by code
+    pub fn all() -> &'static [Currency] {
+        CURRENCIES
+    }
+
+    pub fn from_code(code: &str) -> Option<&'static Currency> {
+        CURRENCIES
+            .iter()
+            .find(|c| c.code.eq_ignore_ascii_case(code))
+    }
+
+    /// Same as `from_code`, falling back to the default currency for unknown codes
+    pub fn from_code_or_default(code: &str) -> &'static Currency {
+        Self::from_code(code)
+            .or_else(|| Self::from_code(DEFAULT_CURRENCY))
+            .expect("Default currency missing from table")
+    }
+
+    /// Format amount with the currency symbol and minor unit digits, e.g. `R$ 12.30`
+    pub fn format(&self, amount: Money) -> String {
+        self.format_with(amount, NumberFormat::default())
+    }
+
+    /// Same as `format`, with the separators of `number_format`, e.g. `R$ 1.234,50`
+    pub fn format_with(&self, amount: Money, number_format: NumberFormat) -> String {
+        let number = number_format.apply(&amount.abs().format_fixed(self.decimals));
+        let sign = if amount.round_to(self.decimals).is_negative() {
+            "-"
+        } else {
+            ""
+        };
+
+        // Multi-character symbols read better with a separating space
+        if self.symbol.chars().count() > 1 {
+            format!("{}{} {}", sign, self.symbol, number)
+        } else {
+            format!("{}{}{}", sign, self.symbol, number)
+        }
+    }
+
+    /// Parse user input written with the separators of `number_format`,
+    /// rejecting more decimal places than the currency has
+    pub fn parse(&self, text: &str, number_format: NumberFormat) -> Result<Money> {
+        let text = text.trim();
+        let text = text.strip_prefix(self.symbol).unwrap_or(text);
+        let amount = Money::parse_with(text, number_format)?;
+
+        if amount.fractional_digits() > self.decimals {
+            bail!(
+                "{} amounts have at most {} decimal places",
+                self.code,
+                self.decimals
+            );
+        }
+
+        Ok(amount)
+    }
+
+    /// Label shown in currency selectors, e.g. `BRL (R$)`
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.code, self.symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_with_number_format() {
+        let brl = Currency::from_code("BRL").unwrap();
+        let amount: Money = "-1234.5".parse().unwrap();
+
+        assert_eq!(brl.format(amount), "-R$ 1,234.50");
+        assert_eq!(brl.format_with(amount, NumberFormat::Comma), "-R$ 1.234,50");
+    }
+
+    #[test]
+    fn parses_grouped_input() {
+        let eur = Currency::from_code("EUR").unwrap();
+
+        assert_eq!(
+            eur.parse("€1 234,56", NumberFormat::Space).unwrap(),
+            "1234.56".parse().unwrap()
+        );
+        assert_eq!(
+            eur.parse("1.005,5", NumberFormat::Comma).unwrap(),
+            "1005.5".parse().unwrap()
+        );
+        assert!(eur.parse("0.125", NumberFormat::Point).is_err());
+    }
+
+    #[test]
+    fn parses_with_number_format() {
+        let usd = Currency::from_code("USD").unwrap();
+
+        assert_eq!(
+            usd.parse("1,500", NumberFormat::Point).unwrap(),
+            "1500".parse().unwrap()
+        );
+        assert_eq!(
+            usd.parse("1,5", NumberFormat::Comma).unwrap(),
+            "1.5".parse().unwrap()
+        );
+    }
+}
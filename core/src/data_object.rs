@@ -0,0 +1,149 @@
+use std::ffi::OsString;
+use std::fs::{self, remove_file, File};
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::migrations::{self, is_newer_schema, Migration};
+
+/// Path with `suffix` appended to the full file name, e.g. `group.json` -> `group.json.bak`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// Copy of the last good version of a data file
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+/// Replace file contents so that readers only ever see the old or the new version.
+///
+/// Data goes to a temporary file that is flushed to disk and then renamed
+/// over the target, so a crash or full disk never leaves a truncated file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp_path = with_suffix(path, ".tmp");
+
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp_path, path)?;
+
+        // Persist the rename itself
+        if let Some(dir) = path.parent() {
+            File::open(dir)?.sync_all()?;
+        }
+
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// Keep the current version of `path` as its `.bak` before it is overwritten
+fn rotate_backup(path: &Path) -> Result<()> {
+    let backup = backup_path(path);
+
+    match fs::read(path) {
+        Ok(contents) => write_atomic(&backup, &contents),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+pub trait DataObject {
+    /// Schema migrations, where entry `i` upgrades version `i` to `i + 1`
+    fn migrations() -> &'static [Migration]
+    where
+        Self: Sized,
+    {
+        &[]
+    }
+
+    /// Load object, upgrading documents saved with an older schema.
+    ///
+    /// Upgraded files are backed up and then rewritten with the current schema.
+    fn load_from_file(path: &Path) -> Result<Self>
+    where
+        Self: Sized + DeserializeOwned + Serialize,
+    {
+        let mut file = File::open(path)?;
+        let mut fc = String::new();
+
+        file.read_to_string(&mut fc)?;
+
+        let mut doc: Value = serde_json::from_str(&fc)?;
+        let migrations = Self::migrations();
+        let original_version = migrations::upgrade(&mut doc, migrations)?;
+
+        let val: Self = serde_json::from_value(doc)?;
+
+        if original_version < migrations.len() as u32 {
+            migrations::backup_file(path, original_version)?;
+            write_atomic(path, serde_json::to_string(&val)?.as_bytes())?;
+        }
+
+        Ok(val)
+    }
+
+    /// Same as `load_from_file`, but falls back to the `.bak` copy when the
+    /// file is corrupt. The recovered version then replaces the broken file.
+    fn load_from_file_or_backup(path: &Path) -> Result<Self>
+    where
+        Self: Sized + DeserializeOwned + Serialize,
+    {
+        match Self::load_from_file(path) {
+            Ok(val) => Ok(val),
+            Err(error) => {
+                // Newer files are fine, an older backup must not replace them
+                let backup = backup_path(path);
+                if !backup.exists() || is_newer_schema(&error) {
+                    return Err(error);
+                }
+
+                let val = Self::load_from_file(&backup).map_err(|backup_error| {
+                    anyhow!("{} (backup also unreadable: {})", error, backup_error)
+                })?;
+                write_atomic(path, serde_json::to_string(&val)?.as_bytes())?;
+
+                Ok(val)
+            }
+        }
+    }
+
+    fn filename(&self) -> String;
+
+    fn save_to_file(&self, path: &Path) -> Result<()>
+    where
+        Self: Serialize,
+    {
+        let filename = path.join(self.filename());
+        let serialized = serde_json::to_string(self)?;
+
+        rotate_backup(&filename)?;
+        write_atomic(&filename, serialized.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn delete_file(&self, base_path: &Path) -> Result<()> {
+        let filename = base_path.join(self.filename());
+        remove_file(&filename)?;
+
+        match remove_file(backup_path(&filename)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
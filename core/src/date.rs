@@ -0,0 +1,291 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Calendar date without time or timezone.
+///
+/// Transactions store ISO 8601 date-times in local time, so their date is
+/// the first ten characters, e.g. `2022-07-04` in `2022-07-04T17:03:12-03`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+/// Day of week, numbered from Monday (1) to Sunday (7) as in ISO 8601
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    Monday = 1,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub fn from_number(number: u32) -> Option<Self> {
+        match number {
+            1 => Some(Weekday::Monday),
+            2 => Some(Weekday::Tuesday),
+            3 => Some(Weekday::Wednesday),
+            4 => Some(Weekday::Thursday),
+            5 => Some(Weekday::Friday),
+            6 => Some(Weekday::Saturday),
+            7 => Some(Weekday::Sunday),
+            _ => None,
+        }
+    }
+
+    pub fn number(&self) -> u32 {
+        *self as u32
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ if is_leap_year(year) => 29,
+        _ => 28,
+    }
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Result<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(anyhow!("Invalid date {:04}-{:02}-{:02}", year, month, day));
+        }
+
+        Ok(Self { year, month, day })
+    }
+
+    /// Date part of an ISO 8601 date or date-time
+    pub fn parse_iso(text: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid date \"{}\"", text);
+
+        let date = text.get(..10).ok_or_else(invalid)?;
+        let mut parts = date.split('-');
+        let year = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let month = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let day = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+
+        Self::new(year, month, day)
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// Days since 1970-01-01
+    fn to_days(self) -> i64 {
+        // Howard Hinnant's days_from_civil
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = self.month as i64;
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Self {
+            year: year as i32,
+            month,
+            day,
+        }
+    }
+
+    pub fn add_days(&self, days: i64) -> Self {
+        Self::from_days(self.to_days() + days)
+    }
+
+    /// Number of days from `other` to `self`
+    pub fn days_since(&self, other: Date) -> i64 {
+        self.to_days() - other.to_days()
+    }
+
+    /// Same day `months` later, clamped to the end of shorter months
+    pub fn add_months(&self, months: i32) -> Self {
+        let total = self.year * 12 + self.month as i32 - 1 + months;
+        let year = total.div_euclid(12);
+        let month = total.rem_euclid(12) as u32 + 1;
+
+        Self {
+            year,
+            month,
+            day: self.day.min(days_in_month(year, month)),
+        }
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        let number = (self.to_days() + 3).rem_euclid(7) as u32 + 1;
+        Weekday::from_number(number).unwrap()
+    }
+
+    pub fn first_of_month(&self) -> Self {
+        Self { day: 1, ..*self }
+    }
+
+    pub fn first_of_year(&self) -> Self {
+        Self {
+            month: 1,
+            day: 1,
+            ..*self
+        }
+    }
+
+    /// Start of the week containing this date, for weeks starting on `first_day`
+    pub fn start_of_week(&self, first_day: Weekday) -> Self {
+        let offset = (self.weekday().number() + 7 - first_day.number()) % 7;
+        self.add_days(-(offset as i64))
+    }
+}
+
+impl fmt::Display for Date {
+    /// ISO 8601, e.g. `2022-07-04`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+
+        Date::parse_iso(&text).map_err(de::Error::custom)
+    }
+}
+
+/// Days from `start` up to, but not including, `end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub start: Date,
+    pub end: Date,
+}
+
+impl DateRange {
+    pub fn new(start: Date, end: Date) -> Self {
+        Self { start, end }
+    }
+
+    pub fn month_of(date: Date) -> Self {
+        let start = date.first_of_month();
+        Self::new(start, start.add_months(1))
+    }
+
+    pub fn year_of(date: Date) -> Self {
+        let start = date.first_of_year();
+        Self::new(start, start.add_months(12))
+    }
+
+    pub fn week_of(date: Date, first_day: Weekday) -> Self {
+        let start = date.start_of_week(first_day);
+        Self::new(start, start.add_days(7))
+    }
+
+    pub fn contains(&self, date: Date) -> bool {
+        self.start <= date && date < self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        Date::parse_iso(text).unwrap()
+    }
+
+    #[test]
+    fn parses_date_times() {
+        assert_eq!(date("2022-07-04T17:03:12.345678-03"), date("2022-07-04"));
+        assert!(Date::parse_iso("2022-02-30").is_err());
+        assert!(Date::parse_iso("07/04/2022").is_err());
+    }
+
+    #[test]
+    fn day_arithmetic() {
+        assert_eq!(date("2022-12-31").add_days(1), date("2023-01-01"));
+        assert_eq!(date("2024-03-01").add_days(-1), date("2024-02-29"));
+        assert_eq!(date("1969-12-31").add_days(366), date("1971-01-01"));
+        assert_eq!(date("2022-07-04").days_since(date("2022-01-01")), 184);
+    }
+
+    #[test]
+    fn month_arithmetic_clamps_day() {
+        assert_eq!(date("2022-01-31").add_months(1), date("2022-02-28"));
+        assert_eq!(date("2024-01-31").add_months(1), date("2024-02-29"));
+        assert_eq!(date("2022-11-15").add_months(3), date("2023-02-15"));
+        assert_eq!(date("2022-01-15").add_months(-1), date("2021-12-15"));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(date("2022-07-04").weekday(), Weekday::Monday);
+        assert_eq!(date("2000-01-01").weekday(), Weekday::Saturday);
+        assert_eq!(
+            date("2022-07-06").start_of_week(Weekday::Sunday),
+            date("2022-07-03")
+        );
+        assert_eq!(
+            date("2022-07-03").start_of_week(Weekday::Monday),
+            date("2022-06-27")
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        let month = DateRange::month_of(date("2022-12-15"));
+
+        assert_eq!(month.start, date("2022-12-01"));
+        assert_eq!(month.end, date("2023-01-01"));
+        assert!(month.contains(date("2022-12-31")));
+        assert!(!month.contains(date("2023-01-01")));
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::currency::DEFAULT_CURRENCY;
+use crate::data_object::write_atomic;
+use crate::tags::{self, TagTotal};
+use crate::{Currency, DateRange, Group, Money};
+
+/// Name of the rate table file, stored next to the `groups` directory
+pub const EXCHANGE_RATES_FILE: &str = "exchange-rates.json";
+
+/// User-maintained exchange rate table.
+///
+/// Each entry in `rates` says how many units of `base` one unit of that
+/// currency is worth, e.g. with `base` BRL, `"EUR": "5.43"` means 1 EUR = 5.43 BRL.
+/// Rates are decimal strings so they are kept exactly as typed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRates {
+    pub base: String,
+    #[serde(default)]
+    pub rates: BTreeMap<String, String>,
+}
+
+impl Default for ExchangeRates {
+    fn default() -> Self {
+        Self {
+            base: String::from(DEFAULT_CURRENCY),
+            rates: BTreeMap::new(),
+        }
+    }
+}
+
+/// Fractional digits kept for rates
+const RATE_DECIMALS: u32 = 8;
+
+/// Parse a decimal rate into units of 10^-8
+fn parse_rate(text: &str) -> Result<i128> {
+    let text = text.trim();
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+
+    if whole.is_empty() && fraction.is_empty()
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+        || fraction.len() > RATE_DECIMALS as usize
+    {
+        bail!("Invalid exchange rate \"{}\"", text);
+    }
+
+    let whole: i128 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let fraction: i128 = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<i128>()? * 10_i128.pow(RATE_DECIMALS - fraction.len() as u32)
+    };
+
+    let rate = whole * 10_i128.pow(RATE_DECIMALS) + fraction;
+    if rate == 0 {
+        bail!("Exchange rate cannot be zero");
+    }
+
+    Ok(rate)
+}
+
+/// Integer division rounding half away from zero
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+
+    if remainder.abs() * 2 >= denominator.abs() {
+        quotient + numerator.signum() * denominator.signum()
+    } else {
+        quotient
+    }
+}
+
+impl ExchangeRates {
+    /// Load table from save directory, writing an empty template if there is none
+    pub fn load(base_path: &Path) -> Result<Self> {
+        let path = base_path.join(EXCHANGE_RATES_FILE);
+
+        match File::open(&path) {
+            Ok(mut file) => {
+                let mut fc = String::new();
+                file.read_to_string(&mut fc)?;
+
+                Ok(serde_json::from_str(&fc)?)
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                let rates = Self::default();
+                rates.save(base_path)?;
+
+                Ok(rates)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save(&self, base_path: &Path) -> Result<()> {
+        fs::create_dir_all(base_path)?;
+        let serialized = serde_json::to_string_pretty(self)?;
+        write_atomic(&base_path.join(EXCHANGE_RATES_FILE), serialized.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Rate of `code` in units of 10^-8 of the base currency
+    fn rate(&self, code: &str) -> Result<i128> {
+        if code.eq_ignore_ascii_case(&self.base) {
+            return Ok(10_i128.pow(RATE_DECIMALS));
+        }
+
+        let rate = self
+            .rates
+            .iter()
+            .find(|(c, _)| c.eq_ignore_ascii_case(code))
+            .map(|(_, rate)| rate)
+            .ok_or_else(|| anyhow!("No exchange rate for {} in {}", code, EXCHANGE_RATES_FILE))?;
+
+        parse_rate(rate)
+    }
+
+    /// Convert amount between two currencies through the base currency
+    pub fn convert(&self, amount: Money, from: &str, to: &str) -> Result<Money> {
+        if from.eq_ignore_ascii_case(to) {
+            return Ok(amount);
+        }
+
+        let from_rate = self.rate(from)?;
+        let to_rate = self.rate(to)?;
+
+        let converted = div_round(amount.units() as i128 * from_rate, to_rate);
+        let converted =
+            i64::try_from(converted).map_err(|_| anyhow!("Converted amount is too large"))?;
+
+        Ok(Money::from_units(converted))
+    }
+
+    /// Sum of every group balance, converted to the base currency
+    pub fn total_balance<'a>(
+        &self,
+        groups: impl IntoIterator<Item = &'a Group>,
+    ) -> Result<(Money, &'static Currency)> {
+        let currency = Currency::from_code(&self.base)
+            .ok_or_else(|| anyhow!("Unknown base currency {}", self.base))?;
+
+        let mut total = Money::ZERO;
+        for group in groups {
+            total += self.convert(group.balance(), group.currency().code, currency.code)?;
+        }
+
+        Ok((total, currency))
+    }
+
+    /// Totals of every tag over the transactions of all `groups` within
+    /// `period`, converted to the base currency
+    pub fn tag_totals<'a>(
+        &self,
+        groups: impl IntoIterator<Item = &'a Group>,
+        period: Option<DateRange>,
+    ) -> Result<(BTreeMap<String, TagTotal>, &'static Currency)> {
+        let currency = Currency::from_code(&self.base)
+            .ok_or_else(|| anyhow!("Unknown base currency {}", self.base))?;
+
+        let mut totals: BTreeMap<String, TagTotal> = BTreeMap::new();
+        for group in groups {
+            let from = group.currency().code;
+
+            for (tag, total) in tags::totals_by_tag(&group.transactions, period) {
+                *totals.entry(tag).or_default() += TagTotal {
+                    income: self.convert(total.income, from, currency.code)?,
+                    expenses: self.convert(total.expenses, from, currency.code)?,
+                    count: total.count,
+                };
+            }
+        }
+
+        Ok((totals, currency))
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::export::journal::{column_widths, entries, Accounts};
+use crate::{Date, DateRange, Group};
+
+/// Transactions of `groups` within `period` as a beancount ledger.
+///
+/// Every account is opened on the day of the first transaction. Account
+/// roots must be one of beancount's five, so `accounts` should start with
+/// `Assets`, `Liabilities`, `Equity`, `Income` or `Expenses`. Transfers
+/// between groups in different currencies are priced with what arrived.
+/// The same data always gives the same ledger.
+pub fn write(groups: &[Group], period: Option<DateRange>, accounts: &Accounts) -> String {
+    let entries = entries(groups, period, accounts, account_component);
+    let (account_width, amount_width) = column_widths(&entries);
+
+    let mut ledger = String::new();
+
+    let mut opened: BTreeMap<&str, Date> = BTreeMap::new();
+    for entry in &entries {
+        for posting in &entry.postings {
+            opened.entry(&posting.account).or_insert(entry.date);
+        }
+    }
+    if let Some(first) = entries.first() {
+        for account in opened.keys() {
+            let _ = writeln!(ledger, "{} open {}", first.date, account);
+        }
+        ledger.push('\n');
+    }
+
+    for entry in &entries {
+        let transaction = entry.transaction;
+
+        let _ = write!(ledger, "{} * \"{}\"", entry.date, escape(&transaction.name));
+        for tag in &transaction.tags {
+            let _ = write!(ledger, " #{}", tag_name(tag));
+        }
+        ledger.push('\n');
+
+        if let Some(notes) = &transaction.notes {
+            let _ = writeln!(ledger, "  notes: \"{}\"", escape(notes));
+        }
+
+        let (own, other) = (&entry.postings[0], &entry.postings[1]);
+        let price = if own.currency == other.currency {
+            String::new()
+        } else {
+            format!(" @@ {} {}", other.amount.abs(), other.currency)
+        };
+        for (posting, price) in [(own, price), (other, String::new())] {
+            let _ = writeln!(
+                ledger,
+                "  {:<account_width$}  {:>amount_width$} {}{}",
+                posting.account,
+                posting.amount.to_string(),
+                posting.currency,
+                price,
+            );
+        }
+        ledger.push('\n');
+    }
+
+    ledger
+}
+
+/// Part of an account name: words of letters and digits joined by dashes,
+/// starting with a capital letter
+fn account_component(name: &str) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    if words.is_empty() {
+        String::from("Unnamed")
+    } else {
+        words.join("-")
+    }
+}
+
+/// Tag with characters beancount doesn't allow replaced by dashes
+fn tag_name(tag: &str) -> String {
+    tag.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '/' | '.' => c,
+            _ => '-',
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transfer::transfer_pair;
+    use crate::{Currency, Transaction, TransactionType};
+    use uuid::Uuid;
+
+    #[test]
+    fn writes_beancount_ledger() {
+        let mut market = Group::new(
+            "🛒",
+            [0.0; 4],
+            "café & bar",
+            Currency::from_code("BRL").unwrap(),
+        );
+        let mut travel = Group::new(
+            "✈️",
+            [0.0; 4],
+            "Travel",
+            Currency::from_code("USD").unwrap(),
+        );
+        market.new_transaction(Transaction {
+            tags: vec![String::from("food:coffee")],
+            notes: Some(String::from("With \"extra\" foam")),
+            ..Transaction::new(
+                "Espresso",
+                TransactionType::EXPENSE,
+                "7".parse().unwrap(),
+                "2022-07-04T09:00:00",
+            )
+        });
+        let (outgoing, incoming) = transfer_pair(
+            "Exchange",
+            "500".parse().unwrap(),
+            "100".parse().unwrap(),
+            "2022-07-05T09:00:00",
+            market.id,
+            travel.id,
+        );
+        market.new_transaction(outgoing);
+        travel.new_transaction(incoming);
+
+        let ledger = write(&[market, travel], None, &Accounts::default());
+
+        assert_eq!(
+            ledger,
+            "2022-07-04 open Assets:Cash\n\
+             2022-07-04 open Expenses:Café-Bar\n\
+             2022-07-04 open Expenses:Travel\n\
+             \n\
+             2022-07-04 * \"Espresso\" #food-coffee\n\
+             \x20 notes: \"With \\\"extra\\\" foam\"\n\
+             \x20 Expenses:Café-Bar     7.00 BRL\n\
+             \x20 Assets:Cash          -7.00 BRL\n\
+             \n\
+             2022-07-05 * \"Exchange\"\n\
+             \x20 Expenses:Café-Bar   500.00 BRL @@ 100.00 USD\n\
+             \x20 Expenses:Travel    -100.00 USD\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn groups_with_the_same_account_are_told_apart() {
+        let groups: Vec<Group> = ["Café & Bar", "café-bar", "🎉", "🎁"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mut group =
+                    Group::new("💰", [0.0; 4], name, Currency::from_code("BRL").unwrap());
+                group.id = Uuid::from_u128((0x1a2b3c4d + i as u128) << 96);
+                group.new_transaction(Transaction::new(
+                    "Snack",
+                    TransactionType::EXPENSE,
+                    "1".parse().unwrap(),
+                    "2022-07-04T09:00:00",
+                ));
+                group
+            })
+            .collect();
+
+        let ledger = write(&groups, None, &Accounts::default());
+
+        for account in [
+            "Expenses:Café-Bar-1a2b3c4d ",
+            "Expenses:Café-Bar-1a2b3c4e ",
+            "Expenses:Unnamed-1a2b3c4f ",
+            "Expenses:Unnamed-1a2b3c50 ",
+        ] {
+            assert!(
+                ledger.contains(account),
+                "{} missing from\n{}",
+                account,
+                ledger
+            );
+        }
+    }
+}
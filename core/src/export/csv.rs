@@ -0,0 +1,156 @@
+use crate::export::{transactions_in, type_name};
+use crate::{DateRange, Group, NumberFormat};
+
+/// Header of the exported file, in column order
+pub const COLUMNS: &[&str] = &[
+    "date", "group", "emoji", "name", "type", "amount", "currency", "tags", "payee", "location",
+    "notes",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvDialect {
+    /// Commas between fields and a point before decimals, as RFC 4180 and
+    /// most programs expect
+    Rfc4180,
+    /// What spreadsheets expect in the locale of `NumberFormat`: where the
+    /// comma is the decimal separator, fields are separated by semicolons
+    Locale(NumberFormat),
+}
+
+impl CsvDialect {
+    fn delimiter(&self) -> char {
+        match self {
+            CsvDialect::Locale(format) if format.decimal_separator() == ',' => ';',
+            _ => ',',
+        }
+    }
+
+    fn decimal_separator(&self) -> char {
+        match self {
+            CsvDialect::Rfc4180 => '.',
+            CsvDialect::Locale(format) => format.decimal_separator(),
+        }
+    }
+}
+
+/// Transactions of `groups` within `period` as CSV, one per line after the
+/// header. Amounts are signed and written without digit grouping, dates are
+/// ISO 8601 days. Lines end in CRLF.
+pub fn write(groups: &[Group], period: Option<DateRange>, dialect: CsvDialect) -> String {
+    let delimiter = dialect.delimiter();
+    let mut csv = String::new();
+
+    let mut push_record = |fields: &[&str]| {
+        let record: Vec<String> = fields.iter().map(|field| quote(field, delimiter)).collect();
+        csv.push_str(&record.join(&delimiter.to_string()));
+        csv.push_str("\r\n");
+    };
+
+    push_record(COLUMNS);
+
+    for (group, transaction) in transactions_in(groups, period) {
+        let date = transaction
+            .day()
+            .map(|day| day.to_string())
+            .unwrap_or_else(|| transaction.date.clone());
+        let amount = transaction
+            .signed_amount()
+            .to_string()
+            .replace('.', &dialect.decimal_separator().to_string());
+
+        push_record(&[
+            &date,
+            &group.name,
+            &group.emoji,
+            &transaction.name,
+            type_name(transaction.tr_type),
+            &amount,
+            &group.currency,
+            &transaction.tags.join(", "),
+            transaction.payee.as_deref().unwrap_or_default(),
+            transaction.location.as_deref().unwrap_or_default(),
+            transaction.notes.as_deref().unwrap_or_default(),
+        ]);
+    }
+
+    csv
+}
+
+/// Field quoted if it holds the delimiter, a quote or a line break
+fn quote(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::csv::parse_records;
+    use crate::{Currency, Date, Transaction, TransactionType};
+
+    fn groups() -> Vec<Group> {
+        let mut market = Group::new(
+            "🛒",
+            [0.0; 4],
+            "Market",
+            Currency::from_code("BRL").unwrap(),
+        );
+        market.new_transaction(Transaction {
+            notes: Some(String::from("Bread, \"fresh\"\nand milk")),
+            ..Transaction::new(
+                "Bakery",
+                TransactionType::EXPENSE,
+                "1234.5".parse().unwrap(),
+                "2022-07-04T09:00:00",
+            )
+        });
+
+        let mut salary = Group::new("💼", [0.0; 4], "Job", Currency::from_code("BRL").unwrap());
+        salary.new_transaction(Transaction::new(
+            "Salary",
+            TransactionType::INCOME,
+            "3000".parse().unwrap(),
+            "2022-06-30T09:00:00",
+        ));
+
+        vec![market, salary]
+    }
+
+    #[test]
+    fn rfc_4180_reads_back() {
+        let csv = write(&groups(), None, CsvDialect::Rfc4180);
+
+        let records = parse_records(&csv, ',').unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].1, COLUMNS);
+        assert_eq!(
+            records[1].1[..6],
+            ["2022-06-30", "Job", "💼", "Salary", "income", "3000.00"]
+        );
+        assert_eq!(records[2].1[5], "-1234.50");
+        assert_eq!(records[2].1[10], "Bread, \"fresh\"\nand milk");
+    }
+
+    #[test]
+    fn locale_dialect_uses_decimal_comma() {
+        let csv = write(&groups(), None, CsvDialect::Locale(NumberFormat::Comma));
+
+        let records = parse_records(&csv, ';').unwrap();
+        assert_eq!(records[2].1[5], "-1234,50");
+        assert!(csv.starts_with("date;group;"));
+    }
+
+    #[test]
+    fn period_limits_transactions() {
+        let july = DateRange::month_of(Date::new(2022, 7, 1).unwrap());
+
+        let csv = write(&groups(), Some(july), CsvDialect::Rfc4180);
+
+        let records = parse_records(&csv, ',').unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].1[3], "Bakery");
+    }
+}
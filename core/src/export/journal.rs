@@ -0,0 +1,153 @@
+//! Double-entry view of groups shared by the plain-text accounting exporters
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::export::transactions_in;
+use crate::{Date, DateRange, Group, Money, Transaction};
+
+/// Accounts transactions are posted to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accounts {
+    /// Parent of the account each group becomes, `Expenses` gives
+    /// `Expenses:Groceries` for a group called Groceries
+    pub group_parent: String,
+    /// Other side of transactions that don't move money between two of the
+    /// exported groups
+    pub balancing: String,
+}
+
+impl Default for Accounts {
+    fn default() -> Self {
+        Self {
+            group_parent: String::from("Expenses"),
+            balancing: String::from("Assets:Cash"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Posting {
+    pub account: String,
+    pub amount: Money,
+    pub currency: String,
+}
+
+/// Transaction with the postings balancing it
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Entry<'a> {
+    pub date: Date,
+    pub transaction: &'a Transaction,
+    pub postings: Vec<Posting>,
+}
+
+/// Entries of the transactions of `groups` within `period`, in the order of
+/// `transactions_in`. `component` turns any text into a valid account name
+/// part for the target syntax.
+///
+/// Expenses are positive on the group account and incomes negative. A
+/// transfer between two exported groups is a single entry from one group
+/// account to the other, written where the money leaves.
+///
+/// Groups whose names give the same account, or the balancing one, are told
+/// apart by the start of their id.
+pub(crate) fn entries<'a>(
+    groups: &'a [Group],
+    period: Option<DateRange>,
+    accounts: &Accounts,
+    component: fn(&str) -> String,
+) -> Vec<Entry<'a>> {
+    let account = |name: &str| name.split(':').map(component).collect::<Vec<_>>().join(":");
+    let parent = account(&accounts.group_parent);
+    let balancing = account(&accounts.balancing);
+
+    let named = |group: &Group| format!("{}:{}", parent, component(&group.name));
+    let mut uses: HashMap<String, usize> = HashMap::new();
+    for group in groups {
+        *uses.entry(named(group)).or_default() += 1;
+    }
+    let group_accounts: HashMap<Uuid, String> = groups
+        .iter()
+        .map(|group| {
+            let account = named(group);
+            if uses[&account] > 1 || account == balancing {
+                (
+                    group.id,
+                    format!("{}-{}", account, &group.id.to_string()[..8]),
+                )
+            } else {
+                (group.id, account)
+            }
+        })
+        .collect();
+    let group_account = |group: &Group| group_accounts[&group.id].clone();
+
+    let mut entries = vec![];
+    for (group, transaction) in transactions_in(groups, period) {
+        let date = match transaction.day() {
+            Some(date) => date,
+            None => continue,
+        };
+
+        let own = Posting {
+            account: group_account(group),
+            amount: -transaction.signed_amount(),
+            currency: group.currency.clone(),
+        };
+
+        let destination = transaction.transfer.and_then(|transfer| {
+            groups
+                .iter()
+                .find(|group| group.id == transfer.group)
+                .map(|other| (transfer, other))
+        });
+        let other = match destination {
+            Some((transfer, _)) if transfer.incoming => continue,
+            Some((transfer, to)) => {
+                let received = to
+                    .transactions
+                    .iter()
+                    .find(|t| t.id == transfer.counterpart)
+                    .map_or(transaction.amount, |t| t.amount);
+
+                Posting {
+                    account: group_account(to),
+                    amount: -received,
+                    currency: to.currency.clone(),
+                }
+            }
+            None => Posting {
+                account: balancing.clone(),
+                amount: transaction.signed_amount(),
+                currency: group.currency.clone(),
+            },
+        };
+
+        entries.push(Entry {
+            date,
+            transaction,
+            postings: vec![own, other],
+        });
+    }
+
+    entries
+}
+
+/// Width of the longest account and amount of `entries`, for aligning postings
+pub(crate) fn column_widths(entries: &[Entry]) -> (usize, usize) {
+    entries
+        .iter()
+        .flat_map(|entry| &entry.postings)
+        .fold((0, 0), |(account, amount), posting| {
+            (
+                account.max(posting.account.chars().count()),
+                amount.max(posting.amount.to_string().len()),
+            )
+        })
+}
+
+/// Text on a single line with runs of whitespace collapsed
+pub(crate) fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,200 @@
+//! JSON export of groups and their transactions.
+//!
+//! Unlike group files, whose layout changes with the schema version, this
+//! document keeps its shape for a given `version`:
+//!
+//! ```json
+//! {
+//!   "format": "beedget-export",
+//!   "version": 1,
+//!   "period": { "start": "2022-07-01", "end": "2022-08-01" },
+//!   "groups": [
+//!     {
+//!       "id": "5f0e…",
+//!       "name": "Market",
+//!       "emoji": "🛒",
+//!       "currency": "BRL",
+//!       "transactions": [
+//!         {
+//!           "id": "9b1c…",
+//!           "date": "2022-07-04",
+//!           "name": "Bakery",
+//!           "type": "expense",
+//!           "amount": "-12.50",
+//!           "tags": ["food"],
+//!           "payee": null,
+//!           "location": null,
+//!           "notes": null
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! - `period` is `null` when every transaction is exported. `start` is the
+//!   first day included and `end` the first day after the period.
+//! - Groups are ordered by name and transactions by date, then name.
+//! - `type` is `expense`, `income` or `transfer`.
+//! - `amount` is a decimal string, negative for expenses and outgoing
+//!   transfers, so no precision is lost to floating point.
+//! - `date` is an ISO 8601 day in local time.
+
+use anyhow::Result;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::export::{transactions_in, type_name};
+use crate::{Date, DateRange, Group, Money};
+
+/// Value of the `format` field
+pub const FORMAT: &str = "beedget-export";
+/// Version of the document described in the module documentation
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+struct Export<'a> {
+    format: &'static str,
+    version: u32,
+    period: Option<Period>,
+    groups: Vec<ExportedGroup<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Period {
+    start: Date,
+    end: Date,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportedGroup<'a> {
+    id: Uuid,
+    name: &'a str,
+    emoji: &'a str,
+    currency: &'a str,
+    transactions: Vec<ExportedTransaction<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportedTransaction<'a> {
+    id: Uuid,
+    date: String,
+    name: &'a str,
+    #[serde(rename = "type")]
+    tr_type: &'static str,
+    amount: Money,
+    tags: &'a [String],
+    payee: Option<&'a str>,
+    location: Option<&'a str>,
+    notes: Option<&'a str>,
+}
+
+/// Transactions of `groups` within `period` as the document described above.
+/// Groups without transactions in the period are left out.
+pub fn write(groups: &[Group], period: Option<DateRange>) -> Result<String> {
+    let mut exported: Vec<ExportedGroup> = vec![];
+
+    for (group, transaction) in transactions_in(groups, period) {
+        let transaction = ExportedTransaction {
+            id: transaction.id,
+            date: transaction
+                .day()
+                .map(|day| day.to_string())
+                .unwrap_or_else(|| transaction.date.clone()),
+            name: &transaction.name,
+            tr_type: type_name(transaction.tr_type),
+            amount: transaction.signed_amount(),
+            tags: &transaction.tags,
+            payee: transaction.payee.as_deref(),
+            location: transaction.location.as_deref(),
+            notes: transaction.notes.as_deref(),
+        };
+
+        match exported.iter_mut().find(|exported| exported.id == group.id) {
+            Some(exported) => exported.transactions.push(transaction),
+            None => exported.push(ExportedGroup {
+                id: group.id,
+                name: &group.name,
+                emoji: &group.emoji,
+                currency: &group.currency,
+                transactions: vec![transaction],
+            }),
+        }
+    }
+    exported.sort_by(|a, b| a.name.cmp(b.name).then_with(|| a.id.cmp(&b.id)));
+
+    let export = Export {
+        format: FORMAT,
+        version: VERSION,
+        period: period.map(|period| Period {
+            start: period.start,
+            end: period.end,
+        }),
+        groups: exported,
+    };
+
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Currency, Transaction, TransactionType};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn document_has_documented_shape() {
+        let mut group = Group::new(
+            "🛒",
+            [0.0; 4],
+            "Market",
+            Currency::from_code("BRL").unwrap(),
+        );
+        let bakery = Transaction {
+            tags: vec![String::from("food")],
+            ..Transaction::new(
+                "Bakery",
+                TransactionType::EXPENSE,
+                "12.5".parse().unwrap(),
+                "2022-07-04T09:00:00",
+            )
+        };
+        group.new_transaction(bakery.clone());
+        group.new_transaction(Transaction::new(
+            "Old",
+            TransactionType::EXPENSE,
+            "1".parse().unwrap(),
+            "2022-06-04T09:00:00",
+        ));
+        let july = DateRange::month_of(Date::new(2022, 7, 1).unwrap());
+
+        let document: Value =
+            serde_json::from_str(&write(&[group.clone()], Some(july)).unwrap()).unwrap();
+
+        assert_eq!(
+            document,
+            json!({
+                "format": "beedget-export",
+                "version": 1,
+                "period": { "start": "2022-07-01", "end": "2022-08-01" },
+                "groups": [{
+                    "id": group.id,
+                    "name": "Market",
+                    "emoji": "🛒",
+                    "currency": "BRL",
+                    "transactions": [{
+                        "id": bakery.id,
+                        "date": "2022-07-04",
+                        "name": "Bakery",
+                        "type": "expense",
+                        "amount": "-12.50",
+                        "tags": ["food"],
+                        "payee": null,
+                        "location": null,
+                        "notes": null,
+                    }],
+                }],
+            })
+        );
+    }
+}
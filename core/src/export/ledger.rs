@@ -0,0 +1,175 @@
+use std::fmt::Write;
+
+use crate::export::journal::{column_widths, entries, single_line, Accounts};
+use crate::{DateRange, Group};
+
+/// Ledger and hledger read the same journal but write tags differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerDialect {
+    /// `; :food:travel:`
+    Ledger,
+    /// `; food:, travel:`
+    Hledger,
+}
+
+/// Transactions of `groups` within `period` as a ledger journal, oldest
+/// first. Notes and tags become comments. The same data always gives the
+/// same journal.
+pub fn write(
+    groups: &[Group],
+    period: Option<DateRange>,
+    accounts: &Accounts,
+    dialect: LedgerDialect,
+) -> String {
+    let entries = entries(groups, period, accounts, account_component);
+    let (account_width, amount_width) = column_widths(&entries);
+
+    let mut journal = String::new();
+    for entry in &entries {
+        let transaction = entry.transaction;
+        let _ = writeln!(journal, "{} {}", entry.date, single_line(&transaction.name));
+
+        if let Some(notes) = &transaction.notes {
+            for line in notes.lines().filter(|line| !line.trim().is_empty()) {
+                let _ = writeln!(journal, "    ; {}", line.trim());
+            }
+        }
+
+        if !transaction.tags.is_empty() {
+            let tags: Vec<String> = transaction
+                .tags
+                .iter()
+                .map(|tag| tag.replace(':', "-"))
+                .collect();
+            let _ = match dialect {
+                LedgerDialect::Ledger => writeln!(journal, "    ; :{}:", tags.join(":")),
+                LedgerDialect::Hledger => writeln!(journal, "    ; {}:", tags.join(":, ")),
+            };
+        }
+
+        for posting in &entry.postings {
+            let _ = writeln!(
+                journal,
+                "    {:<account_width$}  {:>amount_width$} {}",
+                posting.account,
+                posting.amount.to_string(),
+                posting.currency,
+            );
+        }
+        journal.push('\n');
+    }
+
+    journal
+}
+
+/// Part of an account name. Colons separate parts and two spaces end the
+/// name, so neither may appear.
+fn account_component(name: &str) -> String {
+    let component = single_line(&name.replace(':', "-"));
+
+    if component.is_empty() {
+        String::from("Unnamed")
+    } else {
+        component
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transfer::transfer_pair;
+    use crate::{Currency, Transaction, TransactionType};
+
+    fn groups() -> Vec<Group> {
+        let mut market = Group::new(
+            "🛒",
+            [0.0; 4],
+            "Market: food",
+            Currency::from_code("BRL").unwrap(),
+        );
+        market.new_transaction(Transaction {
+            tags: vec![String::from("food"), String::from("trip")],
+            notes: Some(String::from("Croissants\nand coffee")),
+            ..Transaction::new(
+                "Bakery",
+                TransactionType::EXPENSE,
+                "12.5".parse().unwrap(),
+                "2022-07-04T09:00:00",
+            )
+        });
+        market.new_transaction(Transaction::new(
+            "Refund",
+            TransactionType::INCOME,
+            "2".parse().unwrap(),
+            "2022-07-05T09:00:00",
+        ));
+
+        let savings = Group::new(
+            "💰",
+            [0.0; 4],
+            "Savings",
+            Currency::from_code("BRL").unwrap(),
+        );
+        let (outgoing, incoming) = transfer_pair(
+            "Save",
+            "100".parse().unwrap(),
+            "100".parse().unwrap(),
+            "2022-07-06T09:00:00",
+            market.id,
+            savings.id,
+        );
+        market.new_transaction(outgoing);
+        let mut savings = savings;
+        savings.new_transaction(incoming);
+
+        vec![savings, market]
+    }
+
+    #[test]
+    fn writes_ledger_journal() {
+        let journal = write(&groups(), None, &Accounts::default(), LedgerDialect::Ledger);
+
+        assert_eq!(
+            journal,
+            "2022-07-04 Bakery\n\
+             \x20   ; Croissants\n\
+             \x20   ; and coffee\n\
+             \x20   ; :food:trip:\n\
+             \x20   Expenses:Market- food    12.50 BRL\n\
+             \x20   Assets:Cash             -12.50 BRL\n\
+             \n\
+             2022-07-05 Refund\n\
+             \x20   Expenses:Market- food    -2.00 BRL\n\
+             \x20   Assets:Cash               2.00 BRL\n\
+             \n\
+             2022-07-06 Save\n\
+             \x20   Expenses:Market- food   100.00 BRL\n\
+             \x20   Expenses:Savings       -100.00 BRL\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn hledger_tags_and_stable_output() {
+        let mut reversed = groups();
+        reversed.reverse();
+
+        let journal = write(
+            &groups(),
+            None,
+            &Accounts::default(),
+            LedgerDialect::Hledger,
+        );
+
+        assert!(journal.contains("    ; food:, trip:\n"));
+        assert_eq!(
+            journal,
+            write(
+                &reversed,
+                None,
+                &Accounts::default(),
+                LedgerDialect::Hledger
+            )
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Writing groups to files read by other tools.
+
+pub mod beancount;
+pub mod csv;
+mod journal;
+pub mod json;
+pub mod ledger;
+pub mod qif;
+
+pub use self::journal::Accounts;
+
+use crate::{DateRange, Group, Transaction, TransactionType};
+
+/// Transactions of `groups` dated within `period`, or all of them, with their
+/// group. Ordered by date, then group and transaction name, so exporting the
+/// same data twice gives the same file.
+pub fn transactions_in(groups: &[Group], period: Option<DateRange>) -> Vec<(&Group, &Transaction)> {
+    let mut transactions: Vec<(&Group, &Transaction)> = groups
+        .iter()
+        .flat_map(|group| group.transactions.iter().map(move |t| (group, t)))
+        .filter(|(_, transaction)| match period {
+            Some(period) => transaction.day().is_some_and(|day| period.contains(day)),
+            None => true,
+        })
+        .collect();
+
+    transactions.sort_by(|(a_group, a), (b_group, b)| {
+        a.date
+            .cmp(&b.date)
+            .then_with(|| a_group.name.cmp(&b_group.name))
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    transactions
+}
+
+/// Lowercase name of a transaction type, as written to exported files
+pub fn type_name(tr_type: TransactionType) -> &'static str {
+    match tr_type {
+        TransactionType::EXPENSE => "expense",
+        TransactionType::INCOME => "income",
+        TransactionType::TRANSFER => "transfer",
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt::Write;
+
+use crate::{Group, Transaction};
+
+/// Group as a QIF bank account, oldest transaction first.
+///
+/// Dates are written month first with a four-digit year, which is what most
+/// programs expect. The first tag of a transaction becomes its category.
+pub fn write(group: &Group) -> String {
+    let mut transactions: Vec<&Transaction> = group.transactions.iter().collect();
+    transactions.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+
+    let mut qif = String::new();
+    let _ = writeln!(
+        qif,
+        "!Account\nN{}\nTBank\n^\n!Type:Bank",
+        single_line(&group.name)
+    );
+
+    for transaction in transactions {
+        let day = match transaction.day() {
+            Some(day) => day,
+            None => continue,
+        };
+
+        let _ = writeln!(
+            qif,
+            "D{:02}/{:02}/{:04}\nT{}\nP{}",
+            day.month(),
+            day.day(),
+            day.year(),
+            transaction.signed_amount(),
+            single_line(&transaction.name)
+        );
+        if let Some(notes) = &transaction.notes {
+            let _ = writeln!(qif, "M{}", single_line(notes));
+        }
+        if let Some(tag) = transaction.tags.first() {
+            let _ = writeln!(qif, "L{}", tag);
+        }
+        qif.push_str("^\n");
+    }
+
+    qif
+}
+
+/// QIF fields end at the line break
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::qif;
+    use crate::import::{DateFormat, DecimalSeparator};
+    use crate::{Currency, TransactionType};
+
+    #[test]
+    fn exported_group_reads_back() {
+        let mut group = Group::new(
+            "🛒",
+            [0.0; 4],
+            "Groceries",
+            Currency::from_code("USD").unwrap(),
+        );
+        group.new_transaction(Transaction {
+            tags: vec![String::from("weekly")],
+            notes: Some(String::from("Milk\nand eggs")),
+            ..Transaction::new(
+                "Market",
+                TransactionType::EXPENSE,
+                "23.40".parse().unwrap(),
+                "2022-07-09T10:00:00",
+            )
+        });
+        group.new_transaction(Transaction::new(
+            "Refund",
+            TransactionType::INCOME,
+            "5".parse().unwrap(),
+            "2022-07-02T10:00:00",
+        ));
+
+        let text = write(&group);
+
+        assert_eq!(
+            text,
+            "!Account\nNGroceries\nTBank\n^\n!Type:Bank\n\
+             D07/02/2022\nT5.00\nPRefund\n^\n\
+             D07/09/2022\nT-23.40\nPMarket\nMMilk and eggs\nLweekly\n^\n"
+        );
+
+        let entries = qif::parse(&text, DateFormat::MonthDayYear, DecimalSeparator::Point).unwrap();
+        let market = entries[1].1.as_ref().unwrap().clone().tagged();
+        assert_eq!(market.name, "Market");
+        assert_eq!(market.signed_amount(), "-23.40".parse().unwrap());
+        assert_eq!(market.tags, vec![String::from("weekly")]);
+    }
+}
@@ -0,0 +1,543 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::migrations::{self, Migration, GROUP_MIGRATIONS, GROUP_SCHEMA_VERSION};
+use crate::{
+    Budget, BudgetStatus, Currency, DataObject, Date, DateRange, Money, RecurringTransaction,
+    Rollover, Transaction, TransactionType, Weekday,
+};
+
+/// Named collection of transactions sharing one currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub schema_version: u32,
+    pub id: Uuid,
+    pub name: String,
+    pub emoji: String,
+    /// RGBA components between 0 and 1
+    pub color: Vec<f32>,
+    pub currency: String,
+    /// Optional spending limit, `null` when the group has none
+    pub budget: Option<Budget>,
+    /// Rules that add transactions to this group on a schedule
+    pub recurring: Vec<RecurringTransaction>,
+    pub transactions: Vec<Transaction>,
+}
+
+/// Totals of a group over some period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub income: Money,
+    /// Sum of expense amounts, as a positive value
+    pub expenses: Money,
+    /// Balance at the end of the period, counting everything before it
+    pub balance: Money,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            schema_version: GROUP_SCHEMA_VERSION,
+            id: Uuid::nil(),
+            name: String::new(),
+            emoji: String::new(),
+            color: vec![],
+            currency: String::new(),
+            budget: None,
+            recurring: vec![],
+            transactions: vec![],
+        }
+    }
+}
+
+impl DataObject for Group {
+    fn migrations() -> &'static [Migration] {
+        GROUP_MIGRATIONS
+    }
+
+    fn filename(&self) -> String {
+        self.id.to_string() + &String::from(".json")
+    }
+}
+
+impl Group {
+    pub fn new(emoji: &str, color: [f32; 4], name: &str, currency: &Currency) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            emoji: emoji.to_string(),
+            color: color.to_vec(),
+            currency: currency.code.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Build group from its JSON document, upgrading older schemas
+    pub fn from_document(mut doc: Value) -> Result<Self> {
+        migrations::upgrade(&mut doc, GROUP_MIGRATIONS)?;
+
+        Ok(serde_json::from_value(doc)?)
+    }
+
+    /// Group and its transactions as a JSON document in the current schema
+    pub fn to_document(&self) -> Result<Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    pub fn currency(&self) -> &'static Currency {
+        Currency::from_code_or_default(&self.currency)
+    }
+
+    /// Sum of all signed transaction amounts, in the group's currency
+    pub fn balance(&self) -> Money {
+        self.transactions
+            .iter()
+            .map(|transaction| transaction.signed_amount())
+            .sum()
+    }
+
+    /// Spending against the budget in the period containing `date`
+    pub fn budget_status(&self, date: Date, first_day: Weekday) -> Option<BudgetStatus> {
+        let budget = self.budget?;
+        let range = budget.period.range_containing(date, first_day);
+
+        Some(BudgetStatus {
+            range,
+            limit: budget.limit,
+            carried: self.carried_into(&budget, range, first_day),
+            spent: self.summary(Some(range)).expenses,
+        })
+    }
+
+    /// Amount carried over into `range` by replaying every earlier period.
+    ///
+    /// Counting starts at the first transaction, or when the budget was set
+    /// up if that is later.
+    fn carried_into(&self, budget: &Budget, range: DateRange, first_day: Weekday) -> Money {
+        if budget.rollover == Rollover::Reset {
+            return Money::ZERO;
+        }
+
+        let first_day_used = self.transactions.iter().filter_map(Transaction::day).min();
+        let start = match (first_day_used, budget.since) {
+            (Some(first), Some(since)) => first.max(since),
+            (Some(first), None) => first,
+            (None, _) => return Money::ZERO,
+        };
+
+        let mut carried = Money::ZERO;
+        let mut period = budget.period.range_containing(start, first_day);
+        while period.start < range.start {
+            let spent = self.summary(Some(period)).expenses;
+            carried = budget.rollover.carry(budget.limit + carried - spent);
+
+            period = budget.period.range_containing(period.end, first_day);
+        }
+
+        carried
+    }
+
+    /// Whether the group spent more than its limit for the period containing `today`.
+    ///
+    /// Groups without a budget are never over it.
+    pub fn is_over_budget(&self, today: Date, first_day: Weekday) -> bool {
+        self.budget_status(today, first_day)
+            .is_some_and(|status| status.is_over())
+    }
+
+    /// Income and expenses within `period` and the balance at its end.
+    ///
+    /// Without a period every transaction is counted. Transfers are left out
+    /// of income and expenses.
+    pub fn summary(&self, period: Option<DateRange>) -> Summary {
+        let mut summary = Summary::default();
+
+        for transaction in &self.transactions {
+            let day = transaction.day();
+            if let (Some(period), Some(day)) = (period, day) {
+                if day >= period.end {
+                    continue;
+                }
+
+                summary.balance += transaction.signed_amount();
+                if day < period.start {
+                    continue;
+                }
+            } else {
+                summary.balance += transaction.signed_amount();
+            }
+
+            match transaction.tr_type {
+                TransactionType::INCOME => summary.income += transaction.amount,
+                TransactionType::EXPENSE => summary.expenses += transaction.amount,
+                // Only moves money around, so it changes the balance alone
+                TransactionType::TRANSFER => {}
+            }
+        }
+
+        summary
+    }
+
+    /// Balance right after each transaction, in chronological order.
+    ///
+    /// Transactions at the same time keep the order they were added in.
+    pub fn running_balances(&self) -> Vec<(Uuid, Money)> {
+        let mut transactions: Vec<&Transaction> = self.transactions.iter().collect();
+        // Dates are local time, so the text up to the seconds sorts correctly
+        transactions.sort_by_key(|transaction| transaction.date.get(..19).unwrap_or(""));
+
+        let mut balance = Money::ZERO;
+        transactions
+            .into_iter()
+            .map(|transaction| {
+                balance += transaction.signed_amount();
+                (transaction.id, balance)
+            })
+            .collect()
+    }
+
+    /// Transactions of every recurring rule that came due up to `today`.
+    ///
+    /// Rules are updated so the same occurrence is never returned twice;
+    /// adding the transactions to the group is up to the caller.
+    pub fn take_due_recurring(&mut self, today: Date) -> Vec<Transaction> {
+        self.recurring
+            .iter_mut()
+            .flat_map(|rule| rule.take_due(today))
+            .collect()
+    }
+
+    pub fn transaction(&self, transaction_id: Uuid) -> Option<&Transaction> {
+        self.transactions
+            .iter()
+            .find(|transaction| transaction.id == transaction_id)
+    }
+
+    pub fn new_transaction(&mut self, transaction: Transaction) {
+        self.transactions.push(transaction);
+    }
+
+    /// Remove transaction, returning it if it was part of the group
+    pub fn delete_transaction(&mut self, transaction_id: Uuid) -> Option<Transaction> {
+        let idx = self
+            .transactions
+            .iter()
+            .position(|transaction| transaction.id == transaction_id)?;
+
+        Some(self.transactions.remove(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::currency::DEFAULT_CURRENCY;
+    use crate::test_util::{group, transaction};
+    use crate::{BudgetPeriod, Frequency, RecurrenceEnd};
+
+    fn today() -> Date {
+        Date::new(2022, 7, 4).unwrap()
+    }
+
+    fn dated(tr_type: TransactionType, amount: &str, date: &str) -> Transaction {
+        Transaction {
+            date: date.to_string(),
+            ..transaction(tr_type, amount)
+        }
+    }
+
+    fn transaction_ids(group: &Group) -> Vec<Uuid> {
+        group.transactions.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn new_transaction_is_added() {
+        let mut group = group("Wallet");
+
+        let expense = transaction(TransactionType::EXPENSE, "12.50");
+        let income = transaction(TransactionType::INCOME, "100");
+        group.new_transaction(expense.clone());
+        group.new_transaction(income.clone());
+
+        assert_eq!(transaction_ids(&group), vec![expense.id, income.id]);
+        assert_eq!(group.balance(), "87.50".parse().unwrap());
+    }
+
+    #[test]
+    fn delete_transaction_removes_only_that_transaction() {
+        let mut group = group("Wallet");
+
+        let first = transaction(TransactionType::EXPENSE, "1");
+        let second = transaction(TransactionType::EXPENSE, "2");
+        let third = transaction(TransactionType::EXPENSE, "3");
+        group.new_transaction(first.clone());
+        group.new_transaction(second.clone());
+        group.new_transaction(third.clone());
+
+        assert_eq!(group.delete_transaction(second.id), Some(second));
+        assert_eq!(group.delete_transaction(Uuid::new_v4()), None);
+
+        assert_eq!(transaction_ids(&group), vec![first.id, third.id]);
+        assert_eq!(group.balance(), "-4".parse().unwrap());
+    }
+
+    #[test]
+    fn transfers_only_change_balance() {
+        let mut savings = group("Savings");
+        let mut vacation = group("Vacation");
+        savings.new_transaction(transaction(TransactionType::INCOME, "1000"));
+
+        let (outgoing, incoming) = crate::transfer::transfer_pair(
+            "Trip",
+            "300".parse().unwrap(),
+            "300".parse().unwrap(),
+            "2022-07-04T12:30:00Z",
+            savings.id,
+            vacation.id,
+        );
+        savings.new_transaction(outgoing);
+        vacation.new_transaction(incoming);
+
+        assert_eq!(savings.balance(), "700".parse().unwrap());
+        assert_eq!(vacation.balance(), "300".parse().unwrap());
+
+        let summary = savings.summary(None);
+        assert_eq!(summary.income, "1000".parse().unwrap());
+        assert_eq!(summary.expenses, Money::ZERO);
+        assert_eq!(vacation.summary(None).income, Money::ZERO);
+    }
+
+    #[test]
+    fn summary_counts_only_the_period() {
+        let mut group = group("Wallet");
+        group.new_transaction(dated(
+            TransactionType::INCOME,
+            "100",
+            "2022-06-30T10:00:00-03",
+        ));
+        group.new_transaction(dated(
+            TransactionType::EXPENSE,
+            "30",
+            "2022-07-01T10:00:00-03",
+        ));
+        group.new_transaction(dated(
+            TransactionType::INCOME,
+            "50",
+            "2022-07-31T23:59:00-03",
+        ));
+        group.new_transaction(dated(
+            TransactionType::EXPENSE,
+            "5",
+            "2022-08-01T00:00:00-03",
+        ));
+
+        let july = DateRange::month_of(crate::Date::new(2022, 7, 1).unwrap());
+        let summary = group.summary(Some(july));
+
+        assert_eq!(summary.income, "50".parse().unwrap());
+        assert_eq!(summary.expenses, "30".parse().unwrap());
+        assert_eq!(summary.balance, "120".parse().unwrap());
+
+        let all = group.summary(None);
+        assert_eq!(all.income, "150".parse().unwrap());
+        assert_eq!(all.expenses, "35".parse().unwrap());
+        assert_eq!(all.balance, group.balance());
+    }
+
+    #[test]
+    fn running_balances_are_chronological() {
+        let mut group = group("Wallet");
+        let late = dated(TransactionType::EXPENSE, "30", "2022-07-02T10:00:00-03");
+        let early = dated(TransactionType::INCOME, "100", "2022-07-01T10:00:00-03");
+        let same_time = dated(TransactionType::EXPENSE, "10", "2022-07-02T10:00:00-03");
+        group.new_transaction(late.clone());
+        group.new_transaction(early.clone());
+        group.new_transaction(same_time.clone());
+
+        assert_eq!(
+            group.running_balances(),
+            vec![
+                (early.id, "100".parse().unwrap()),
+                (late.id, "70".parse().unwrap()),
+                (same_time.id, "60".parse().unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn negative_balance_without_budget_is_not_over_budget() {
+        let mut group = group("Wallet");
+        group.new_transaction(transaction(TransactionType::EXPENSE, "10"));
+
+        assert!(group.balance().is_negative());
+        assert!(!group.is_over_budget(today(), Weekday::Monday));
+
+        group.budget = Some(Budget {
+            limit: "5".parse().unwrap(),
+            period: BudgetPeriod::Monthly,
+            ..Default::default()
+        });
+        assert!(group.is_over_budget(today(), Weekday::Monday));
+    }
+
+    #[test]
+    fn budget_counts_expenses_of_current_period() {
+        let mut group = group("Groceries");
+        group.budget = Some(Budget {
+            limit: "100".parse().unwrap(),
+            period: BudgetPeriod::Monthly,
+            ..Default::default()
+        });
+        group.new_transaction(dated(
+            TransactionType::INCOME,
+            "500",
+            "2022-07-01T10:00:00-03",
+        ));
+        group.new_transaction(dated(
+            TransactionType::EXPENSE,
+            "80",
+            "2022-06-30T10:00:00-03",
+        ));
+        group.new_transaction(dated(
+            TransactionType::EXPENSE,
+            "60",
+            "2022-07-02T10:00:00-03",
+        ));
+
+        let status = group.budget_status(today(), Weekday::Monday).unwrap();
+        assert_eq!(status.spent, "60".parse().unwrap());
+        assert_eq!(status.remaining(), "40".parse().unwrap());
+        assert!(!group.is_over_budget(today(), Weekday::Monday));
+
+        group.new_transaction(dated(
+            TransactionType::EXPENSE,
+            "50",
+            "2022-07-03T10:00:00-03",
+        ));
+        assert!(group.is_over_budget(today(), Weekday::Monday));
+    }
+
+    #[test]
+    fn rollover_replays_earlier_periods() {
+        let mut group = group("Groceries");
+        group.budget = Some(Budget {
+            limit: "100".parse().unwrap(),
+            period: BudgetPeriod::Monthly,
+            rollover: Rollover::Both,
+            since: None,
+        });
+        // May leaves 40, June overspends by 70
+        group.new_transaction(dated(
+            TransactionType::EXPENSE,
+            "60",
+            "2022-05-10T10:00:00-03",
+        ));
+        group.new_transaction(dated(
+            TransactionType::EXPENSE,
+            "170",
+            "2022-06-10T10:00:00-03",
+        ));
+        group.new_transaction(dated(
+            TransactionType::EXPENSE,
+            "10",
+            "2022-07-02T10:00:00-03",
+        ));
+
+        let status = group.budget_status(today(), Weekday::Monday).unwrap();
+        assert_eq!(status.carried, "-30".parse().unwrap());
+        assert_eq!(status.remaining(), "60".parse().unwrap());
+
+        group.budget.as_mut().unwrap().rollover = Rollover::Surplus;
+        let status = group.budget_status(today(), Weekday::Monday).unwrap();
+        assert_eq!(status.carried, Money::ZERO);
+
+        // Periods before the budget was set up are not counted
+        group.budget.as_mut().unwrap().rollover = Rollover::Both;
+        group.budget.as_mut().unwrap().since = Some(Date::new(2022, 6, 1).unwrap());
+        let status = group.budget_status(today(), Weekday::Monday).unwrap();
+        assert_eq!(status.carried, "-70".parse().unwrap());
+    }
+
+    #[test]
+    fn due_recurring_transactions_are_taken_once() {
+        let mut group = group("Home");
+        let rent = dated(TransactionType::EXPENSE, "1200", "2022-05-05T09:00:00-03");
+        group.recurring.push(RecurringTransaction::from_transaction(
+            &rent,
+            Frequency::Monthly,
+            1,
+            RecurrenceEnd::Never,
+        ));
+        group.new_transaction(rent);
+
+        let due = group.take_due_recurring(today());
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].date, "2022-06-05T09:00:00-03");
+
+        for transaction in due {
+            group.new_transaction(transaction);
+        }
+        assert!(group.take_due_recurring(today()).is_empty());
+        assert_eq!(group.balance(), "-2400".parse().unwrap());
+    }
+
+    #[test]
+    fn document_round_trip() {
+        let mut original = group("Wallet");
+        original.budget = Some(Budget {
+            limit: "250".parse().unwrap(),
+            period: BudgetPeriod::Weekly,
+            rollover: Rollover::Both,
+            since: Some(today()),
+        });
+        original.new_transaction(transaction(TransactionType::EXPENSE, "0.1"));
+        original.new_transaction(transaction(TransactionType::INCOME, "1234.5678"));
+
+        let doc = original.to_document().unwrap();
+        let restored = Group::from_document(doc.clone()).unwrap();
+
+        assert_eq!(restored, original);
+        assert_eq!(restored.currency().code, "BRL");
+        assert_eq!(restored.to_document().unwrap(), doc);
+    }
+
+    #[test]
+    fn old_documents_are_upgraded() {
+        let id = Uuid::new_v4();
+        let doc = serde_json::json!({
+            "id": id,
+            "name": "Wallet",
+            "emoji": "💰",
+            "color": [0.2, 0.4, 0.6, 1.0],
+            "transactions": [{
+                "id": Uuid::new_v4(),
+                "name": "Groceries",
+                "tr_type": "EXPENSE",
+                "amount": 12.5,
+                "date": "2022-07-04T12:30:00Z"
+            }]
+        });
+
+        let group = Group::from_document(doc).unwrap();
+
+        assert_eq!(group.id, id);
+        assert_eq!(group.currency().code, DEFAULT_CURRENCY);
+        assert_eq!(group.balance(), "-12.50".parse().unwrap());
+        assert_eq!(group.budget, None);
+        assert_eq!(group.transactions[0].installment, None);
+        assert!(group.transactions[0].tags.is_empty());
+        assert_eq!(group.transactions[0].payee, None);
+        assert_eq!(group.schema_version, GROUP_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn newer_documents_are_rejected() {
+        let mut doc = group("Wallet").to_document().unwrap();
+        doc["schema_version"] = (GROUP_SCHEMA_VERSION + 1).into();
+
+        assert!(Group::from_document(doc).is_err());
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::noon;
+use crate::data_object::write_atomic;
+use crate::{Date, Money, NumberFormat, Transaction, TransactionType};
+
+/// Name of the file keeping CSV mappings, stored next to the `groups` directory
+pub const IMPORT_PROFILES_FILE: &str = "import-profiles.json";
+
+/// How amounts tell expenses and incomes apart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignConvention {
+    /// Negative amounts are expenses, as in most bank statements
+    #[default]
+    NegativeIsExpense,
+    /// Positive amounts are expenses, as in most credit card statements
+    PositiveIsExpense,
+    /// Amounts are unsigned and the type column marks expenses
+    TypeColumn,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DateFormat {
+    /// `2022-07-04`
+    #[default]
+    YearMonthDay,
+    /// `04/07/2022`
+    DayMonthYear,
+    /// `07/04/2022`
+    MonthDayYear,
+}
+
+impl DateFormat {
+    /// Parse `text` in this format. Parts may be separated by `-`, `/`, `.`
+    /// or `'`, and two-digit years are taken as 20xx. Anything after the date,
+    /// such as a time, is ignored.
+    pub fn parse(&self, text: &str) -> Result<Date> {
+        let invalid = || anyhow!("Invalid date \"{}\"", text);
+
+        let date = text.trim().split([' ', 'T']).next().unwrap_or_default();
+        let parts: Vec<i32> = date
+            .split(['-', '/', '.', '\''])
+            .map(|part| part.parse().map_err(|_| invalid()))
+            .collect::<Result<_>>()?;
+        if parts.len() != 3 {
+            return Err(invalid());
+        }
+
+        let (year, month, day) = match self {
+            DateFormat::YearMonthDay => (parts[0], parts[1], parts[2]),
+            DateFormat::DayMonthYear => (parts[2], parts[1], parts[0]),
+            DateFormat::MonthDayYear => (parts[2], parts[0], parts[1]),
+        };
+        let year = if year < 100 { year + 2000 } else { year };
+
+        Date::new(year, month as u32, day as u32).map_err(|_| invalid())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecimalSeparator {
+    /// `1,234.56`
+    #[default]
+    Point,
+    /// `1.234,56`
+    Comma,
+}
+
+impl DecimalSeparator {
+    /// Parse a signed amount. Thousands separators, currency symbols and
+    /// spaces are skipped, and amounts in parentheses are negative.
+    pub fn parse(&self, text: &str) -> Result<Money> {
+        let text = text.trim();
+        let (negative, text) = match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+            Some(inner) => (true, inner),
+            None => (false, text),
+        };
+
+        let decimal = match self {
+            DecimalSeparator::Point => '.',
+            DecimalSeparator::Comma => ',',
+        };
+        let number: String = text
+            .chars()
+            .filter_map(|c| match c {
+                '0'..='9' | '-' | '+' => Some(c),
+                c if c == decimal => Some('.'),
+                _ => None,
+            })
+            .collect();
+
+        let amount = Money::parse_with(&number, NumberFormat::Point)
+            .map_err(|_| anyhow!("Invalid amount \"{}\"", text))?;
+
+        Ok(if negative { -amount.abs() } else { amount })
+    }
+}
+
+/// Which columns hold which values and how to read them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvMapping {
+    pub delimiter: char,
+    /// Whether the first record holds column names instead of a transaction
+    pub has_header: bool,
+    pub name: usize,
+    pub amount: usize,
+    pub date: usize,
+    /// Column with the transaction type, only read with `SignConvention::TypeColumn`
+    pub tr_type: Option<usize>,
+    /// Value of the type column marking expenses, compared ignoring case
+    pub expense_marker: String,
+    pub sign: SignConvention,
+    pub date_format: DateFormat,
+    pub decimal_separator: DecimalSeparator,
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            has_header: true,
+            name: 1,
+            amount: 2,
+            date: 0,
+            tr_type: None,
+            expense_marker: String::from("D"),
+            sign: SignConvention::default(),
+            date_format: DateFormat::default(),
+            decimal_separator: DecimalSeparator::default(),
+        }
+    }
+}
+
+impl CsvMapping {
+    /// Transaction of one record, dated at noon local time
+    pub fn transaction(&self, record: &[String]) -> Result<Transaction> {
+        let field = |column: usize| {
+            record
+                .get(column)
+                .map(|value| value.trim())
+                .ok_or_else(|| anyhow!("Missing column {}", column + 1))
+        };
+
+        let name = field(self.name)?;
+        if name.is_empty() {
+            bail!("Empty name");
+        }
+
+        let date = self.date_format.parse(field(self.date)?)?;
+        let amount = self.decimal_separator.parse(field(self.amount)?)?;
+
+        let is_expense = match self.sign {
+            SignConvention::NegativeIsExpense => amount.is_negative(),
+            SignConvention::PositiveIsExpense => amount.is_positive(),
+            SignConvention::TypeColumn => {
+                let column = self
+                    .tr_type
+                    .ok_or_else(|| anyhow!("No type column selected"))?;
+                field(column)?.eq_ignore_ascii_case(self.expense_marker.trim())
+            }
+        };
+        let tr_type = if is_expense {
+            TransactionType::EXPENSE
+        } else {
+            TransactionType::INCOME
+        };
+
+        Ok(Transaction::new(name, tr_type, amount.abs(), &noon(date)))
+    }
+
+    /// Transactions of every record of `text` after the header, with the
+    /// line each one starts at. Records that cannot be read keep their error.
+    pub fn read(&self, text: &str) -> Result<Vec<(usize, Result<Transaction>)>> {
+        let records = parse_records(text, self.delimiter)?;
+        let skip = usize::from(self.has_header);
+
+        Ok(records
+            .into_iter()
+            .skip(skip)
+            .filter(|(_, record)| record.iter().any(|field| !field.trim().is_empty()))
+            .map(|(line, record)| (line, self.transaction(&record)))
+            .collect())
+    }
+}
+
+/// Most common of `,`, `;` and tab in the first line of `text`
+pub fn detect_delimiter(text: &str) -> char {
+    let first_line = text.lines().next().unwrap_or_default();
+
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|delimiter| first_line.matches(*delimiter).count())
+        .unwrap_or(',')
+}
+
+/// Records of RFC 4180 `text`, with the line each one starts at.
+///
+/// Fields may be quoted with `"`, quoted fields may contain delimiters,
+/// line breaks and doubled quotes.
+pub fn parse_records(text: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut record)));
+                line += 1;
+                record_line = line;
+            }
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        bail!(
+            "Quoted field starting on line {} is never closed",
+            record_line
+        );
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+
+    Ok(records)
+}
+
+/// Mappings remembered for each bank, by profile name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportProfiles {
+    pub profiles: BTreeMap<String, CsvMapping>,
+}
+
+impl ImportProfiles {
+    /// Load profiles from save directory, empty if none were saved yet
+    pub fn load(base_path: &Path) -> Result<Self> {
+        match File::open(base_path.join(IMPORT_PROFILES_FILE)) {
+            Ok(mut file) => {
+                let mut fc = String::new();
+                file.read_to_string(&mut fc)?;
+
+                Ok(serde_json::from_str(&fc)?)
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save(&self, base_path: &Path) -> Result<()> {
+        fs::create_dir_all(base_path)?;
+        let serialized = serde_json::to_string_pretty(self)?;
+        write_atomic(&base_path.join(IMPORT_PROFILES_FILE), serialized.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::money;
+
+    #[test]
+    fn quoted_fields_keep_delimiters_and_quotes() {
+        let text = "Date,Description,Amount\r\n\
+                    2022-07-04,\"Bakery, \"\"Sweet\"\" corner\",-12.50\r\n\
+                    2022-07-05,\"Two\nlines\",3\r\n";
+
+        let records = parse_records(text, ',').unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].1[1], "Bakery, \"Sweet\" corner");
+        assert_eq!(
+            records[2],
+            (
+                3,
+                vec![
+                    String::from("2022-07-05"),
+                    String::from("Two\nlines"),
+                    String::from("3"),
+                ]
+            )
+        );
+        assert!(parse_records("a,\"b\n", ',').is_err());
+    }
+
+    #[test]
+    fn amounts_follow_decimal_separator() {
+        assert_eq!(
+            DecimalSeparator::Point.parse("1,234.56").unwrap(),
+            money("1234.56")
+        );
+        assert_eq!(
+            DecimalSeparator::Comma.parse("-1.234,56").unwrap(),
+            money("-1234.56")
+        );
+        assert_eq!(
+            DecimalSeparator::Comma.parse("R$ 10,00").unwrap(),
+            money("10")
+        );
+        assert_eq!(
+            DecimalSeparator::Point.parse("(12.50)").unwrap(),
+            money("-12.50")
+        );
+        assert!(DecimalSeparator::Point.parse("abc").is_err());
+    }
+
+    #[test]
+    fn dates_follow_format() {
+        let date = Date::new(2022, 7, 4).unwrap();
+
+        assert_eq!(DateFormat::YearMonthDay.parse("2022-07-04").unwrap(), date);
+        assert_eq!(DateFormat::DayMonthYear.parse("04/07/2022").unwrap(), date);
+        assert_eq!(
+            DateFormat::MonthDayYear.parse("7.4.22 10:30").unwrap(),
+            date
+        );
+        assert!(DateFormat::DayMonthYear.parse("07/31/2022").is_err());
+    }
+
+    #[test]
+    fn mapping_reads_statement() {
+        let text = "Data;Histórico;Valor;Tipo\n\
+                    04/07/2022;Padaria;12,50;D\n\
+                    05/07/2022;Salário;3.000,00;C\n\
+                    ;;;\n\
+                    06/07/2022;Mercado;abc;D\n";
+        let mapping = CsvMapping {
+            delimiter: detect_delimiter(text),
+            name: 1,
+            amount: 2,
+            date: 0,
+            tr_type: Some(3),
+            sign: SignConvention::TypeColumn,
+            date_format: DateFormat::DayMonthYear,
+            decimal_separator: DecimalSeparator::Comma,
+            ..Default::default()
+        };
+
+        let rows = mapping.read(text).unwrap();
+
+        assert_eq!(rows.len(), 3);
+        let bakery = rows[0].1.as_ref().unwrap();
+        assert_eq!(bakery.name, "Padaria");
+        assert_eq!(bakery.tr_type, TransactionType::EXPENSE);
+        assert_eq!(bakery.amount, money("12.50"));
+        assert_eq!(bakery.day(), Some(Date::new(2022, 7, 4).unwrap()));
+        assert_eq!(rows[1].1.as_ref().unwrap().tr_type, TransactionType::INCOME);
+        assert_eq!(rows[2].0, 5);
+        assert!(rows[2].1.is_err());
+    }
+
+    #[test]
+    fn sign_conventions() {
+        let record: Vec<String> = ["2022-07-04", "Refund", "-5"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        let mut mapping = CsvMapping {
+            name: 1,
+            amount: 2,
+            date: 0,
+            ..Default::default()
+        };
+
+        let transaction = mapping.transaction(&record).unwrap();
+        assert_eq!(transaction.tr_type, TransactionType::EXPENSE);
+        assert_eq!(transaction.amount, money("5"));
+
+        mapping.sign = SignConvention::PositiveIsExpense;
+        let transaction = mapping.transaction(&record).unwrap();
+        assert_eq!(transaction.tr_type, TransactionType::INCOME);
+    }
+}
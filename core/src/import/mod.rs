@@ -0,0 +1,45 @@
+//! Reading transactions from files written by banks and other tools.
+//!
+//! Importers only turn files into transactions; adding them to a group is
+//! up to the caller.
+
+pub mod csv;
+pub mod ofx;
+pub mod qif;
+
+use std::collections::HashSet;
+
+use crate::{Date, Transaction};
+
+pub use self::csv::{CsvMapping, DateFormat, DecimalSeparator, ImportProfiles, SignConvention};
+
+/// `imported` transactions that are not in `existing` yet, as told by their
+/// `import_id`. Transactions without one are always kept.
+pub fn skip_imported(existing: &[Transaction], imported: Vec<Transaction>) -> Vec<Transaction> {
+    let mut known: HashSet<String> = existing
+        .iter()
+        .filter_map(|transaction| transaction.import_id.clone())
+        .collect();
+
+    imported
+        .into_iter()
+        .filter(|transaction| match &transaction.import_id {
+            Some(id) => known.insert(id.clone()),
+            None => true,
+        })
+        .collect()
+}
+
+/// Text of a statement file. Older statements are often Windows-1252 rather
+/// than UTF-8, those are read as Latin-1, which differs only in a few symbols.
+pub fn decode(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_owned(),
+        Err(_) => bytes.iter().map(|byte| char::from(*byte)).collect(),
+    }
+}
+
+/// Date of an imported transaction, which statements give without a time
+fn noon(date: Date) -> String {
+    format!("{}T12:00:00", date)
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use super::noon;
+use crate::{Date, Money, NumberFormat, Transaction, TransactionType};
+
+/// Transactions of the `STMTTRN` records of an OFX or QFX statement.
+///
+/// Both OFX 1.x, which is SGML and may leave elements unclosed, and the XML
+/// of OFX 2.x are read. Bank and credit card statements are treated alike.
+/// The `FITID` of each record is kept as `import_id`.
+pub fn parse(text: &str) -> Result<Vec<Transaction>> {
+    let start = text
+        .find("<OFX>")
+        .ok_or_else(|| anyhow!("Not an OFX statement"))?;
+
+    let mut transactions = vec![];
+    // Values of the record being read, by element name
+    let mut record: Option<HashMap<&str, String>> = None;
+
+    for element in text[start..].split('<').skip(1) {
+        let (tag, value) = element
+            .split_once('>')
+            .ok_or_else(|| anyhow!("Unclosed tag \"<{}\"", element.trim()))?;
+
+        match tag.trim() {
+            "STMTTRN" => record = Some(HashMap::new()),
+            "/STMTTRN" => {
+                let fields = record
+                    .take()
+                    .ok_or_else(|| anyhow!("</STMTTRN> without <STMTTRN>"))?;
+                let transaction =
+                    transaction(&fields).with_context(|| match fields.get("FITID") {
+                        Some(fitid) => format!("Transaction {}", fitid),
+                        None => format!("Transaction {}", transactions.len() + 1),
+                    })?;
+
+                transactions.push(transaction);
+            }
+            tag if tag.starts_with('/') => {}
+            tag => {
+                let value = unescape(value.trim());
+                // Aggregates like PAYEE have no value of their own
+                if let (Some(fields), false) = (record.as_mut(), value.is_empty()) {
+                    fields.entry(tag).or_insert(value);
+                }
+            }
+        }
+    }
+
+    if record.is_some() {
+        bail!("<STMTTRN> is never closed");
+    }
+
+    Ok(transactions)
+}
+
+fn transaction(fields: &HashMap<&str, String>) -> Result<Transaction> {
+    let field = |name: &str| {
+        fields
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("Missing <{}>", name))
+    };
+
+    // Amounts are never grouped, but may use a decimal comma
+    let amount = Money::parse_with(&field("TRNAMT")?.replace(',', "."), NumberFormat::Point)
+        .map_err(|_| anyhow!("Invalid amount \"{}\"", fields["TRNAMT"]))?;
+    let date = parse_date(field("DTPOSTED")?)?;
+
+    // Other types, such as FEE or POS, carry the direction in the sign
+    let tr_type = match field("TRNTYPE")? {
+        "DEBIT" => TransactionType::EXPENSE,
+        "CREDIT" => TransactionType::INCOME,
+        _ if amount.is_negative() => TransactionType::EXPENSE,
+        _ => TransactionType::INCOME,
+    };
+
+    // Some banks leave the name out and describe the transaction in the memo
+    let (name, notes) = match (fields.get("NAME"), fields.get("MEMO")) {
+        (Some(name), memo) => (name.clone(), memo.filter(|memo| *memo != name).cloned()),
+        (None, Some(memo)) => (memo.clone(), None),
+        (None, None) => (field("TRNTYPE")?.to_owned(), None),
+    };
+
+    Ok(Transaction {
+        notes,
+        location: fields.get("CITY").cloned(),
+        import_id: fields.get("FITID").cloned(),
+        ..Transaction::new(&name, tr_type, amount.abs(), &noon(date))
+    })
+}
+
+/// Day of an OFX date such as `20220704120000.000[-3:BRT]`. The time and
+/// zone are left out, banks use them inconsistently.
+fn parse_date(text: &str) -> Result<Date> {
+    let invalid = || anyhow!("Invalid date \"{}\"", text);
+
+    let digits = text.get(..8).ok_or_else(invalid)?;
+    let part = |range: std::ops::Range<usize>| {
+        digits
+            .get(range)
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(invalid)
+    };
+
+    Date::new(part(0..4)? as i32, part(4..6)?, part(6..8)?).map_err(|_| invalid())
+}
+
+/// Replace the character entities of SGML and XML
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_owned();
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::skip_imported;
+    use crate::test_util::money;
+
+    #[test]
+    fn reads_sgml_statement() {
+        let transactions = parse(include_str!("fixtures/statement-v1.ofx")).unwrap();
+
+        assert_eq!(transactions.len(), 3);
+
+        let bakery = &transactions[0];
+        assert_eq!(bakery.name, "PADARIA PAO & CIA");
+        assert_eq!(bakery.tr_type, TransactionType::EXPENSE);
+        assert_eq!(bakery.amount, money("12.50"));
+        assert_eq!(bakery.day(), Some(Date::new(2022, 7, 4).unwrap()));
+        assert_eq!(bakery.notes.as_deref(), Some("Compra no debito"));
+        assert_eq!(bakery.import_id.as_deref(), Some("20220704001"));
+
+        let salary = &transactions[1];
+        assert_eq!(salary.name, "SALARIO");
+        assert_eq!(salary.tr_type, TransactionType::INCOME);
+        assert_eq!(salary.notes, None);
+
+        let fee = &transactions[2];
+        assert_eq!(fee.tr_type, TransactionType::EXPENSE);
+        assert_eq!(fee.amount, money("4.90"));
+    }
+
+    #[test]
+    fn reads_xml_statement() {
+        let transactions = parse(include_str!("fixtures/statement-v2.qfx")).unwrap();
+
+        assert_eq!(transactions.len(), 3);
+
+        let grocery = &transactions[0];
+        assert_eq!(grocery.name, "Corner Grocery");
+        assert_eq!(grocery.tr_type, TransactionType::EXPENSE);
+        assert_eq!(grocery.amount, money("45.99"));
+        assert_eq!(grocery.day(), Some(Date::new(2022, 7, 12).unwrap()));
+        assert_eq!(grocery.location.as_deref(), Some("Springfield"));
+
+        assert_eq!(transactions[1].tr_type, TransactionType::INCOME);
+        assert_eq!(transactions[1].amount, money("120"));
+    }
+
+    #[test]
+    fn reimport_skips_known_transactions() {
+        let existing = parse(include_str!("fixtures/statement-v1.ofx")).unwrap();
+        let again = parse(include_str!("fixtures/statement-v1.ofx")).unwrap();
+        assert!(skip_imported(&existing, again).is_empty());
+
+        // The credit card statement lists one purchase twice
+        let card = parse(include_str!("fixtures/statement-v2.qfx")).unwrap();
+        assert_eq!(skip_imported(&existing, card).len(), 2);
+    }
+
+    #[test]
+    fn broken_records_are_rejected() {
+        assert!(parse("OFXHEADER:100\n\n").is_err());
+        assert!(parse("<OFX><STMTTRN><TRNTYPE>DEBIT<TRNAMT>1</STMTTRN></OFX>").is_err());
+        assert!(parse("<OFX><STMTTRN><TRNTYPE>DEBIT</OFX>").is_err());
+
+        let error = parse(
+            "<OFX><STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20221301<TRNAMT>-1<FITID>A1</STMTTRN></OFX>",
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", error).contains("A1"));
+
+        assert!(parse_date("202é0704").is_err());
+        assert!(parse_date("2022070").is_err());
+    }
+}
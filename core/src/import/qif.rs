@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::noon;
+use super::{DateFormat, DecimalSeparator};
+use crate::tags::normalize_tag;
+use crate::{Transaction, TransactionType};
+
+/// Section types holding bank-like transactions. Investment, category and
+/// class lists are skipped.
+const BANK_SECTIONS: &[&str] = &["Bank", "Cash", "CCard", "Oth A", "Oth L"];
+
+/// Transaction of a QIF file with the category it was filed under
+#[derive(Debug, Clone, PartialEq)]
+pub struct QifEntry {
+    pub transaction: Transaction,
+    /// Category of the `L` field without its class, `None` for transfers
+    /// and uncategorized transactions
+    pub category: Option<String>,
+}
+
+impl QifEntry {
+    /// Transaction tagged with its category, for importing all entries into
+    /// a single group
+    pub fn tagged(self) -> Transaction {
+        let mut transaction = self.transaction;
+
+        if let Some(tag) = self.category.as_deref().and_then(normalize_tag) {
+            if !transaction.tags.contains(&tag) {
+                transaction.tags.push(tag);
+            }
+        }
+
+        transaction
+    }
+}
+
+/// Entries of the bank sections of QIF `text`, with the line each starts at.
+/// Entries that cannot be read keep their error.
+///
+/// QIF leaves the date format to the program that wrote it, so it has to be
+/// given, as does the decimal separator.
+pub fn parse(
+    text: &str,
+    date_format: DateFormat,
+    decimal_separator: DecimalSeparator,
+) -> Result<Vec<(usize, Result<QifEntry>)>> {
+    let mut entries = vec![];
+    // Whether the current section holds transactions, `None` before the first header
+    let mut in_bank: Option<bool> = None;
+    let mut fields: HashMap<char, &str> = HashMap::new();
+    let mut entry_line = 1;
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+
+        if let Some(header) = line.strip_prefix('!') {
+            in_bank = Some(match header.trim().strip_prefix("Type:") {
+                Some(section) => BANK_SECTIONS
+                    .iter()
+                    .any(|bank| bank.eq_ignore_ascii_case(section.trim())),
+                None => false,
+            });
+            fields.clear();
+            continue;
+        }
+
+        let mut chars = line.chars();
+        match chars.next() {
+            None => {}
+            Some('^') => {
+                if in_bank == Some(true) && !fields.is_empty() {
+                    entries.push((entry_line, entry(&fields, date_format, decimal_separator)));
+                }
+                fields.clear();
+            }
+            Some(code) => {
+                if fields.is_empty() {
+                    entry_line = index + 1;
+                }
+                // Split lines repeat codes, the first value describes the whole entry
+                fields.entry(code).or_insert_with(|| chars.as_str().trim());
+            }
+        }
+    }
+
+    if in_bank.is_none() {
+        bail!("Not a QIF file");
+    }
+
+    Ok(entries)
+}
+
+fn entry(
+    fields: &HashMap<char, &str>,
+    date_format: DateFormat,
+    decimal_separator: DecimalSeparator,
+) -> Result<QifEntry> {
+    let field = |code: char| fields.get(&code).copied().filter(|value| !value.is_empty());
+
+    // Quicken writes dates such as ` 7/ 4'22`
+    let date = date_format.parse(
+        &field('D')
+            .ok_or_else(|| anyhow!("Missing date"))?
+            .replace(' ', ""),
+    )?;
+    let amount = decimal_separator.parse(
+        field('T')
+            .or_else(|| field('U'))
+            .ok_or_else(|| anyhow!("Missing amount"))?,
+    )?;
+    let tr_type = if amount.is_negative() {
+        TransactionType::EXPENSE
+    } else {
+        TransactionType::INCOME
+    };
+
+    // Transfers name the other account in brackets, classes follow a slash
+    let category = field('L')
+        .filter(|category| !category.starts_with('['))
+        .and_then(|category| category.split('/').next())
+        .map(str::trim)
+        .filter(|category| !category.is_empty())
+        .map(str::to_owned);
+
+    let (name, notes) = match (field('P'), field('M')) {
+        (Some(payee), memo) => (payee, memo.filter(|memo| *memo != payee)),
+        (None, Some(memo)) => (memo, None),
+        (None, None) => (
+            category
+                .as_deref()
+                .ok_or_else(|| anyhow!("Missing payee"))?,
+            None,
+        ),
+    };
+
+    Ok(QifEntry {
+        transaction: Transaction {
+            notes: notes.map(str::to_owned),
+            ..Transaction::new(name, tr_type, amount.abs(), &noon(date))
+        },
+        category,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::money;
+    use crate::Date;
+
+    const STATEMENT: &str = "!Account\n\
+                             NChecking\n\
+                             TBank\n\
+                             ^\n\
+                             !Type:Bank\n\
+                             D 7/ 4'22\n\
+                             T-12.50\n\
+                             PBakery\n\
+                             MCroissants\n\
+                             LFood:Bakery/Personal\n\
+                             ^\n\
+                             D07/05/2022\n\
+                             T3,000.00\n\
+                             PEmployer\n\
+                             LSalary\n\
+                             ^\n\
+                             D07/06/2022\n\
+                             T-100.00\n\
+                             PSavings\n\
+                             L[Savings]\n\
+                             ^\n\
+                             D07/32/2022\n\
+                             T-1.00\n\
+                             PBroken\n\
+                             ^\n\
+                             !Type:Cat\n\
+                             NFood\n\
+                             E\n\
+                             ^\n";
+
+    #[test]
+    fn reads_bank_section() {
+        let entries = parse(STATEMENT, DateFormat::MonthDayYear, DecimalSeparator::Point).unwrap();
+
+        assert_eq!(entries.len(), 4);
+
+        let bakery = entries[0].1.as_ref().unwrap();
+        assert_eq!(entries[0].0, 6);
+        assert_eq!(bakery.transaction.name, "Bakery");
+        assert_eq!(bakery.transaction.tr_type, TransactionType::EXPENSE);
+        assert_eq!(bakery.transaction.amount, money("12.50"));
+        assert_eq!(
+            bakery.transaction.day(),
+            Some(Date::new(2022, 7, 4).unwrap())
+        );
+        assert_eq!(bakery.transaction.notes.as_deref(), Some("Croissants"));
+        assert_eq!(bakery.category.as_deref(), Some("Food:Bakery"));
+
+        let salary = entries[1].1.as_ref().unwrap();
+        assert_eq!(salary.transaction.tr_type, TransactionType::INCOME);
+        assert_eq!(salary.transaction.amount, money("3000"));
+
+        assert_eq!(entries[2].1.as_ref().unwrap().category, None);
+        assert!(entries[3].1.is_err());
+    }
+
+    #[test]
+    fn categories_become_tags() {
+        let entries = parse(STATEMENT, DateFormat::MonthDayYear, DecimalSeparator::Point).unwrap();
+
+        let bakery = entries[0].1.as_ref().unwrap().clone().tagged();
+        assert_eq!(bakery.tags, vec![normalize_tag("Food:Bakery").unwrap()]);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse(
+            "Date,Amount\n",
+            DateFormat::YearMonthDay,
+            DecimalSeparator::Point
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{Date, Money, Transaction};
+
+/// Position of a transaction within a purchase paid in installments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Installment {
+    /// Shared by every installment of the same purchase
+    pub plan: Uuid,
+    /// Counting from 1
+    pub number: u32,
+    pub count: u32,
+}
+
+impl Installment {
+    /// Whether `other` belongs to the same purchase and comes after this one
+    pub fn is_followed_by(&self, other: &Installment) -> bool {
+        other.plan == self.plan && other.number > self.number
+    }
+}
+
+/// Displays as "3/10"
+impl fmt::Display for Installment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.number, self.count)
+    }
+}
+
+/// Split `purchase` into `count` monthly installments starting on its date.
+///
+/// Its amount is the total, divided in parts with `decimals` fractional
+/// digits. Any remainder goes to the first installment so the parts add up
+/// exactly. The first installment keeps the id of `purchase`.
+pub fn split_installments(purchase: &Transaction, count: u32, decimals: u32) -> Vec<Transaction> {
+    let count = count.max(1);
+    let parts = split_amount(purchase.amount, count, decimals);
+
+    let plan = Uuid::new_v4();
+    let first_day = Date::parse_iso(&purchase.date).ok();
+    let time = purchase.date.get(10..).unwrap_or_default();
+
+    (0..count)
+        .map(|index| {
+            let date = match first_day {
+                Some(day) => format!("{}{}", day.add_months(index as i32), time),
+                None => purchase.date.clone(),
+            };
+
+            Transaction {
+                id: if index == 0 {
+                    purchase.id
+                } else {
+                    Uuid::new_v4()
+                },
+                amount: parts[index as usize],
+                date,
+                installment: Some(Installment {
+                    plan,
+                    number: index + 1,
+                    count,
+                }),
+                ..purchase.clone()
+            }
+        })
+        .collect()
+}
+
+/// Amounts of the installments from an edited one to the last, after the
+/// edited one changed from `previous[0]` to `amount`.
+///
+/// `previous` holds the amounts before the edit, the edited installment
+/// first. Nothing changes when the amount is the same. Otherwise every part
+/// moves by the same difference and the remaining total is split again like
+/// `split_installments` does, so the remainder stays on the edited one.
+pub fn respread_installments(previous: &[Money], amount: Money, decimals: u32) -> Vec<Money> {
+    let edited = match previous.first() {
+        Some(edited) if *edited != amount => *edited,
+        _ => return previous.to_vec(),
+    };
+
+    let count = previous.len() as u32;
+    let change = amount - edited;
+    let total: Money = previous.iter().map(|part| *part + change).sum();
+
+    split_amount(total, count, decimals)
+}
+
+/// `total` in `count` parts with `decimals` fractional digits, any remainder
+/// on the first one
+fn split_amount(total: Money, count: u32, decimals: u32) -> Vec<Money> {
+    let step = 10_i64.pow(Money::DECIMALS - decimals.min(Money::DECIMALS));
+    let part = total.units() / step / count as i64 * step;
+    let first_part = total.units() - part * (count as i64 - 1);
+
+    (0..count)
+        .map(|index| Money::from_units(if index == 0 { first_part } else { part }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::money;
+    use crate::TransactionType;
+
+    fn purchase(amount: &str, date: &str) -> Transaction {
+        Transaction::new(
+            "Laptop",
+            TransactionType::EXPENSE,
+            amount.parse().unwrap(),
+            date,
+        )
+    }
+
+    #[test]
+    fn parts_add_up_to_total() {
+        let laptop = purchase("1000", "2022-01-31T10:00:00-03");
+
+        let parts = split_installments(&laptop, 3, 2);
+
+        let amounts: Vec<String> = parts.iter().map(|t| t.amount.to_string()).collect();
+        assert_eq!(amounts, vec!["333.34", "333.33", "333.33"]);
+        assert_eq!(parts.iter().map(|t| t.amount).sum::<Money>(), laptop.amount);
+    }
+
+    #[test]
+    fn installments_are_spread_monthly() {
+        let laptop = purchase("1000", "2022-01-31T10:00:00-03");
+
+        let parts = split_installments(&laptop, 3, 2);
+
+        let dates: Vec<&str> = parts.iter().map(|t| t.date.as_str()).collect();
+        assert_eq!(
+            dates,
+            vec![
+                "2022-01-31T10:00:00-03",
+                "2022-02-28T10:00:00-03",
+                "2022-03-31T10:00:00-03",
+            ]
+        );
+    }
+
+    #[test]
+    fn installments_are_linked() {
+        let laptop = purchase("1000", "2022-01-31T10:00:00-03");
+
+        let parts = split_installments(&laptop, 10, 0);
+        let first = parts[0].installment.unwrap();
+        let third = parts[2].installment.unwrap();
+
+        assert_eq!(parts[0].id, laptop.id);
+        assert_eq!(third.to_string(), "3/10");
+        assert!(first.is_followed_by(&third));
+        assert!(!third.is_followed_by(&first));
+        assert!(parts
+            .iter()
+            .all(|t| t.installment.unwrap().plan == first.plan));
+    }
+
+    #[test]
+    fn editing_only_details_keeps_amounts() {
+        let parts = split_installments(&purchase("1000", "2022-01-31T10:00:00-03"), 3, 2);
+        let amounts: Vec<Money> = parts.iter().map(|t| t.amount).collect();
+
+        let respread = respread_installments(&amounts, amounts[0], 2);
+
+        assert_eq!(respread, amounts);
+        assert_eq!(respread.iter().sum::<Money>(), money("1000"));
+    }
+
+    #[test]
+    fn changed_amount_is_split_again() {
+        let parts = split_installments(&purchase("1000", "2022-01-31T10:00:00-03"), 3, 2);
+        let amounts: Vec<Money> = parts.iter().map(|t| t.amount).collect();
+
+        let from_first = respread_installments(&amounts, money("400"), 2);
+        let from_second = respread_installments(&amounts[1..], money("350"), 2);
+
+        assert_eq!(
+            from_first,
+            vec![money("400"), money("399.99"), money("399.99")]
+        );
+        assert_eq!(from_second, vec![money("350"), money("350")]);
+    }
+}
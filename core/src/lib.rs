@@ -0,0 +1,43 @@
+//! Budgeting data types, persistence and calculations used by Beedget.
+//!
+//! Nothing in here depends on GTK, so the same logic can back the desktop
+//! app or any other frontend.
+
+pub mod budget;
+pub mod currency;
+pub mod data_object;
+pub mod date;
+pub mod exchange_rates;
+pub mod export;
+pub mod group;
+pub mod import;
+pub mod installment;
+pub mod migrations;
+pub mod money;
+pub mod number_format;
+pub mod recurrence;
+pub mod split;
+pub mod storage;
+pub mod tags;
+pub mod transaction;
+pub mod transfer;
+
+#[cfg(test)]
+mod test_util;
+
+pub use budget::{Budget, BudgetPeriod, BudgetStatus, Rollover};
+pub use currency::Currency;
+pub use data_object::DataObject;
+pub use date::{Date, DateRange, Weekday};
+pub use exchange_rates::ExchangeRates;
+pub use group::{Group, Summary};
+pub use import::{CsvMapping, ImportProfiles};
+pub use installment::Installment;
+pub use money::Money;
+pub use number_format::NumberFormat;
+pub use recurrence::{Frequency, RecurrenceEnd, RecurringTransaction};
+pub use split::Split;
+pub use storage::{LoadProblem, Storage, StorageBackend};
+pub use tags::TagTotal;
+pub use transaction::{Transaction, TransactionType};
+pub use transfer::Transfer;
@@ -0,0 +1,249 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::currency::DEFAULT_CURRENCY;
+use crate::Money;
+
+/// Upgrades a raw document from one schema version to the next
+pub type Migration = fn(&mut Value) -> Result<()>;
+
+/// Key holding the schema version in every saved document
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Directory, next to the data directory, where pre-migration copies are kept
+pub const BACKUP_DIR: &str = "backups";
+
+/// Group file migrations. Entry `i` upgrades version `i` to `i + 1`.
+pub static GROUP_MIGRATIONS: &[Migration] = &[
+    group_v0_to_v1,
+    group_v1_to_v2,
+    group_v2_to_v3,
+    group_v3_to_v4,
+    group_v4_to_v5,
+    group_v5_to_v6,
+    group_v6_to_v7,
+    group_v7_to_v8,
+    group_v8_to_v9,
+    group_v9_to_v10,
+];
+
+/// Current version of the group file schema
+pub const GROUP_SCHEMA_VERSION: u32 = GROUP_MIGRATIONS.len() as u32;
+
+/// Version stored in document. Files written before versioning count as 0.
+pub fn schema_version(doc: &Value) -> Result<u32> {
+    match doc.get(SCHEMA_VERSION_KEY) {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| anyhow!("Invalid schema version {}", version)),
+    }
+}
+
+/// Document written by a newer version of Beedget. Such files are valid and
+/// must be left where they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NewerSchema {
+    pub version: u32,
+    pub supported: u32,
+}
+
+impl fmt::Display for NewerSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "File has schema version {} but this version of Beedget only supports up to {}",
+            self.version, self.supported
+        )
+    }
+}
+
+impl std::error::Error for NewerSchema {}
+
+/// Whether `error` was caused by a document newer than this version supports
+pub fn is_newer_schema(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<NewerSchema>())
+}
+
+/// Apply every pending migration in order.
+///
+/// Returns the version the document had before upgrading.
+pub fn upgrade(doc: &mut Value, migrations: &[Migration]) -> Result<u32> {
+    let original_version = schema_version(doc)?;
+    let latest_version = migrations.len() as u32;
+
+    if original_version > latest_version {
+        return Err(NewerSchema {
+            version: original_version,
+            supported: latest_version,
+        }
+        .into());
+    }
+
+    for (version, migration) in migrations
+        .iter()
+        .enumerate()
+        .skip(original_version as usize)
+    {
+        migration(doc)?;
+        doc[SCHEMA_VERSION_KEY] = json!(version + 1);
+    }
+
+    Ok(original_version)
+}
+
+/// Copy file to the backup directory before it is rewritten with a newer schema
+pub fn backup_file(path: &Path, version: u32) -> Result<PathBuf> {
+    let data_dir = path
+        .parent()
+        .ok_or_else(|| anyhow!("{} has no parent directory", path.display()))?;
+    let backup_dir = data_dir.parent().unwrap_or(data_dir).join(BACKUP_DIR);
+    fs::create_dir_all(&backup_dir)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", path.display()))?
+        .to_string_lossy();
+    let backup_path = backup_dir.join(format!("{}.v{}", file_name, version));
+    fs::copy(path, &backup_path)?;
+
+    Ok(backup_path)
+}
+
+/// v1: amounts become exact decimal strings and groups get a currency
+fn group_v0_to_v1(doc: &mut Value) -> Result<()> {
+    let group = doc
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Group document is not an object"))?;
+
+    group
+        .entry("currency")
+        .or_insert_with(|| json!(DEFAULT_CURRENCY));
+
+    if let Some(transactions) = group.get_mut("transactions").and_then(Value::as_array_mut) {
+        for transaction in transactions {
+            if let Some(amount) = transaction.get("amount").and_then(Value::as_f64) {
+                transaction["amount"] = json!(Money::from_f64(amount).to_string());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// v2: groups may have a spending limit
+fn group_v1_to_v2(doc: &mut Value) -> Result<()> {
+    doc.as_object_mut()
+        .ok_or_else(|| anyhow!("Group document is not an object"))?
+        .entry("budget")
+        .or_insert(Value::Null);
+
+    Ok(())
+}
+
+/// v3: budgets choose what happens to leftover money
+fn group_v2_to_v3(doc: &mut Value) -> Result<()> {
+    if let Some(budget) = doc.get_mut("budget").and_then(Value::as_object_mut) {
+        budget.entry("rollover").or_insert_with(|| json!("reset"));
+        budget.entry("since").or_insert(Value::Null);
+    }
+
+    Ok(())
+}
+
+/// v4: groups may have recurring transaction rules
+fn group_v3_to_v4(doc: &mut Value) -> Result<()> {
+    doc.as_object_mut()
+        .ok_or_else(|| anyhow!("Group document is not an object"))?
+        .entry("recurring")
+        .or_insert_with(|| json!([]));
+
+    Ok(())
+}
+
+/// v5: transactions may be installments of a purchase
+fn group_v4_to_v5(doc: &mut Value) -> Result<()> {
+    if let Some(transactions) = doc.get_mut("transactions").and_then(Value::as_array_mut) {
+        for transaction in transactions {
+            if let Some(transaction) = transaction.as_object_mut() {
+                transaction.entry("installment").or_insert(Value::Null);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// v6: transactions may be one side of a transfer between groups
+fn group_v5_to_v6(doc: &mut Value) -> Result<()> {
+    if let Some(transactions) = doc.get_mut("transactions").and_then(Value::as_array_mut) {
+        for transaction in transactions {
+            if let Some(transaction) = transaction.as_object_mut() {
+                transaction.entry("transfer").or_insert(Value::Null);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// v7: transactions may be a share of one split across groups
+fn group_v6_to_v7(doc: &mut Value) -> Result<()> {
+    if let Some(transactions) = doc.get_mut("transactions").and_then(Value::as_array_mut) {
+        for transaction in transactions {
+            if let Some(transaction) = transaction.as_object_mut() {
+                transaction.entry("split").or_insert(Value::Null);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// v8: transactions and recurring rules have a list of tags
+fn group_v7_to_v8(doc: &mut Value) -> Result<()> {
+    for key in ["transactions", "recurring"] {
+        if let Some(items) = doc.get_mut(key).and_then(Value::as_array_mut) {
+            for item in items {
+                if let Some(item) = item.as_object_mut() {
+                    item.entry("tags").or_insert_with(|| json!([]));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// v9: transactions may have a payee, location and notes
+fn group_v8_to_v9(doc: &mut Value) -> Result<()> {
+    if let Some(transactions) = doc.get_mut("transactions").and_then(Value::as_array_mut) {
+        for transaction in transactions {
+            if let Some(transaction) = transaction.as_object_mut() {
+                for key in ["payee", "location", "notes"] {
+                    transaction.entry(key).or_insert(Value::Null);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// v10: imported transactions keep the identifier given by the bank
+fn group_v9_to_v10(doc: &mut Value) -> Result<()> {
+    if let Some(transactions) = doc.get_mut("transactions").and_then(Value::as_array_mut) {
+        for transaction in transactions {
+            if let Some(transaction) = transaction.as_object_mut() {
+                transaction.entry("import_id").or_insert(Value::Null);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,420 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::NumberFormat;
+
+/// Exact monetary amount, stored as a whole number of ten-thousandths.
+///
+/// Four fractional digits cover every ISO 4217 currency, so sums never drift
+/// the way `f32` amounts did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    /// Number of fractional digits kept internally
+    pub const DECIMALS: u32 = 4;
+    /// Internal units per whole currency unit
+    pub const SCALE: i64 = 10_i64.pow(Self::DECIMALS);
+
+    pub const ZERO: Money = Money(0);
+
+    /// Build amount from raw ten-thousandths
+    pub const fn from_units(units: i64) -> Self {
+        Self(units)
+    }
+
+    /// Raw ten-thousandths, used as the GObject property value
+    pub const fn units(&self) -> i64 {
+        self.0
+    }
+
+    /// Convert legacy floating point amounts, rounding to the nearest unit.
+    ///
+    /// Only meant for reading files saved before amounts were exact.
+    pub fn from_f64(value: f64) -> Self {
+        Self((value * Self::SCALE as f64).round() as i64)
+    }
+
+    /// Approximate value, for display purposes such as progress bars
+    pub fn to_f64(&self) -> f64 {
+        self.0 as f64 / Self::SCALE as f64
+    }
+
+    pub fn abs(&self) -> Self {
+        Self(self.0.abs())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    /// Round half away from zero to `decimals` fractional digits
+    pub fn round_to(&self, decimals: u32) -> Self {
+        if decimals >= Self::DECIMALS {
+            return *self;
+        }
+
+        let step = 10_i64.pow(Self::DECIMALS - decimals);
+        let half = step / 2;
+        let rounded = if self.0 >= 0 {
+            (self.0 + half) / step * step
+        } else {
+            (self.0 - half) / step * step
+        };
+
+        Self(rounded)
+    }
+
+    /// Format with exactly `decimals` fractional digits, e.g. `format_fixed(2)` -> `"12.30"`
+    pub fn format_fixed(&self, decimals: u32) -> String {
+        let decimals = decimals.min(Self::DECIMALS);
+        let rounded = self.round_to(decimals).0;
+
+        let sign = if rounded < 0 { "-" } else { "" };
+        let whole = rounded.abs() / Self::SCALE;
+        let fraction = rounded.abs() % Self::SCALE;
+
+        if decimals == 0 {
+            format!("{}{}", sign, whole)
+        } else {
+            let fraction = fraction / 10_i64.pow(Self::DECIMALS - decimals);
+            format!(
+                "{}{}.{:0width$}",
+                sign,
+                whole,
+                fraction,
+                width = decimals as usize
+            )
+        }
+    }
+
+    /// Number of significant fractional digits (0 to `DECIMALS`)
+    pub fn fractional_digits(&self) -> u32 {
+        let mut fraction = self.0.abs() % Self::SCALE;
+        if fraction == 0 {
+            return 0;
+        }
+
+        let mut digits = Self::DECIMALS;
+        while fraction % 10 == 0 {
+            fraction /= 10;
+            digits -= 1;
+        }
+
+        digits
+    }
+
+    /// Parse an amount written in `format`, such as `1,234.56` for
+    /// `NumberFormat::Point`. Spaces may group digits in any format.
+    pub fn parse_with(text: &str, format: NumberFormat) -> Result<Self> {
+        let s = text.trim();
+
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+
+        let (whole_str, fraction_str) = match digits.split_once(format.decimal_separator()) {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (digits, ""),
+        };
+
+        // Every group after the first has exactly three digits
+        let groups: Vec<&str> = whole_str
+            .split([format.group_separator(), ' ', '\u{a0}'])
+            .collect();
+        let grouped = groups.len() > 1
+            && (1..=3).contains(&groups[0].len())
+            && groups[1..].iter().all(|group| group.len() == 3);
+        if groups.len() > 1 && !grouped {
+            bail!("Invalid amount \"{}\"", s);
+        }
+        let whole_str = groups.concat();
+
+        if whole_str.is_empty() && fraction_str.is_empty() {
+            bail!("Empty amount");
+        }
+
+        if !whole_str.chars().all(|c| c.is_ascii_digit())
+            || !fraction_str.chars().all(|c| c.is_ascii_digit())
+        {
+            bail!("Invalid amount \"{}\"", s);
+        }
+
+        if fraction_str.len() > Self::DECIMALS as usize {
+            bail!(
+                "Amount \"{}\" has more than {} decimal places",
+                s,
+                Self::DECIMALS
+            );
+        }
+
+        let whole: i64 = if whole_str.is_empty() {
+            0
+        } else {
+            whole_str.parse()?
+        };
+        let fraction: i64 = if fraction_str.is_empty() {
+            0
+        } else {
+            fraction_str.parse::<i64>()? * 10_i64.pow(Self::DECIMALS - fraction_str.len() as u32)
+        };
+
+        let units = whole
+            .checked_mul(Self::SCALE)
+            .and_then(|w| w.checked_add(fraction))
+            .ok_or_else(|| anyhow::anyhow!("Amount \"{}\" is too large", s))?;
+
+        Ok(Self(if negative { -units } else { units }))
+    }
+}
+
+impl fmt::Display for Money {
+    /// Shortest exact representation with at least two fractional digits
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format_fixed(self.fractional_digits().max(2)))
+    }
+}
+
+impl FromStr for Money {
+    type Err = Error;
+
+    /// Parse a decimal amount such as `12`, `-3.5`, `1,234.56` or `1.234,56`.
+    ///
+    /// When both `.` and `,` appear, the last one is the decimal separator and
+    /// the other is treated as a thousands separator. Input where a single
+    /// kind of separator could be either, such as `1,500` or `1.234.567`, is
+    /// rejected; use `parse_with` when the number format is known.
+    fn from_str(s: &str) -> Result<Self> {
+        let digits = s.trim().trim_start_matches(['-', '+']);
+
+        let format = match (digits.rfind('.'), digits.rfind(',')) {
+            (Some(dot), Some(comma)) if dot > comma => NumberFormat::Point,
+            (Some(_), Some(_)) => NumberFormat::Comma,
+            (Some(_), None) | (None, Some(_)) => {
+                let separator = if digits.contains('.') { '.' } else { ',' };
+                let (whole, fraction) = digits.rsplit_once(separator).unwrap();
+
+                let repeated = whole.contains(separator);
+                let could_be_grouping = fraction.len() == 3
+                    && (1..=3).contains(&whole.len())
+                    && whole.chars().all(|c| c.is_ascii_digit())
+                    && !whole.starts_with('0');
+                if repeated || could_be_grouping {
+                    bail!(
+                        "Amount \"{}\" is ambiguous, \"{}\" could separate decimals or thousands",
+                        s.trim(),
+                        separator
+                    );
+                }
+
+                if separator == ',' {
+                    NumberFormat::Comma
+                } else {
+                    NumberFormat::Point
+                }
+            }
+            (None, None) => NumberFormat::Point,
+        };
+
+        Self::parse_with(s, format)
+    }
+}
+
+impl Add for Money {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |acc, x| acc + x)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |acc, x| acc + *x)
+    }
+}
+
+/// Amounts are saved as decimal strings so no precision is lost in JSON
+impl Serialize for Money {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Accepts decimal strings as well as the plain numbers older group files stored
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MoneyVisitor;
+
+        impl<'de> Visitor<'de> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a decimal amount as string or number")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Money, E> {
+                Money::parse_with(v, NumberFormat::Point).map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+                v.checked_mul(Money::SCALE)
+                    .map(Money)
+                    .ok_or_else(|| E::custom("amount out of range"))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+                i64::try_from(v)
+                    .ok()
+                    .and_then(|v| v.checked_mul(Money::SCALE))
+                    .map(Money)
+                    .ok_or_else(|| E::custom("amount out of range"))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+                Ok(Money::from_f64(v))
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::money;
+
+    #[test]
+    fn parses_either_decimal_separator() {
+        assert_eq!(money("12.5"), Money::from_units(125_000));
+        assert_eq!(money("12,5"), Money::from_units(125_000));
+        assert_eq!(money("1,234.56"), Money::from_units(12_345_600));
+        assert_eq!(money("1.234,56"), Money::from_units(12_345_600));
+        assert_eq!(money("-0.0001"), Money::from_units(-1));
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        assert!("".parse::<Money>().is_err());
+        assert!("abc".parse::<Money>().is_err());
+        assert!("1.00001".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn rejects_ambiguous_separators() {
+        assert!("1,500".parse::<Money>().is_err());
+        assert!("1.234.567".parse::<Money>().is_err());
+        assert!("1,2.5".parse::<Money>().is_err());
+        assert_eq!(money("0.125"), Money::from_units(1_250));
+        assert_eq!(money("1234.567"), Money::from_units(12_345_670));
+    }
+
+    #[test]
+    fn parses_with_number_format() {
+        let parse = |text, format| Money::parse_with(text, format).unwrap();
+
+        assert_eq!(parse("1,500", NumberFormat::Point), money("1500"));
+        assert_eq!(parse("1,500", NumberFormat::Comma), money("1.5"));
+        assert_eq!(parse("1.234.567", NumberFormat::Comma), money("1234567"));
+        assert_eq!(parse("1 234,5", NumberFormat::Space), money("1234.5"));
+        assert!(Money::parse_with("1.5", NumberFormat::Comma).is_err());
+        assert!(Money::parse_with("12,34.5", NumberFormat::Point).is_err());
+    }
+
+    #[test]
+    fn serialized_amounts_are_never_ambiguous() {
+        let amount = Money::from_units(121_250);
+
+        assert_eq!(serde_json::from_str::<Money>("\"12.125\"").unwrap(), amount);
+    }
+
+    #[test]
+    fn displays_at_least_two_decimals() {
+        assert_eq!(money("12").to_string(), "12.00");
+        assert_eq!(money("12.5").to_string(), "12.50");
+        assert_eq!(money("0.125").to_string(), "0.125");
+        assert_eq!(money("-3.1").to_string(), "-3.10");
+    }
+
+    #[test]
+    fn arithmetic_is_exact() {
+        let total: Money = ["0.1", "0.2", "0.3"].iter().map(|text| money(text)).sum();
+
+        assert_eq!(total, money("0.6"));
+        assert_eq!(money("0.1") - money("0.3"), -money("0.2"));
+    }
+
+    #[test]
+    fn serializes_as_string() {
+        let amount = money("1234.5678");
+        let serialized = serde_json::to_string(&amount).unwrap();
+
+        assert_eq!(serialized, "\"1234.5678\"");
+        assert_eq!(serde_json::from_str::<Money>(&serialized).unwrap(), amount);
+    }
+
+    #[test]
+    fn deserializes_legacy_numbers() {
+        assert_eq!(serde_json::from_str::<Money>("12").unwrap(), money("12"));
+        assert_eq!(
+            serde_json::from_str::<Money>("12.34").unwrap(),
+            money("12.34")
+        );
+    }
+}
@@ -0,0 +1,83 @@
+/// How amounts are written: decimal separator and digit grouping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    /// `1,234.56`
+    #[default]
+    Point,
+    /// `1.234,56`
+    Comma,
+    /// `1 234,56`
+    Space,
+}
+
+impl NumberFormat {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "comma" => NumberFormat::Comma,
+            "space" => NumberFormat::Space,
+            _ => NumberFormat::Point,
+        }
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        match self {
+            NumberFormat::Point => '.',
+            NumberFormat::Comma | NumberFormat::Space => ',',
+        }
+    }
+
+    /// Thousands separator. The space is non-breaking so amounts stay on one line.
+    pub fn group_separator(&self) -> char {
+        match self {
+            NumberFormat::Point => ',',
+            NumberFormat::Comma => '.',
+            NumberFormat::Space => '\u{a0}',
+        }
+    }
+
+    /// Apply separators to a plain number such as `-1234.56`
+    pub fn apply(&self, number: &str) -> String {
+        let (sign, digits) = match number.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", number),
+        };
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (digits, None),
+        };
+
+        let mut formatted = String::from(sign);
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                formatted.push(self.group_separator());
+            }
+            formatted.push(digit);
+        }
+
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal_separator());
+            formatted.push_str(fraction);
+        }
+
+        formatted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_thousands() {
+        assert_eq!(NumberFormat::Point.apply("1234567.89"), "1,234,567.89");
+        assert_eq!(NumberFormat::Comma.apply("1234567.89"), "1.234.567,89");
+        assert_eq!(NumberFormat::Space.apply("1234.5"), "1\u{a0}234,5");
+    }
+
+    #[test]
+    fn keeps_short_and_negative_numbers() {
+        assert_eq!(NumberFormat::Point.apply("123.45"), "123.45");
+        assert_eq!(NumberFormat::Comma.apply("-1234"), "-1.234");
+        assert_eq!(NumberFormat::Point.apply("-999"), "-999");
+    }
+}
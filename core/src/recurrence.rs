@@ -0,0 +1,276 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{Date, Money, Transaction, TransactionType};
+
+/// Unit a recurrence repeats in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    /// On the day of month of the first occurrence, or the last day of
+    /// shorter months
+    #[default]
+    Monthly,
+    Yearly,
+}
+
+/// When a recurrence stops producing transactions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RecurrenceEnd {
+    #[default]
+    Never,
+    /// Last occurrence may fall on `date`
+    Until { date: Date },
+    /// Total number of occurrences, counting the first one
+    Count { occurrences: u32 },
+}
+
+/// Rule that adds a transaction to its group on a schedule.
+///
+/// Occurrence `n` is computed from the first one rather than the previous,
+/// so monthly rules starting on the 31st keep coming back to the 31st.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringTransaction {
+    pub id: Uuid,
+    pub name: String,
+    pub tr_type: TransactionType,
+    pub amount: Money,
+    /// Copied to every transaction the rule adds
+    pub tags: Vec<String>,
+    /// ISO 8601 date and time of the first occurrence. Later occurrences
+    /// keep its time of day.
+    pub start: String,
+    pub frequency: Frequency,
+    /// Repeat every `interval` units of `frequency`
+    pub interval: u32,
+    pub end: RecurrenceEnd,
+    pub paused: bool,
+    /// Occurrences already added to the group
+    pub generated: u32,
+}
+
+impl RecurringTransaction {
+    /// Rule repeating `transaction`, which counts as its first occurrence
+    pub fn from_transaction(
+        transaction: &Transaction,
+        frequency: Frequency,
+        interval: u32,
+        end: RecurrenceEnd,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: transaction.name.clone(),
+            tr_type: transaction.tr_type,
+            amount: transaction.amount,
+            tags: transaction.tags.clone(),
+            start: transaction.date.clone(),
+            frequency,
+            interval: interval.max(1),
+            end,
+            paused: false,
+            generated: 1,
+        }
+    }
+
+    /// Date of occurrence `index`, counting from 0, ignoring the end condition
+    pub fn occurrence(&self, index: u32) -> Option<Date> {
+        let start = Date::parse_iso(&self.start).ok()?;
+        let steps = index as i64 * self.interval.max(1) as i64;
+
+        Some(match self.frequency {
+            Frequency::Daily => start.add_days(steps),
+            Frequency::Weekly => start.add_days(steps * 7),
+            Frequency::Monthly => start.add_months(steps as i32),
+            Frequency::Yearly => start.add_months(steps as i32 * 12),
+        })
+    }
+
+    /// Whether occurrence `index` is within the end condition
+    fn includes(&self, index: u32, date: Date) -> bool {
+        match self.end {
+            RecurrenceEnd::Never => true,
+            RecurrenceEnd::Until { date: until } => date <= until,
+            RecurrenceEnd::Count { occurrences } => index < occurrences,
+        }
+    }
+
+    /// Date of the next occurrence not yet added, `None` once finished
+    pub fn next_occurrence(&self) -> Option<Date> {
+        let date = self.occurrence(self.generated)?;
+
+        self.includes(self.generated, date).then_some(date)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_occurrence().is_none()
+    }
+
+    /// Transaction for the occurrence on `date`
+    fn transaction_on(&self, date: Date) -> Transaction {
+        let time = self.start.get(10..).unwrap_or_default();
+
+        Transaction {
+            tags: self.tags.clone(),
+            ..Transaction::new(
+                &self.name,
+                self.tr_type,
+                self.amount,
+                &format!("{}{}", date, time),
+            )
+        }
+    }
+
+    /// Transactions for every occurrence due up to and including `today`.
+    ///
+    /// They are counted as generated, so each one is only returned once.
+    pub fn take_due(&mut self, today: Date) -> Vec<Transaction> {
+        let mut due = vec![];
+        if self.paused {
+            return due;
+        }
+
+        while let Some(date) = self.next_occurrence().filter(|date| *date <= today) {
+            due.push(self.transaction_on(date));
+            self.generated += 1;
+        }
+
+        due
+    }
+
+    /// Pause or resume the rule. Occurrences missed while paused are skipped.
+    pub fn set_paused(&mut self, paused: bool, today: Date) {
+        if self.paused && !paused {
+            while self.next_occurrence().is_some_and(|date| date < today) {
+                self.generated += 1;
+            }
+        }
+
+        self.paused = paused;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        Date::parse_iso(text).unwrap()
+    }
+
+    fn rule(
+        start: &str,
+        frequency: Frequency,
+        interval: u32,
+        end: RecurrenceEnd,
+    ) -> RecurringTransaction {
+        let first = Transaction::new(
+            "Rent",
+            TransactionType::EXPENSE,
+            "1200".parse().unwrap(),
+            start,
+        );
+
+        RecurringTransaction::from_transaction(&first, frequency, interval, end)
+    }
+
+    fn dates(transactions: &[Transaction]) -> Vec<String> {
+        transactions.iter().map(|t| t.date.clone()).collect()
+    }
+
+    #[test]
+    fn monthly_rules_keep_day_of_month() {
+        let mut rent = rule(
+            "2022-01-31T09:00:00-03",
+            Frequency::Monthly,
+            1,
+            RecurrenceEnd::Never,
+        );
+
+        let due = rent.take_due(date("2022-04-30"));
+
+        assert_eq!(
+            dates(&due),
+            vec![
+                "2022-02-28T09:00:00-03",
+                "2022-03-31T09:00:00-03",
+                "2022-04-30T09:00:00-03",
+            ]
+        );
+        assert_eq!(rent.generated, 4);
+        assert_eq!(rent.next_occurrence(), Some(date("2022-05-31")));
+        assert!(rent.take_due(date("2022-04-30")).is_empty());
+    }
+
+    #[test]
+    fn intervals_and_units() {
+        let biweekly = rule(
+            "2022-07-04T12:00:00Z",
+            Frequency::Weekly,
+            2,
+            RecurrenceEnd::Never,
+        );
+        assert_eq!(biweekly.occurrence(2), Some(date("2022-08-01")));
+
+        let daily = rule(
+            "2022-07-04T12:00:00Z",
+            Frequency::Daily,
+            3,
+            RecurrenceEnd::Never,
+        );
+        assert_eq!(daily.occurrence(1), Some(date("2022-07-07")));
+
+        let yearly = rule(
+            "2024-02-29T12:00:00Z",
+            Frequency::Yearly,
+            1,
+            RecurrenceEnd::Never,
+        );
+        assert_eq!(yearly.occurrence(1), Some(date("2025-02-28")));
+        assert_eq!(yearly.occurrence(4), Some(date("2028-02-29")));
+    }
+
+    #[test]
+    fn end_conditions_stop_rule() {
+        let mut counted = rule(
+            "2022-01-10T12:00:00Z",
+            Frequency::Monthly,
+            1,
+            RecurrenceEnd::Count { occurrences: 3 },
+        );
+        assert_eq!(counted.take_due(date("2023-01-01")).len(), 2);
+        assert!(counted.is_finished());
+
+        let mut until = rule(
+            "2022-01-10T12:00:00Z",
+            Frequency::Monthly,
+            1,
+            RecurrenceEnd::Until {
+                date: date("2022-03-10"),
+            },
+        );
+        assert_eq!(until.take_due(date("2023-01-01")).len(), 2);
+        assert!(until.is_finished());
+    }
+
+    #[test]
+    fn paused_rules_skip_missed_occurrences() {
+        let mut rent = rule(
+            "2022-01-05T12:00:00Z",
+            Frequency::Monthly,
+            1,
+            RecurrenceEnd::Never,
+        );
+
+        rent.set_paused(true, date("2022-01-20"));
+        assert!(rent.take_due(date("2022-04-05")).is_empty());
+
+        rent.set_paused(false, date("2022-04-05"));
+        assert_eq!(
+            dates(&rent.take_due(date("2022-04-05"))),
+            vec!["2022-04-05T12:00:00Z"]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{Money, Transaction};
+
+/// Marks a transaction as one group's share of a receipt split across groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Split {
+    /// Shared by every share of the same receipt
+    pub receipt: Uuid,
+    /// Amount of the whole receipt, the sum of every share
+    pub total: Money,
+}
+
+/// Shares of `receipt` for each group in `shares`, in the same order.
+///
+/// Every share must be positive, groups may only appear once and the shares
+/// must add up to the amount of `receipt`.
+pub fn split_transaction(
+    receipt: &Transaction,
+    shares: &[(Uuid, Money)],
+) -> Result<Vec<(Uuid, Transaction)>> {
+    if shares.iter().any(|(_, amount)| !amount.is_positive()) {
+        bail!("Every share must be larger than zero");
+    }
+
+    for (index, (group, _)) in shares.iter().enumerate() {
+        if shares[..index].iter().any(|(other, _)| other == group) {
+            bail!("A group can only have one share of a transaction");
+        }
+    }
+
+    let sum: Money = shares.iter().map(|(_, amount)| *amount).sum();
+    if sum != receipt.amount {
+        bail!("Shares add up to {} instead of {}", sum, receipt.amount);
+    }
+
+    let split = Split {
+        receipt: Uuid::new_v4(),
+        total: receipt.amount,
+    };
+
+    Ok(shares
+        .iter()
+        .map(|(group, amount)| {
+            let share = Transaction {
+                id: Uuid::new_v4(),
+                amount: *amount,
+                split: Some(split),
+                ..receipt.clone()
+            };
+
+            (*group, share)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::money;
+    use crate::TransactionType;
+
+    fn receipt(amount: &str) -> Transaction {
+        Transaction::new(
+            "Supermarket",
+            TransactionType::EXPENSE,
+            amount.parse().unwrap(),
+            "2022-07-04T18:00:00-03",
+        )
+    }
+
+    #[test]
+    fn shares_keep_receipt_details() {
+        let groceries = Uuid::new_v4();
+        let pets = Uuid::new_v4();
+
+        let shares = split_transaction(
+            &receipt("120"),
+            &[(groceries, money("90")), (pets, money("30"))],
+        )
+        .unwrap();
+
+        assert_eq!(shares[0].0, groceries);
+        assert_eq!(shares[1].1.amount, money("30"));
+        assert_eq!(shares[1].1.name, "Supermarket");
+        assert_eq!(shares[0].1.split, shares[1].1.split);
+        assert_eq!(shares[0].1.split.unwrap().total, money("120"));
+        assert_ne!(shares[0].1.id, shares[1].1.id);
+    }
+
+    #[test]
+    fn shares_must_add_up() {
+        let groceries = Uuid::new_v4();
+        let pets = Uuid::new_v4();
+
+        assert!(split_transaction(
+            &receipt("120"),
+            &[(groceries, money("90")), (pets, money("20"))]
+        )
+        .is_err());
+        assert!(split_transaction(
+            &receipt("120"),
+            &[(groceries, money("130")), (pets, money("-10"))]
+        )
+        .is_err());
+        assert!(split_transaction(
+            &receipt("120"),
+            &[(groceries, money("90")), (groceries, money("30"))]
+        )
+        .is_err());
+    }
+}
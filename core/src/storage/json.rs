@@ -0,0 +1,144 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Error, Result};
+use uuid::Uuid;
+
+use crate::migrations::is_newer_schema;
+use crate::storage::{LoadProblem, Storage};
+use crate::{DataObject, Group, Transaction};
+
+/// Directory, inside the save directory, holding one file per group
+pub const GROUPS_DIR: &str = "groups";
+
+/// Directory, next to `groups`, where unreadable group files are moved
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+/// Stores each group with its transactions as `groups/<uuid>.json`
+#[derive(Debug)]
+pub struct JsonStorage {
+    save_path: PathBuf,
+}
+
+impl JsonStorage {
+    pub fn new(save_path: &Path) -> Self {
+        Self {
+            save_path: save_path.to_owned(),
+        }
+    }
+
+    fn groups_path(&self) -> PathBuf {
+        self.save_path.join(GROUPS_DIR)
+    }
+
+    /// Whether there is at least one group file to load
+    pub fn has_groups(&self) -> bool {
+        fs::read_dir(self.groups_path())
+            .map(|mut entries| {
+                entries.any(|entry| {
+                    entry.is_ok_and(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    /// Move unreadable file out of the groups directory so it is not loaded again
+    fn quarantine_file(&self, path: &Path) -> Result<PathBuf> {
+        let quarantine_dir = self.save_path.join(QUARANTINE_DIR);
+        fs::create_dir_all(&quarantine_dir)?;
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("{} is not a file", path.display()))?
+            .to_string_lossy()
+            .to_string();
+
+        let mut destination = quarantine_dir.join(&file_name);
+        if destination.exists() {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            destination = quarantine_dir.join(format!("{}.{}", file_name, timestamp));
+        }
+
+        fs::rename(path, &destination)?;
+
+        Ok(destination)
+    }
+}
+
+impl Storage for JsonStorage {
+    /// Loads all groups from path, quarantining files that fail to load.
+    /// Files from newer versions of Beedget are reported but left in place.
+    fn load_groups(&self) -> Result<(Vec<Group>, Vec<LoadProblem>)> {
+        let mut loaded_groups: Vec<Group> = vec![];
+        let mut problems: Vec<LoadProblem> = vec![];
+
+        match fs::read_dir(self.groups_path()) {
+            Ok(group_files) => {
+                for file in group_files {
+                    let path = match file {
+                        Ok(entry) => entry.path(),
+                        Err(error) => {
+                            problems.push(LoadProblem {
+                                path: self.groups_path(),
+                                error: Error::new(error),
+                                quarantined_to: None,
+                            });
+                            continue;
+                        }
+                    };
+
+                    // Skip backups, temporary files and anything else that is not a group
+                    if !path.is_file() || path.extension().is_none_or(|ext| ext != "json") {
+                        continue;
+                    }
+
+                    match Group::load_from_file_or_backup(&path) {
+                        Ok(group) => loaded_groups.push(group),
+                        Err(error) if is_newer_schema(&error) => problems.push(LoadProblem {
+                            path,
+                            error,
+                            quarantined_to: None,
+                        }),
+                        Err(error) => {
+                            let quarantined_to = self.quarantine_file(&path).ok();
+                            problems.push(LoadProblem {
+                                path,
+                                error,
+                                quarantined_to,
+                            });
+                        }
+                    }
+                }
+
+                Ok((loaded_groups, problems))
+            }
+            Err(error) => match error.kind() {
+                ErrorKind::NotFound => {
+                    fs::create_dir_all(self.groups_path())?;
+                    Ok((vec![], vec![]))
+                }
+                _ => Err(Error::new(error)),
+            },
+        }
+    }
+
+    fn save_group(&self, group: &Group) -> Result<()> {
+        fs::create_dir_all(self.groups_path())?;
+        group.save_to_file(self.groups_path().as_path())
+    }
+
+    fn delete_group(&self, group: &Group) -> Result<()> {
+        group.delete_file(self.groups_path().as_path())
+    }
+
+    /// Transactions live inside the group file, so the whole group is rewritten
+    fn save_transaction(&self, group: &Group, _transaction: &Transaction) -> Result<()> {
+        self.save_group(group)
+    }
+
+    fn delete_transaction(&self, group: &Group, _transaction_id: Uuid) -> Result<()> {
+        self.save_group(group)
+    }
+}
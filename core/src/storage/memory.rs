@@ -0,0 +1,99 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::storage::{LoadProblem, Storage};
+use crate::{Group, Transaction};
+
+/// Keeps group documents in memory, without touching the filesystem.
+///
+/// Groups go through the same JSON documents as the other backends, so
+/// loading after saving exercises serialization. Clones share contents,
+/// which lets a copy be kept to inspect what was stored.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    groups: Rc<RefCell<Vec<(Uuid, Value)>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stored document of group `id`
+    pub fn group_document(&self, id: Uuid) -> Option<Value> {
+        self.groups
+            .borrow()
+            .iter()
+            .find(|(group_id, _)| *group_id == id)
+            .map(|(_, doc)| doc.clone())
+    }
+
+    /// Number of stored groups
+    pub fn len(&self) -> usize {
+        self.groups.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.borrow().is_empty()
+    }
+
+    /// Store a raw document, e.g. one written with an older schema
+    pub fn insert_document(&self, id: Uuid, doc: Value) {
+        let mut groups = self.groups.borrow_mut();
+
+        match groups.iter_mut().find(|(group_id, _)| *group_id == id) {
+            Some((_, stored)) => *stored = doc,
+            None => groups.push((id, doc)),
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load_groups(&self) -> Result<(Vec<Group>, Vec<LoadProblem>)> {
+        let mut loaded_groups: Vec<Group> = vec![];
+        let mut problems: Vec<LoadProblem> = vec![];
+
+        for (id, doc) in self.groups.borrow().iter() {
+            match Group::from_document(doc.clone()) {
+                Ok(group) => loaded_groups.push(group),
+                Err(error) => problems.push(LoadProblem {
+                    path: Default::default(),
+                    error: error.context(format!("Group {}", id)),
+                    quarantined_to: None,
+                }),
+            }
+        }
+
+        Ok((loaded_groups, problems))
+    }
+
+    fn save_group(&self, group: &Group) -> Result<()> {
+        self.insert_document(group.id, group.to_document()?);
+
+        Ok(())
+    }
+
+    fn delete_group(&self, group: &Group) -> Result<()> {
+        let mut groups = self.groups.borrow_mut();
+        let len = groups.len();
+        groups.retain(|(id, _)| *id != group.id);
+
+        if groups.len() == len {
+            return Err(anyhow!("Group {} is not stored", group.id));
+        }
+
+        Ok(())
+    }
+
+    fn save_transaction(&self, group: &Group, _transaction: &Transaction) -> Result<()> {
+        self.save_group(group)
+    }
+
+    fn delete_transaction(&self, group: &Group, _transaction_id: Uuid) -> Result<()> {
+        self.save_group(group)
+    }
+}
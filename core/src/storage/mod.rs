@@ -0,0 +1,302 @@
+pub mod json;
+pub mod memory;
+pub mod sqlite;
+
+pub use json::JsonStorage;
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
+
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Error, Result};
+use uuid::Uuid;
+
+use crate::exchange_rates::EXCHANGE_RATES_FILE;
+use crate::import::csv::IMPORT_PROFILES_FILE;
+use crate::migrations::BACKUP_DIR;
+use crate::{Group, Transaction};
+
+/// Files and directories that hold data inside a save directory
+const DATA_ENTRIES: &[&str] = &[
+    json::GROUPS_DIR,
+    sqlite::DATABASE_FILE,
+    EXCHANGE_RATES_FILE,
+    IMPORT_PROFILES_FILE,
+    BACKUP_DIR,
+    json::QUARANTINE_DIR,
+];
+
+/// Item that could not be loaded from storage
+#[derive(Debug)]
+pub struct LoadProblem {
+    /// File or database the item was read from
+    pub path: PathBuf,
+    pub error: Error,
+    /// New location of the file, if it was moved out of the data directory
+    pub quarantined_to: Option<PathBuf>,
+}
+
+/// Persistence backend for groups and their transactions
+pub trait Storage: Debug {
+    /// Load every group with its transactions. Items that cannot be read are
+    /// skipped and returned as problems instead of failing the whole load.
+    fn load_groups(&self) -> Result<(Vec<Group>, Vec<LoadProblem>)>;
+
+    /// Insert or update group, including all of its transactions
+    fn save_group(&self, group: &Group) -> Result<()>;
+
+    /// Remove group and all of its transactions
+    fn delete_group(&self, group: &Group) -> Result<()>;
+
+    /// Insert or update a single transaction of `group`
+    fn save_transaction(&self, group: &Group, transaction: &Transaction) -> Result<()>;
+
+    /// Remove transaction that was already taken out of `group`
+    fn delete_transaction(&self, group: &Group, transaction_id: Uuid) -> Result<()>;
+}
+
+/// Backends that can be selected with the `storage-backend` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    Json,
+    Sqlite,
+}
+
+impl StorageBackend {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "sqlite" => StorageBackend::Sqlite,
+            _ => StorageBackend::Json,
+        }
+    }
+}
+
+/// Copy every group from `source` into `destination`.
+///
+/// Returns the number of imported groups and the items that could not be read.
+pub fn import_groups(
+    source: &dyn Storage,
+    destination: &dyn Storage,
+) -> Result<(usize, Vec<LoadProblem>)> {
+    let (groups, problems) = source.load_groups()?;
+
+    for group in groups.iter() {
+        destination.save_group(group)?;
+    }
+
+    Ok((groups.len(), problems))
+}
+
+/// Open selected backend in `save_path`, along with problems found while opening.
+///
+/// A new SQLite database imports existing JSON group files once, so groups
+/// deleted afterwards don't come back; if it cannot be opened, JSON files are
+/// used instead.
+pub fn open(save_path: &Path, backend: StorageBackend) -> (Box<dyn Storage>, Vec<LoadProblem>) {
+    let json_storage = JsonStorage::new(save_path);
+
+    if backend == StorageBackend::Json {
+        return (Box::new(json_storage), vec![]);
+    }
+
+    let mut problems = vec![];
+    let imported = SqliteStorage::open(save_path).and_then(|sqlite_storage| {
+        if !sqlite_storage.json_imported()? {
+            if json_storage.has_groups() {
+                let (_, import_problems) = import_groups(&json_storage, &sqlite_storage)?;
+                problems.extend(import_problems);
+            }
+            sqlite_storage.set_json_imported()?;
+        }
+
+        Ok(sqlite_storage)
+    });
+
+    match imported {
+        Ok(sqlite_storage) => (Box::new(sqlite_storage), problems),
+        Err(error) => {
+            problems.push(LoadProblem {
+                path: save_path.join(sqlite::DATABASE_FILE),
+                error: error.context("Falling back to JSON files"),
+                quarantined_to: None,
+            });
+            (Box::new(json_storage), problems)
+        }
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+
+    Ok(())
+}
+
+/// Copy all data in save directory `from` to `to`.
+///
+/// Fails without copying anything if `to` already has data of its own, and
+/// removes what was copied if a copy fails halfway.
+pub fn copy_data(from: &Path, to: &Path) -> Result<()> {
+    if let Some(existing) = DATA_ENTRIES.iter().find(|entry| to.join(entry).exists()) {
+        bail!("{} already contains {}", to.display(), existing);
+    }
+
+    fs::create_dir_all(to)?;
+
+    for entry in DATA_ENTRIES {
+        let source = from.join(entry);
+        if !source.exists() {
+            continue;
+        }
+
+        if let Err(error) = copy_recursive(&source, &to.join(entry)) {
+            let _ = remove_data(to);
+            return Err(Error::new(error).context(format!("Could not copy {}", source.display())));
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove all data from a save directory, leaving unrelated files in place
+pub fn remove_data(path: &Path) -> Result<()> {
+    for entry in DATA_ENTRIES {
+        let target = path.join(entry);
+
+        if target.is_dir() {
+            fs::remove_dir_all(&target)?;
+        } else if target.exists() {
+            fs::remove_file(&target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Currency, TransactionType};
+
+    /// Empty directory that is removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!("beedget-test-{}", Uuid::new_v4()));
+            fs::create_dir_all(&path).unwrap();
+
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn group() -> Group {
+        let mut group = Group::new(
+            "💰",
+            [0.2, 0.4, 0.6, 1.0],
+            "Wallet",
+            Currency::from_code("EUR").unwrap(),
+        );
+        group.new_transaction(Transaction::new(
+            "Groceries",
+            TransactionType::EXPENSE,
+            "12.50".parse().unwrap(),
+            "2022-07-04T12:30:00Z",
+        ));
+
+        group
+    }
+
+    #[test]
+    fn sqlite_imports_json_groups() {
+        let dir = TempDir::new();
+        let group = group();
+        JsonStorage::new(&dir.0).save_group(&group).unwrap();
+
+        let (storage, problems) = open(&dir.0, StorageBackend::Sqlite);
+        let (groups, load_problems) = storage.load_groups().unwrap();
+
+        assert!(problems.is_empty());
+        assert!(load_problems.is_empty());
+        assert_eq!(groups, vec![group]);
+    }
+
+    #[test]
+    fn json_groups_are_imported_once() {
+        let dir = TempDir::new();
+        let group = group();
+        JsonStorage::new(&dir.0).save_group(&group).unwrap();
+
+        let (storage, _) = open(&dir.0, StorageBackend::Sqlite);
+        storage.delete_group(&group).unwrap();
+        drop(storage);
+
+        let (storage, _) = open(&dir.0, StorageBackend::Sqlite);
+        assert!(storage.load_groups().unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn newer_group_files_stay_in_place() {
+        let dir = TempDir::new();
+        let group = group();
+        let mut doc = group.to_document().unwrap();
+        doc["schema_version"] = (crate::migrations::GROUP_SCHEMA_VERSION + 1).into();
+
+        let path = dir
+            .0
+            .join(json::GROUPS_DIR)
+            .join(format!("{}.json", group.id));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, doc.to_string()).unwrap();
+
+        let (groups, problems) = JsonStorage::new(&dir.0).load_groups().unwrap();
+
+        assert!(groups.is_empty());
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].quarantined_to.is_none());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn copied_data_loads_from_new_directory() {
+        let from = TempDir::new();
+        let to = TempDir::new();
+        let group = group();
+        JsonStorage::new(&from.0).save_group(&group).unwrap();
+
+        copy_data(&from.0, &to.0).unwrap();
+        remove_data(&from.0).unwrap();
+
+        let (groups, _) = JsonStorage::new(&to.0).load_groups().unwrap();
+        assert_eq!(groups, vec![group]);
+        assert!(!from.0.join(json::GROUPS_DIR).exists());
+    }
+
+    #[test]
+    fn copy_refuses_to_mix_data() {
+        let from = TempDir::new();
+        let to = TempDir::new();
+        JsonStorage::new(&from.0).save_group(&group()).unwrap();
+        JsonStorage::new(&to.0).save_group(&group()).unwrap();
+
+        assert!(copy_data(&from.0, &to.0).is_err());
+        assert_eq!(JsonStorage::new(&to.0).load_groups().unwrap().0.len(), 1);
+    }
+}
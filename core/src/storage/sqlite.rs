@@ -0,0 +1,249 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::migrations::{schema_version, GROUP_SCHEMA_VERSION};
+use crate::storage::{LoadProblem, Storage};
+use crate::{Group, Transaction};
+
+/// Database file, stored next to the `groups` directory
+pub const DATABASE_FILE: &str = "beedget.db";
+
+/// Version of the table layout, kept in `PRAGMA user_version`
+const DATABASE_VERSION: i64 = 2;
+
+/// Metadata key set once the JSON group files have been imported
+const JSON_IMPORTED_KEY: &str = "json-imported";
+
+/// Stores groups and transactions as rows of a SQLite database.
+///
+/// Row contents are the same JSON documents used by the JSON backend, so
+/// the schema migrations in `migrations` apply to both. Single transactions
+/// are inserted, updated and deleted without touching the rest of the group.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    path: PathBuf,
+    connection: Connection,
+}
+
+impl SqliteStorage {
+    /// Open database in the save directory, creating tables if needed
+    pub fn open(save_path: &Path) -> Result<Self> {
+        let path = save_path.join(DATABASE_FILE);
+        let connection = Connection::open(&path)?;
+
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+
+        let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > DATABASE_VERSION {
+            return Err(anyhow!(
+                "{} was created by a newer version of Beedget",
+                path.display()
+            ));
+        }
+
+        if version < 1 {
+            connection.execute_batch(
+                "BEGIN;
+                CREATE TABLE IF NOT EXISTS groups (
+                    id TEXT PRIMARY KEY NOT NULL,
+                    data TEXT NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS transactions (
+                    id TEXT PRIMARY KEY NOT NULL,
+                    group_id TEXT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+                    data TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS transactions_group_id ON transactions(group_id);
+                PRAGMA user_version = 1;
+                COMMIT;",
+            )?;
+        }
+
+        if version < 2 {
+            // Databases that already hold groups were filled from the JSON
+            // files when they were created
+            connection.execute_batch(
+                "BEGIN;
+                CREATE TABLE IF NOT EXISTS metadata (
+                    key TEXT PRIMARY KEY NOT NULL,
+                    value TEXT NOT NULL
+                );
+                INSERT OR IGNORE INTO metadata (key, value)
+                    SELECT 'json-imported', '1' WHERE EXISTS (SELECT 1 FROM groups);
+                PRAGMA user_version = 2;
+                COMMIT;",
+            )?;
+        }
+
+        Ok(Self { path, connection })
+    }
+
+    /// Whether the JSON group files were already imported into the database
+    pub fn json_imported(&self) -> Result<bool> {
+        let found: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT value FROM metadata WHERE key = ?1",
+                params![JSON_IMPORTED_KEY],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(found.is_some())
+    }
+
+    /// Record that the JSON group files were imported, so they never are again
+    pub fn set_json_imported(&self) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR IGNORE INTO metadata (key, value) VALUES (?1, '1')",
+            params![JSON_IMPORTED_KEY],
+        )?;
+
+        Ok(())
+    }
+
+    fn upsert_transaction(
+        connection: &Connection,
+        group_id: Uuid,
+        transaction: &Transaction,
+    ) -> Result<()> {
+        connection.execute(
+            "INSERT INTO transactions (id, group_id, data) VALUES (?1, ?2, ?3)
+            ON CONFLICT(id) DO UPDATE SET group_id = excluded.group_id, data = excluded.data",
+            params![
+                transaction.id.to_string(),
+                group_id.to_string(),
+                serde_json::to_string(transaction)?
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Assemble the group document stored in row `id` with its transactions
+    fn group_document(&self, id: &str, data: &str) -> Result<Value> {
+        let mut doc: Value = serde_json::from_str(data)?;
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT data FROM transactions WHERE group_id = ?1 ORDER BY rowid")?;
+        let transactions = statement
+            .query_map(params![id], |row| row.get::<_, String>(0))?
+            .map(|data| Ok(serde_json::from_str::<Value>(&data?)?))
+            .collect::<Result<Vec<Value>>>()?;
+
+        doc.as_object_mut()
+            .ok_or_else(|| anyhow!("Group {} is not an object", id))?
+            .insert(String::from("transactions"), Value::Array(transactions));
+
+        Ok(doc)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load_groups(&self) -> Result<(Vec<Group>, Vec<LoadProblem>)> {
+        let mut loaded_groups: Vec<Group> = vec![];
+        let mut problems: Vec<LoadProblem> = vec![];
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT id, data FROM groups ORDER BY rowid")?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+
+        for (id, data) in rows {
+            let loaded = self.group_document(&id, &data).and_then(|doc| {
+                let outdated = schema_version(&doc)? < GROUP_SCHEMA_VERSION;
+                let group = Group::from_document(doc)?;
+
+                // Store upgraded documents so migrations only run once
+                if outdated {
+                    self.save_group(&group)?;
+                }
+
+                Ok(group)
+            });
+
+            match loaded {
+                Ok(group) => loaded_groups.push(group),
+                Err(error) => problems.push(LoadProblem {
+                    path: self.path.clone(),
+                    error: error.context(format!("Group {}", id)),
+                    quarantined_to: None,
+                }),
+            }
+        }
+
+        Ok((loaded_groups, problems))
+    }
+
+    fn save_group(&self, group: &Group) -> Result<()> {
+        let mut doc = group.to_document()?;
+        doc.as_object_mut()
+            .ok_or_else(|| anyhow!("Group document is not an object"))?
+            .remove("transactions");
+
+        let tx = self.connection.unchecked_transaction()?;
+
+        tx.execute(
+            "INSERT INTO groups (id, data) VALUES (?1, ?2)
+            ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+            params![group.id.to_string(), serde_json::to_string(&doc)?],
+        )?;
+
+        // Drop rows of transactions no longer in the group
+        let transaction_ids: Vec<String> = group
+            .transactions
+            .iter()
+            .map(|transaction| transaction.id.to_string())
+            .collect();
+        let mut statement = tx.prepare("SELECT id FROM transactions WHERE group_id = ?1")?;
+        let stored_ids = statement
+            .query_map(params![group.id.to_string()], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        drop(statement);
+
+        for stored_id in stored_ids {
+            if !transaction_ids.contains(&stored_id) {
+                tx.execute("DELETE FROM transactions WHERE id = ?1", params![stored_id])?;
+            }
+        }
+
+        for transaction in group.transactions.iter() {
+            SqliteStorage::upsert_transaction(&tx, group.id, transaction)?;
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn delete_group(&self, group: &Group) -> Result<()> {
+        self.connection.execute(
+            "DELETE FROM groups WHERE id = ?1",
+            params![group.id.to_string()],
+        )?;
+
+        Ok(())
+    }
+
+    fn save_transaction(&self, group: &Group, transaction: &Transaction) -> Result<()> {
+        SqliteStorage::upsert_transaction(&self.connection, group.id, transaction)
+    }
+
+    fn delete_transaction(&self, _group: &Group, transaction_id: Uuid) -> Result<()> {
+        self.connection.execute(
+            "DELETE FROM transactions WHERE id = ?1",
+            params![transaction_id.to_string()],
+        )?;
+
+        Ok(())
+    }
+}
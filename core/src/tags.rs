@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+
+use crate::{DateRange, Money, Transaction, TransactionType};
+
+/// Form a tag is stored in: lowercase, with inner whitespace replaced by
+/// dashes, e.g. "Trip 2026" becomes "trip-2026". `None` if nothing is left.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Tags of comma separated `text`, normalized and without repeats
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+
+    for tag in text.split(',').filter_map(normalize_tag) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+/// Income and expenses of the transactions carrying a tag
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TagTotal {
+    pub income: Money,
+    /// Sum of expense amounts, as a positive value
+    pub expenses: Money,
+    /// Number of transactions counted
+    pub count: usize,
+}
+
+impl AddAssign for TagTotal {
+    fn add_assign(&mut self, other: Self) {
+        self.income += other.income;
+        self.expenses += other.expenses;
+        self.count += other.count;
+    }
+}
+
+/// Totals of every tag used by `transactions` within `period`, or ever
+/// without one. Amounts are summed as they are, so all transactions must
+/// share one currency. Transfers are left out, like in group summaries.
+pub fn totals_by_tag<'a>(
+    transactions: impl IntoIterator<Item = &'a Transaction>,
+    period: Option<DateRange>,
+) -> BTreeMap<String, TagTotal> {
+    let mut totals: BTreeMap<String, TagTotal> = BTreeMap::new();
+
+    for transaction in transactions {
+        if let (Some(period), Some(day)) = (period, transaction.day()) {
+            if !period.contains(day) {
+                continue;
+            }
+        }
+
+        let total = match transaction.tr_type {
+            TransactionType::INCOME => TagTotal {
+                income: transaction.amount,
+                count: 1,
+                ..Default::default()
+            },
+            TransactionType::EXPENSE => TagTotal {
+                expenses: transaction.amount,
+                count: 1,
+                ..Default::default()
+            },
+            TransactionType::TRANSFER => continue,
+        };
+
+        for tag in &transaction.tags {
+            *totals.entry(tag.clone()).or_default() += total;
+        }
+    }
+
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Date;
+
+    fn tagged(tr_type: TransactionType, amount: &str, day: &str, tags: &str) -> Transaction {
+        let date = format!("{}T12:00:00", day);
+
+        Transaction {
+            tags: parse_tags(tags),
+            ..Transaction::new("Lunch", tr_type, amount.parse().unwrap(), &date)
+        }
+    }
+
+    #[test]
+    fn tags_are_normalized() {
+        assert_eq!(
+            normalize_tag("  Trip   2026 "),
+            Some(String::from("trip-2026"))
+        );
+        assert_eq!(normalize_tag(" "), None);
+        assert_eq!(
+            parse_tags("Work, reimbursable,, work ,trip 2026"),
+            vec!["work", "reimbursable", "trip-2026"]
+        );
+    }
+
+    #[test]
+    fn totals_count_each_tag() {
+        let transactions = [
+            tagged(
+                TransactionType::EXPENSE,
+                "30",
+                "2022-07-04",
+                "work, reimbursable",
+            ),
+            tagged(TransactionType::EXPENSE, "12.5", "2022-07-10", "work"),
+            tagged(TransactionType::INCOME, "30", "2022-08-01", "reimbursable"),
+            tagged(TransactionType::TRANSFER, "100", "2022-07-05", "work"),
+            tagged(TransactionType::EXPENSE, "8", "2022-07-06", ""),
+        ];
+
+        let totals = totals_by_tag(&transactions, None);
+        assert_eq!(totals.len(), 2);
+        assert_eq!(
+            totals["work"],
+            TagTotal {
+                income: Money::ZERO,
+                expenses: "42.5".parse().unwrap(),
+                count: 2,
+            }
+        );
+        assert_eq!(totals["reimbursable"].income, "30".parse().unwrap());
+        assert_eq!(totals["reimbursable"].count, 2);
+
+        let july = DateRange::month_of(Date::new(2022, 7, 1).unwrap());
+        let totals = totals_by_tag(&transactions, Some(july));
+        assert_eq!(totals["reimbursable"].income, Money::ZERO);
+        assert_eq!(totals["reimbursable"].count, 1);
+    }
+}
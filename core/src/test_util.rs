@@ -0,0 +1,21 @@
+//! Fixtures shared by the unit tests
+
+use crate::{Currency, Group, Money, Transaction, TransactionType};
+
+pub fn money(text: &str) -> Money {
+    text.parse().unwrap()
+}
+
+pub fn group(name: &str) -> Group {
+    Group::new(
+        "💰",
+        [0.2, 0.4, 0.6, 1.0],
+        name,
+        Currency::from_code("BRL").unwrap(),
+    )
+}
+
+/// Transaction named Groceries on 2022-07-04
+pub fn transaction(tr_type: TransactionType, amount: &str) -> Transaction {
+    Transaction::new("Groceries", tr_type, money(amount), "2022-07-04T12:30:00Z")
+}
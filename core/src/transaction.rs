@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{Date, Installment, Money, Split, Transfer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionType {
+    EXPENSE,
+    INCOME,
+    /// Money moved between two groups, see `Transaction::transfer`
+    TRANSFER,
+}
+
+pub fn transaction_type_to_string(tr_type: &TransactionType) -> String {
+    match tr_type {
+        TransactionType::EXPENSE => String::from("EXPENSE"),
+        TransactionType::INCOME => String::from("INCOME"),
+        TransactionType::TRANSFER => String::from("TRANSFER"),
+    }
+}
+
+pub fn transaction_type_from_string(tr_str: &str) -> TransactionType {
+    match tr_str {
+        "EXPENSE" => TransactionType::EXPENSE,
+        "INCOME" => TransactionType::INCOME,
+        "TRANSFER" => TransactionType::TRANSFER,
+        _ => unimplemented!(),
+    }
+}
+
+/// Single income or expense entry of a group.
+///
+/// `date` is an ISO 8601 date and time, kept as written by the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub id: Uuid,
+    pub name: String,
+    pub tr_type: TransactionType,
+    pub amount: Money,
+    pub date: String,
+    /// Set when the transaction is one of several paying for a purchase
+    pub installment: Option<Installment>,
+    /// Link to the other side when this is a transfer
+    pub transfer: Option<Transfer>,
+    /// Set when the transaction is this group's share of a larger one
+    pub split: Option<Split>,
+    /// Free-form labels in the form of `tags::normalize_tag`
+    pub tags: Vec<String>,
+    /// Who was paid or who paid, e.g. a shop or an employer
+    pub payee: Option<String>,
+    pub location: Option<String>,
+    /// Free-form text, may span several lines
+    pub notes: Option<String>,
+    /// Identifier given by the bank to imported transactions, such as the
+    /// OFX `FITID`, so importing the same statement again skips them
+    pub import_id: Option<String>,
+}
+
+impl Default for Transaction {
+    fn default() -> Self {
+        Self {
+            id: Uuid::nil(),
+            name: String::new(),
+            tr_type: TransactionType::EXPENSE,
+            amount: Money::ZERO,
+            date: String::new(),
+            installment: None,
+            transfer: None,
+            split: None,
+            tags: vec![],
+            payee: None,
+            location: None,
+            notes: None,
+            import_id: None,
+        }
+    }
+}
+
+impl Transaction {
+    pub fn new(name: &str, tr_type: TransactionType, amount: Money, date: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            tr_type,
+            amount,
+            date: date.to_string(),
+            installment: None,
+            transfer: None,
+            split: None,
+            tags: vec![],
+            payee: None,
+            location: None,
+            notes: None,
+            import_id: None,
+        }
+    }
+
+    /// Amount with sign applied: expenses and outgoing transfers are
+    /// negative, incomes and incoming transfers positive
+    pub fn signed_amount(&self) -> Money {
+        match self.tr_type {
+            TransactionType::EXPENSE => -self.amount,
+            TransactionType::INCOME => self.amount,
+            TransactionType::TRANSFER if self.is_incoming() => self.amount,
+            TransactionType::TRANSFER => -self.amount,
+        }
+    }
+
+    /// Whether this is the receiving side of a transfer
+    pub fn is_incoming(&self) -> bool {
+        self.transfer.is_some_and(|transfer| transfer.incoming)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own == tag)
+    }
+
+    /// Whether `query` is part of the name, payee, location, notes or a tag,
+    /// ignoring case
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+
+        std::iter::once(&self.name)
+            .chain(&self.payee)
+            .chain(&self.location)
+            .chain(&self.notes)
+            .chain(&self.tags)
+            .any(|text| text.to_lowercase().contains(&query))
+    }
+
+    /// Calendar date of the transaction, `None` if `date` is malformed
+    pub fn day(&self) -> Option<Date> {
+        Date::parse_iso(&self.date).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::transaction;
+
+    #[test]
+    fn expense_is_negative() {
+        let expense = transaction(TransactionType::EXPENSE, "12.50");
+
+        assert_eq!(expense.amount, "12.50".parse().unwrap());
+        assert_eq!(expense.signed_amount(), "-12.50".parse().unwrap());
+    }
+
+    #[test]
+    fn income_is_positive() {
+        let income = transaction(TransactionType::INCOME, "12.50");
+
+        assert_eq!(income.signed_amount(), "12.50".parse().unwrap());
+    }
+
+    #[test]
+    fn signed_amount_follows_changes() {
+        let mut transaction = transaction(TransactionType::EXPENSE, "10");
+
+        transaction.amount = "0.3".parse().unwrap();
+        assert_eq!(transaction.signed_amount(), "-0.3".parse().unwrap());
+
+        transaction.tr_type = TransactionType::INCOME;
+        assert_eq!(transaction.signed_amount(), "0.3".parse().unwrap());
+    }
+
+    #[test]
+    fn signed_amounts_add_up_exactly() {
+        let transactions = [
+            transaction(TransactionType::INCOME, "0.1"),
+            transaction(TransactionType::INCOME, "0.2"),
+            transaction(TransactionType::EXPENSE, "0.3"),
+        ];
+
+        let total: Money = transactions.iter().map(|t| t.signed_amount()).sum();
+
+        assert!(total.is_zero());
+    }
+
+    #[test]
+    fn search_looks_at_every_detail() {
+        let transaction = Transaction {
+            payee: Some(String::from("Corner Bakery")),
+            location: Some(String::from("Porto Alegre")),
+            notes: Some(String::from("Birthday cake\nPaid half")),
+            tags: vec![String::from("party")],
+            ..transaction(TransactionType::EXPENSE, "40")
+        };
+
+        for query in ["groceries", "BAKERY", "alegre", "paid half", "part"] {
+            assert!(transaction.matches(query), "{} should match", query);
+        }
+        assert!(!transaction.matches("pharmacy"));
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let original = transaction(TransactionType::INCOME, "1234.5678");
+
+        let serialized = serde_json::to_string(&original).unwrap();
+        let restored: Transaction = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(restored, original);
+        assert_eq!(serde_json::to_string(&restored).unwrap(), serialized);
+    }
+}
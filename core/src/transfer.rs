@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{Money, Transaction, TransactionType};
+
+/// Link from one side of a transfer to the other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transfer {
+    /// Group holding the other side
+    pub group: Uuid,
+    /// Transaction on the other side
+    pub counterpart: Uuid,
+    /// Whether money comes into the group holding this side
+    pub incoming: bool,
+}
+
+/// Linked pair of transactions moving money from group `from` to group `to`.
+///
+/// `received` is what arrives at `to`, which differs from `amount` when the
+/// groups use different currencies. Returns the outgoing side first.
+pub fn transfer_pair(
+    name: &str,
+    amount: Money,
+    received: Money,
+    date: &str,
+    from: Uuid,
+    to: Uuid,
+) -> (Transaction, Transaction) {
+    let mut outgoing = Transaction::new(name, TransactionType::TRANSFER, amount, date);
+    let mut incoming = Transaction::new(name, TransactionType::TRANSFER, received, date);
+
+    outgoing.transfer = Some(Transfer {
+        group: to,
+        counterpart: incoming.id,
+        incoming: false,
+    });
+    incoming.transfer = Some(Transfer {
+        group: from,
+        counterpart: outgoing.id,
+        incoming: true,
+    });
+
+    (outgoing, incoming)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sides_point_at_each_other() {
+        let savings = Uuid::new_v4();
+        let vacation = Uuid::new_v4();
+        let amount: Money = "200".parse().unwrap();
+
+        let (outgoing, incoming) = transfer_pair(
+            "Trip",
+            amount,
+            amount,
+            "2022-07-04T12:00:00Z",
+            savings,
+            vacation,
+        );
+
+        let out_link = outgoing.transfer.unwrap();
+        let in_link = incoming.transfer.unwrap();
+        assert_eq!(
+            (out_link.group, out_link.counterpart),
+            (vacation, incoming.id)
+        );
+        assert_eq!((in_link.group, in_link.counterpart), (savings, outgoing.id));
+
+        assert_eq!(outgoing.signed_amount(), -amount);
+        assert_eq!(incoming.signed_amount(), amount);
+    }
+}
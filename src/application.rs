@@ -8,15 +8,98 @@ use gtk::subclass::prelude::*;
 use gtk::{gdk, gio, glib};
 
 use crate::config::VERSION;
-use crate::models::{Group, SaveData};
+use crate::dialogs::PreferencesWindow;
+use crate::models::currency::DEFAULT_CURRENCY;
+use crate::models::{
+    Currency, Date, Group, NumberFormat, SaveData, StorageBackend, Transaction, Weekday,
+};
 use crate::BeedgetWindow;
 
-pub static CLOCK_FORMAT: Lazy<String> = Lazy::new(|| {
+/// Clock format of the desktop, used unless overridden in preferences
+static DESKTOP_CLOCK_FORMAT: Lazy<String> = Lazy::new(|| {
     let desktop_settings = gio::Settings::new("org.gnome.desktop.interface");
 
     desktop_settings.string("clock-format").to_string()
 });
 
+/// Territories where weeks start on Sunday
+const SUNDAY_FIRST_TERRITORIES: &[&str] = &[
+    "AG", "AS", "BR", "BS", "BT", "BW", "BZ", "CA", "CO", "DM", "DO", "ET", "GT", "GU", "HK", "HN",
+    "ID", "IL", "IN", "JM", "JP", "KE", "KH", "KR", "LA", "MH", "MM", "MO", "MT", "MX", "MZ", "NI",
+    "NP", "PA", "PE", "PH", "PK", "PR", "PT", "PY", "SA", "SG", "SV", "TH", "TT", "TW", "UM", "US",
+    "VE", "VI", "WS", "YE", "ZA", "ZW",
+];
+
+/// Running application, for code that has no widget to reach it through
+pub fn instance() -> Option<BeedgetApplication> {
+    gio::Application::default()?.downcast().ok()
+}
+
+/// Settings of the running application
+pub fn settings() -> Option<gio::Settings> {
+    instance()?.imp().settings.get().cloned()
+}
+
+/// `"12h"` or `"24h"`, as chosen in preferences or set for the desktop
+pub fn clock_format() -> String {
+    match settings().map(|settings| settings.string("clock-format")) {
+        Some(format) if format != "system" => format.to_string(),
+        _ => DESKTOP_CLOCK_FORMAT.clone(),
+    }
+}
+
+pub fn number_format() -> NumberFormat {
+    settings()
+        .map(|settings| NumberFormat::from_setting(&settings.string("number-format")))
+        .unwrap_or_default()
+}
+
+/// Currency preselected for new groups
+pub fn default_currency() -> &'static Currency {
+    let code = settings()
+        .map(|settings| settings.string("default-currency").to_string())
+        .unwrap_or_else(|| String::from(DEFAULT_CURRENCY));
+
+    Currency::from_code_or_default(&code)
+}
+
+/// Day weeks start on. Follows the territory of the time locale unless set in preferences.
+pub fn first_day_of_week() -> Weekday {
+    match settings().map(|settings| settings.string("first-day-of-week")) {
+        Some(day) if day == "monday" => Weekday::Monday,
+        Some(day) if day == "sunday" => Weekday::Sunday,
+        Some(day) if day == "saturday" => Weekday::Saturday,
+        _ => {
+            // Locale names look like `pt_BR.UTF-8`
+            let locale = ["LC_ALL", "LC_TIME", "LANG"]
+                .iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .find(|value| !value.is_empty())
+                .unwrap_or_default();
+            let territory = locale
+                .split(['.', '@'])
+                .next()
+                .and_then(|name| name.split_once('_'))
+                .map(|(_, territory)| territory.to_string())
+                .unwrap_or_default();
+
+            if SUNDAY_FIRST_TERRITORIES.contains(&territory.as_str()) {
+                Weekday::Sunday
+            } else {
+                Weekday::Monday
+            }
+        }
+    }
+}
+
+/// Current date in local time
+pub fn today() -> Date {
+    let now = glib::DateTime::now_local().expect("Could not get current time");
+
+    Date::new(now.year(), now.month() as u32, now.day_of_month() as u32)
+        .expect("Current date is invalid")
+}
+
 mod imp {
     use super::*;
 
@@ -50,6 +133,7 @@ mod imp {
             obj.setup_gactions();
             obj.load_css();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
+            obj.set_accels_for_action("app.preferences", &["<primary>comma"]);
             obj.set_accels_for_action("win.start-group-search", &["<Ctrl>f"]);
 
             obj.connect_closure(
@@ -57,20 +141,70 @@ mod imp {
                 false,
                 glib::closure_local!(move |application: Self::Type, group: &Group| {
                     if let Some(data) = application.imp().data.get() {
-                        data.save_group(group);
+                        if let Err(error) = data.save_group(group) {
+                            application.report_error(&error, Some(group));
+                        }
                     }
                 }),
             );
+
+            obj.connect_closure(
+                "save-transaction",
+                false,
+                glib::closure_local!(
+                    move |application: Self::Type, group: &Group, transaction: &Transaction| {
+                        if let Some(data) = application.imp().data.get() {
+                            if let Err(error) = data.save_transaction(group, transaction) {
+                                application.report_error(&error, Some(group));
+                            }
+                        }
+                    }
+                ),
+            );
+
+            obj.connect_closure(
+                "delete-transaction",
+                false,
+                glib::closure_local!(
+                    move |application: Self::Type, group: &Group, transaction: &Transaction| {
+                        if let Some(data) = application.imp().data.get() {
+                            if let Err(error) = data.delete_transaction(group, transaction) {
+                                application.report_error(&error, Some(group));
+                            }
+                        }
+                    }
+                ),
+            );
         }
 
         fn signals() -> &'static [glib::subclass::Signal] {
             static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
-                vec![Signal::builder(
-                    "save-group",
-                    &[Group::static_type().into()],
-                    <()>::static_type().into(),
-                )
-                .build()]
+                vec![
+                    Signal::builder(
+                        "save-group",
+                        &[Group::static_type().into()],
+                        <()>::static_type().into(),
+                    )
+                    .build(),
+                    Signal::builder(
+                        "save-transaction",
+                        &[
+                            Group::static_type().into(),
+                            Transaction::static_type().into(),
+                        ],
+                        <()>::static_type().into(),
+                    )
+                    .build(),
+                    Signal::builder(
+                        "delete-transaction",
+                        &[
+                            Group::static_type().into(),
+                            Transaction::static_type().into(),
+                        ],
+                        <()>::static_type().into(),
+                    )
+                    .build(),
+                ]
             });
             SIGNALS.as_ref()
         }
@@ -88,6 +222,8 @@ mod imp {
 
             // Ask the window manager/compositor to present the window
             window.present();
+
+            application.add_due_recurring();
         }
     }
 
@@ -111,6 +247,28 @@ impl BeedgetApplication {
         self.imp().data.get().expect("Save data not loaded")
     }
 
+    /// Show error in the main window. When `retry_group` is set, the user
+    /// is offered to try saving that group again.
+    pub fn report_error(&self, error: &anyhow::Error, retry_group: Option<&Group>) {
+        glib::g_warning!("beedget", "{:?}", error);
+
+        let window = self
+            .windows()
+            .into_iter()
+            .find_map(|window| window.downcast::<BeedgetWindow>().ok());
+
+        if let Some(window) = window {
+            window.show_error(&error.to_string(), retry_group);
+        }
+    }
+
+    /// Add recurring transactions that came due since the last run
+    pub fn add_due_recurring(&self) {
+        for (group, error) in self.data().add_due_recurring(today()) {
+            self.report_error(&error, Some(&group));
+        }
+    }
+
     fn setup_gactions(&self) {
         let quit_action = gio::SimpleAction::new("quit", None);
         quit_action.connect_activate(clone!(@weak self as app => move |_, _| {
@@ -123,6 +281,31 @@ impl BeedgetApplication {
             app.show_about();
         }));
         self.add_action(&about_action);
+
+        let preferences_action = gio::SimpleAction::new("preferences", None);
+        preferences_action.connect_activate(clone!(@weak self as app => move |_, _| {
+            let window = PreferencesWindow::new(&app.active_window().unwrap());
+            window.present();
+        }));
+        self.add_action(&preferences_action);
+    }
+
+    pub fn settings(&self) -> &gio::Settings {
+        self.imp().settings.get().unwrap()
+    }
+
+    /// Move all data to `path` and save there from now on
+    pub fn move_save_path(&self, path: &Path) -> anyhow::Result<()> {
+        // The setting can only hold UTF-8, so check before anything is copied
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("{} is not a valid UTF-8 path", path.display()))?;
+        let backend = StorageBackend::from_setting(&self.settings().string("storage-backend"));
+
+        self.data().relocate(path, backend, || {
+            self.settings().set_string("save-path", path_str)?;
+            Ok(())
+        })
     }
 
     fn show_about(&self) {
@@ -141,21 +324,23 @@ impl BeedgetApplication {
     /// Load save data from disk and populate save-path setting if empty
     fn load_data(&self) {
         let save_path = self.imp().settings.get().unwrap().string("save-path");
+        let backend = StorageBackend::from_setting(
+            &self.imp().settings.get().unwrap().string("storage-backend"),
+        );
         let data_buf = if save_path.is_empty() {
             let mut dbf = glib::user_data_dir();
             dbf.push(r"beedget");
-            match self
+            // Data can still be saved to the default path if the setting is not writable
+            if let Err(error) = self
                 .imp()
                 .settings
                 .get()
                 .unwrap()
                 .set_string("save-path", dbf.as_path().to_str().unwrap())
             {
-                Ok(()) => dbf,
-                Err(error) => {
-                    panic!("{}", error)
-                }
+                glib::g_warning!("beedget", "Could not store save path: {}", error);
             }
+            dbf
         } else {
             let mut dbf = PathBuf::new();
             dbf.push(Path::new(&save_path));
@@ -164,7 +349,7 @@ impl BeedgetApplication {
 
         self.imp()
             .data
-            .set(SaveData::new(&data_buf))
+            .set(SaveData::new(&data_buf, backend))
             .expect("Failed to load save data.");
     }
 
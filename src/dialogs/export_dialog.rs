@@ -0,0 +1,383 @@
+use std::cell::RefCell;
+
+use gettextrs::{gettext, ngettext};
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gio, glib, CompositeTemplate};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::application;
+use crate::models::export::csv::CsvDialect;
+use crate::models::export::ledger::LedgerDialect;
+use crate::models::export::{self, beancount, csv, json, ledger, Accounts};
+use crate::models::*;
+use crate::widgets::PERIODS;
+
+/// Formats in the order of the format drop-down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    /// CSV using the number format preference
+    LocaleCsv,
+    Json,
+    Ledger,
+    Hledger,
+    Beancount,
+}
+
+const FORMATS: &[ExportFormat] = &[
+    ExportFormat::Csv,
+    ExportFormat::LocaleCsv,
+    ExportFormat::Json,
+    ExportFormat::Ledger,
+    ExportFormat::Hledger,
+    ExportFormat::Beancount,
+];
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv | ExportFormat::LocaleCsv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ledger => "ledger",
+            ExportFormat::Hledger => "journal",
+            ExportFormat::Beancount => "beancount",
+        }
+    }
+
+    /// Whether groups become accounts of a double-entry journal
+    fn uses_accounts(&self) -> bool {
+        matches!(
+            self,
+            ExportFormat::Ledger | ExportFormat::Hledger | ExportFormat::Beancount
+        )
+    }
+
+    fn write(
+        &self,
+        groups: &[beedget_core::Group],
+        period: Option<DateRange>,
+        accounts: &Accounts,
+    ) -> anyhow::Result<String> {
+        match self {
+            ExportFormat::Csv => Ok(csv::write(groups, period, CsvDialect::Rfc4180)),
+            ExportFormat::LocaleCsv => Ok(csv::write(
+                groups,
+                period,
+                CsvDialect::Locale(application::number_format()),
+            )),
+            ExportFormat::Json => json::write(groups, period),
+            ExportFormat::Ledger => Ok(ledger::write(
+                groups,
+                period,
+                accounts,
+                LedgerDialect::Ledger,
+            )),
+            ExportFormat::Hledger => Ok(ledger::write(
+                groups,
+                period,
+                accounts,
+                LedgerDialect::Hledger,
+            )),
+            ExportFormat::Beancount => Ok(beancount::write(groups, period, accounts)),
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/matbme/beedget/ui/export-dialog.ui")]
+    pub struct ExportDialog {
+        #[template_child]
+        pub export_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub groups_list: TemplateChild<gtk::ListBox>,
+
+        #[template_child]
+        pub period_dropdown: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub custom_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub start_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub end_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub format_select: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub accounts_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub group_parent_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub balancing_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub summary_label: TemplateChild<gtk::Label>,
+
+        /// Every group with the check button choosing whether it is exported
+        pub group_checks: RefCell<Vec<(Group, gtk::CheckButton)>>,
+        pub file_chooser: RefCell<Option<gtk::FileChooserNative>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ExportDialog {
+        const NAME: &'static str = "ExportDialog";
+        type Type = super::ExportDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+            Self::Type::bind_template_callbacks(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ExportDialog {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            let accounts = Accounts::default();
+            self.group_parent_entry.set_text(&accounts.group_parent);
+            self.balancing_entry.set_text(&accounts.balancing);
+
+            obj.populate_groups();
+            obj.connect_filter_widgets();
+            obj.update_summary();
+        }
+    }
+
+    impl WidgetImpl for ExportDialog {}
+    impl WindowImpl for ExportDialog {}
+    impl AdwWindowImpl for ExportDialog {}
+}
+
+glib::wrapper! {
+    /// Write transactions of chosen groups and period to a file
+    pub struct ExportDialog(ObjectSubclass<imp::ExportDialog>)
+        @extends adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget,
+                    gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+#[gtk::template_callbacks]
+impl ExportDialog {
+    pub fn new(parent: &gtk::Window) -> Self {
+        glib::Object::new(&[("transient-for", &Some(parent))])
+            .expect("Failed to create `ExportDialog`.")
+    }
+
+    #[template_callback]
+    fn close_window(&self) {
+        self.destroy();
+    }
+
+    #[template_callback]
+    fn choose_file(&self) {
+        let format = self.selected_format();
+
+        let chooser = gtk::FileChooserNative::new(
+            Some(&gettext("Export Transactions")),
+            Some(self),
+            gtk::FileChooserAction::Save,
+            Some(&gettext("_Export")),
+            Some(&gettext("_Cancel")),
+        );
+        chooser.set_modal(true);
+        chooser.set_current_name(&format!("beedget.{}", format.extension()));
+
+        chooser.connect_response(
+            glib::clone!(@weak self as dialog => move |chooser, response| {
+                if response == gtk::ResponseType::Accept {
+                    if let Some(file) = chooser.file() {
+                        dialog.export_to(&file, format);
+                    }
+                }
+
+                dialog.imp().file_chooser.replace(None);
+            }),
+        );
+
+        chooser.show();
+
+        // Native dialogs are not kept alive by their parent
+        self.imp().file_chooser.replace(Some(chooser));
+    }
+
+    fn export_to(&self, file: &gio::File, format: ExportFormat) {
+        let application = application!(self @as crate::BeedgetApplication);
+
+        let period = match self.selected_period() {
+            Ok(period) => period,
+            Err(_) => return,
+        };
+        let result = format
+            .write(&self.selected_groups(), period, &self.accounts())
+            .and_then(|contents| {
+                file.replace_contents(
+                    contents.as_bytes(),
+                    None,
+                    false,
+                    gio::FileCreateFlags::REPLACE_DESTINATION,
+                    gio::Cancellable::NONE,
+                )?;
+                Ok(())
+            });
+
+        match result {
+            Ok(()) => self.destroy(),
+            // Keep dialog open so the user can try again
+            Err(error) => application.report_error(&error, None),
+        }
+    }
+
+    /// List every group, all of them exported unless unchecked
+    fn populate_groups(&self) {
+        let groups = application::instance()
+            .map(|application| application.data().groups.borrow().clone())
+            .unwrap_or_default();
+
+        let mut checks = vec![];
+        for group in groups {
+            let check = gtk::CheckButton::builder()
+                .active(true)
+                .valign(gtk::Align::Center)
+                .build();
+            check.connect_toggled(glib::clone!(@weak self as dialog => move |_| {
+                dialog.update_summary();
+            }));
+
+            let row = adw::ActionRow::builder()
+                .title(&format!("{} {}", group.emoji(), group.name()))
+                .activatable_widget(&check)
+                .build();
+            row.add_prefix(&check);
+            self.imp().groups_list.append(&row);
+
+            checks.push((group, check));
+        }
+
+        self.imp().group_checks.replace(checks);
+    }
+
+    fn connect_filter_widgets(&self) {
+        let imp = self.imp();
+
+        imp.period_dropdown.connect_selected_notify(
+            glib::clone!(@weak self as dialog => move |dropdown| {
+                dialog
+                    .imp()
+                    .custom_box
+                    .set_visible(dropdown.selected() as usize == PERIODS.len());
+                dialog.update_summary();
+            }),
+        );
+
+        for entry in [&*imp.start_entry, &*imp.end_entry] {
+            entry.connect_changed(glib::clone!(@weak self as dialog => move |_| {
+                dialog.update_summary();
+            }));
+        }
+
+        imp.format_select
+            .connect_selected_notify(glib::clone!(@weak self as dialog => move |_| {
+                let uses_accounts = dialog.selected_format().uses_accounts();
+                dialog.imp().accounts_box.set_visible(uses_accounts);
+            }));
+    }
+
+    fn selected_format(&self) -> ExportFormat {
+        FORMATS
+            .get(self.imp().format_select.selected() as usize)
+            .copied()
+            .unwrap_or(ExportFormat::Csv)
+    }
+
+    /// Accounts typed by the user, the defaults for those left empty
+    fn accounts(&self) -> Accounts {
+        let default = Accounts::default();
+        let text = |entry: &gtk::Entry, default: String| {
+            let text = entry.text().trim().to_string();
+            if text.is_empty() {
+                default
+            } else {
+                text
+            }
+        };
+
+        Accounts {
+            group_parent: text(&self.imp().group_parent_entry, default.group_parent),
+            balancing: text(&self.imp().balancing_entry, default.balancing),
+        }
+    }
+
+    /// Data of the checked groups
+    fn selected_groups(&self) -> Vec<beedget_core::Group> {
+        self.imp()
+            .group_checks
+            .borrow()
+            .iter()
+            .filter(|(_, check)| check.is_active())
+            .map(|(group, _)| group.data())
+            .collect()
+    }
+
+    /// Date range to export, `None` for all time. The custom range includes
+    /// both of its days.
+    fn selected_period(&self) -> Result<Option<DateRange>, String> {
+        let selected = self.imp().period_dropdown.selected() as usize;
+        if let Some(period) = PERIODS.get(selected) {
+            return Ok(period.range(application::today()));
+        }
+
+        let parse = |entry: &gtk::Entry| {
+            Date::parse_iso(entry.text().trim())
+                .map_err(|_| gettext("Enter dates as year-month-day, e.g. 2022-07-31"))
+        };
+        let start = parse(&self.imp().start_entry)?;
+        let end = parse(&self.imp().end_entry)?;
+        if end < start {
+            return Err(gettext("The period ends before it starts"));
+        }
+
+        Ok(Some(DateRange::new(start, end.add_days(1))))
+    }
+
+    /// Tell how many transactions will be written, or what is missing
+    fn update_summary(&self) {
+        let groups = self.selected_groups();
+        let period = self.selected_period();
+
+        let summary = match &period {
+            Ok(period) => {
+                let count = export::transactions_in(&groups, *period).len();
+
+                ngettext(
+                    "{} transaction will be exported",
+                    "{} transactions will be exported",
+                    count as u32,
+                )
+                .replace("{}", &count.to_string())
+            }
+            Err(message) => message.clone(),
+        };
+
+        self.imp().summary_label.set_label(&summary);
+        self.imp()
+            .export_button
+            .set_sensitive(period.is_ok() && !groups.is_empty());
+    }
+}
@@ -16,6 +16,19 @@ use rand::prelude::*;
 use crate::application;
 use crate::models::*;
 
+/// Positions of the budget period drop-down entries
+const BUDGET_MONTHLY: u32 = 0;
+const BUDGET_WEEKLY: u32 = 1;
+const BUDGET_CUSTOM: u32 = 2;
+
+/// Rollover choices in the order of the leftover drop-down
+const ROLLOVERS: &[Rollover] = &[
+    Rollover::Reset,
+    Rollover::Surplus,
+    Rollover::Deficit,
+    Rollover::Both,
+];
+
 mod imp {
     use super::*;
 
@@ -31,6 +44,30 @@ mod imp {
         #[template_child]
         pub group_name: TemplateChild<gtk::Entry>,
 
+        #[template_child]
+        pub group_currency: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub budget_check: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub budget_limit: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub budget_period: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub budget_custom_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub budget_days: TemplateChild<gtk::SpinButton>,
+
+        #[template_child]
+        pub budget_rollover_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub budget_rollover: TemplateChild<gtk::DropDown>,
+
         #[template_child]
         pub group_color: TemplateChild<gtk::ColorButton>,
 
@@ -92,6 +129,8 @@ mod imp {
             self.parent_constructed(obj);
 
             if obj.imp().edit_group.get().is_none() {
+                obj.set_currency_dropdown_selection(application::default_currency());
+
                 // Random color
                 let mut rng = rand::thread_rng();
                 self.group_color
@@ -105,6 +144,7 @@ mod imp {
             }
 
             obj.connect_key_event_controller();
+            obj.connect_budget_widgets();
             obj.connect_add_button_to_entry_size();
         }
     }
@@ -152,7 +192,9 @@ impl GroupDialog {
             &self.imp().current_emoji.borrow(),
             self.imp().group_color.rgba(),
             &self.imp().group_name.text(),
+            self.selected_currency(),
         );
+        group.set_budget(self.selected_budget().unwrap());
 
         let application = application!(self @as crate::BeedgetApplication);
         match application.data().new_group(group) {
@@ -160,7 +202,8 @@ impl GroupDialog {
                 self.destroy();
             }
             Err(error) => {
-                panic!("{}", error);
+                // Keep dialog open so the user can try again
+                application.report_error(&error, None);
             }
         }
     }
@@ -171,6 +214,8 @@ impl GroupDialog {
         let name = self.imp().group_name.text();
         group.set_property("name", name.to_value());
 
+        group.set_property("currency", self.selected_currency().code.to_value());
+
         let color_str = self.imp().group_color.rgba().to_str();
         group.set_property("color", color_str.to_value());
 
@@ -181,9 +226,9 @@ impl GroupDialog {
             .expect("No group emoji selected");
         group.set_property("emoji", emoji.to_value());
 
-        application!(self @as crate::BeedgetApplication)
-            .data()
-            .save_group(group);
+        group.set_budget(self.selected_budget().unwrap());
+
+        application!(self @as crate::BeedgetApplication).emit_by_name::<()>("save-group", &[group]);
 
         self.destroy();
     }
@@ -219,21 +264,128 @@ impl GroupDialog {
         emoji_picker.popup();
     }
 
-    /// Disables button if name entry is empty
+    /// Disables button if name entry is empty or the budget limit is invalid
     fn connect_add_button_to_entry_size(&self) {
         // Set initial
-        self.imp()
-            .add_button
-            .set_sensitive(self.imp().group_name.text_length() > 0);
+        self.update_add_button();
 
         // Subscribe to changes
         self.imp().group_name.buffer().connect_length_notify(
             glib::clone!(@weak self as parent => move |_| {
-                parent.imp().add_button.set_sensitive(parent.imp().group_name.text_length() > 0);
+                parent.update_add_button();
+            }),
+        );
+        self.imp().budget_limit.buffer().connect_text_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_add_button();
+            }),
+        );
+        self.imp()
+            .budget_check
+            .connect_toggled(glib::clone!(@weak self as parent => move |_| {
+                parent.update_add_button();
+            }));
+        self.imp().group_currency.connect_selected_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_add_button();
             }),
         );
     }
 
+    fn update_add_button(&self) {
+        let budget_valid = self.selected_budget().is_some();
+        if budget_valid || self.imp().budget_limit.text_length() == 0 {
+            self.imp().budget_limit.remove_css_class("error");
+        } else {
+            self.imp().budget_limit.add_css_class("error");
+        }
+
+        self.imp()
+            .add_button
+            .set_sensitive(self.imp().group_name.text_length() > 0 && budget_valid);
+    }
+
+    /// Enable budget fields only when a limit is wanted
+    fn connect_budget_widgets(&self) {
+        self.imp()
+            .budget_check
+            .connect_toggled(glib::clone!(@weak self as parent => move |_| {
+                parent.update_budget_widgets();
+            }));
+        self.imp().budget_period.connect_selected_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_budget_widgets();
+            }),
+        );
+
+        self.update_budget_widgets();
+    }
+
+    fn update_budget_widgets(&self) {
+        let imp = self.imp();
+        let active = imp.budget_check.is_active();
+
+        imp.budget_limit.set_sensitive(active);
+        imp.budget_period.set_sensitive(active);
+        imp.budget_custom_box.set_sensitive(active);
+        imp.budget_rollover_box.set_sensitive(active);
+        imp.budget_custom_box
+            .set_visible(imp.budget_period.selected() == BUDGET_CUSTOM);
+    }
+
+    /// Budget entered in the dialog. `None` if the limit cannot be parsed.
+    fn selected_budget(&self) -> Option<Option<Budget>> {
+        if !self.imp().budget_check.is_active() {
+            return Some(None);
+        }
+
+        let limit = self
+            .selected_currency()
+            .parse(
+                self.imp().budget_limit.text().as_str(),
+                application::number_format(),
+            )
+            .ok()
+            .filter(|limit| !limit.is_negative())?;
+
+        let period = match self.imp().budget_period.selected() {
+            BUDGET_WEEKLY => BudgetPeriod::Weekly,
+            BUDGET_CUSTOM => {
+                let days = self.imp().budget_days.value_as_int().max(1) as u32;
+
+                // Keep the cycle of an existing custom budget
+                let start = match self.imp().edit_group.get().and_then(|group| group.budget()) {
+                    Some(Budget {
+                        period: BudgetPeriod::Custom { start, .. },
+                        ..
+                    }) => start,
+                    _ => application::today(),
+                };
+
+                BudgetPeriod::Custom { start, days }
+            }
+            _ => BudgetPeriod::Monthly,
+        };
+
+        let rollover = ROLLOVERS
+            .get(self.imp().budget_rollover.selected() as usize)
+            .copied()
+            .unwrap_or_default();
+
+        // Periods before the budget existed carry nothing over
+        let since = match self.imp().edit_group.get().and_then(|group| group.budget()) {
+            Some(budget) => budget.since,
+            None => Some(application::today()),
+        };
+
+        Some(Some(Budget {
+            limit,
+            period,
+            rollover,
+            since,
+        }))
+    }
+
     /// Handle keyboard events
     fn connect_key_event_controller(&self) {
         let key_controller = gtk::EventControllerKey::new();
@@ -264,9 +416,64 @@ impl GroupDialog {
             .group_name
             .set_buffer(&gtk::EntryBuffer::new(Some(&group.name())));
 
+        self.set_currency_dropdown_selection(group.currency());
+
+        if let Some(budget) = group.budget() {
+            self.imp().budget_check.set_active(true);
+            self.imp().budget_limit.set_text(
+                &application::number_format()
+                    .apply(&budget.limit.format_fixed(group.currency().decimals)),
+            );
+
+            match budget.period {
+                BudgetPeriod::Monthly => self.imp().budget_period.set_selected(BUDGET_MONTHLY),
+                BudgetPeriod::Weekly => self.imp().budget_period.set_selected(BUDGET_WEEKLY),
+                BudgetPeriod::Custom { days, .. } => {
+                    self.imp().budget_period.set_selected(BUDGET_CUSTOM);
+                    self.imp().budget_days.set_value(days as f64);
+                }
+            }
+
+            if let Some(idx) = ROLLOVERS.iter().position(|r| *r == budget.rollover) {
+                self.imp().budget_rollover.set_selected(idx as u32);
+            }
+        }
+
         self.imp().group_color.set_rgba(&group.rgba_color());
         self.imp()
             .group_icon_picker_button
             .set_label(&group.emoji());
     }
+
+    fn populate_currency_dropdown(&self) {
+        let labels: Vec<String> = Currency::all().iter().map(|c| c.label()).collect();
+        let labels: Vec<&str> = labels.iter().map(|l| l.as_str()).collect();
+
+        self.imp()
+            .group_currency
+            .set_model(Some(&gtk::StringList::new(&labels)));
+        self.imp()
+            .group_currency
+            .set_expression(Some(&gtk::PropertyExpression::new(
+                gtk::StringObject::static_type(),
+                gtk::Expression::NONE,
+                "string",
+            )));
+    }
+
+    fn set_currency_dropdown_selection(&self, currency: &Currency) {
+        if self.imp().group_currency.model().is_none() {
+            self.populate_currency_dropdown();
+        }
+
+        if let Some(idx) = Currency::all().iter().position(|c| c == currency) {
+            self.imp().group_currency.set_selected(idx as u32);
+        }
+    }
+
+    fn selected_currency(&self) -> &'static Currency {
+        Currency::all()
+            .get(self.imp().group_currency.selected() as usize)
+            .unwrap_or_else(application::default_currency)
+    }
 }
@@ -0,0 +1,776 @@
+use std::cell::{Cell, RefCell};
+
+use gettextrs::{gettext, ngettext};
+use gtk::gio::ListStore;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, gio, glib, CompositeTemplate};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use rand::prelude::*;
+
+use crate::application;
+use crate::dialogs::string_store;
+use crate::models::import::csv::{self, DateFormat, DecimalSeparator, SignConvention};
+use crate::models::import::{self, ofx, qif};
+use crate::models::*;
+
+/// Choices in the order of the format drop-downs
+const DELIMITERS: &[char] = &[',', ';', '\t'];
+const SIGN_CONVENTIONS: &[SignConvention] = &[
+    SignConvention::NegativeIsExpense,
+    SignConvention::PositiveIsExpense,
+    SignConvention::TypeColumn,
+];
+const DATE_FORMATS: &[DateFormat] = &[
+    DateFormat::YearMonthDay,
+    DateFormat::DayMonthYear,
+    DateFormat::MonthDayYear,
+];
+const DECIMAL_SEPARATORS: &[DecimalSeparator] = &[DecimalSeparator::Point, DecimalSeparator::Comma];
+
+/// Rows listed in the preview, the rest only count towards the summary
+const PREVIEW_ROWS: usize = 20;
+
+/// Position of the QIF category drop-down entry turning categories into
+/// groups, the other one turns them into tags
+const CATEGORIES_AS_GROUPS: u32 = 1;
+
+/// Transaction read from a statement
+#[derive(Debug)]
+struct Entry {
+    transaction: beedget_core::Transaction,
+    /// Group the transaction goes to instead of the selected one, created if
+    /// there is none by that name
+    group_name: Option<String>,
+}
+
+/// Entry, or why it could not be read, with the line or record number it comes from
+type Row = (usize, anyhow::Result<Entry>);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatementFormat {
+    /// Columns are picked by the user and remembered in a profile
+    #[default]
+    Csv,
+    /// OFX or QFX, which name their values
+    Ofx,
+    /// Quicken interchange format, whose categories become groups or tags
+    Qif,
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/matbme/beedget/ui/import-dialog.ui")]
+    pub struct ImportDialog {
+        #[template_child]
+        pub import_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub file_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub mapping_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub profile_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub group_select: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub date_column: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub name_column: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub amount_column: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub type_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub type_column: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub expense_marker_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub header_check: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub delimiter_select: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub sign_select: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub date_format_select: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub decimal_select: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub format_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub category_select: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub summary_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub preview_list: TemplateChild<gtk::ListBox>,
+
+        /// Text of the chosen file
+        pub contents: RefCell<Option<String>>,
+        pub format: Cell<StatementFormat>,
+        /// Mapping shown by the widgets, kept while no file gives the column choices
+        pub mapping: RefCell<CsvMapping>,
+        /// Set while widgets are filled from the mapping, so they don't write it back
+        pub updating: Cell<bool>,
+        pub file_chooser: RefCell<Option<gtk::FileChooserNative>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ImportDialog {
+        const NAME: &'static str = "ImportDialog";
+        type Type = super::ImportDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+            Self::Type::bind_template_callbacks(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ImportDialog {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            obj.populate_group_select_dropdown();
+            obj.setup_profile_completion();
+            obj.connect_mapping_widgets();
+            obj.set_mapping(CsvMapping::default());
+        }
+    }
+
+    impl WidgetImpl for ImportDialog {}
+    impl WindowImpl for ImportDialog {}
+    impl AdwWindowImpl for ImportDialog {}
+}
+
+glib::wrapper! {
+    /// Add transactions from a CSV, OFX, QFX or QIF bank statement to a group
+    pub struct ImportDialog(ObjectSubclass<imp::ImportDialog>)
+        @extends adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget,
+                    gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+#[gtk::template_callbacks]
+impl ImportDialog {
+    pub fn new(parent: &gtk::Window, group: Option<&Group>) -> Self {
+        let dialog: Self = glib::Object::new(&[("transient-for", &Some(parent))])
+            .expect("Failed to create `ImportDialog`.");
+
+        if let Some(group) = group {
+            let model = dialog.imp().group_select.model().unwrap();
+            if let Some(position) = model.downcast_ref::<ListStore>().unwrap().find(group) {
+                dialog.imp().group_select.set_selected(position);
+            }
+        }
+
+        dialog
+    }
+
+    #[template_callback]
+    fn close_window(&self) {
+        self.destroy();
+    }
+
+    #[template_callback]
+    fn choose_file(&self) {
+        let chooser = gtk::FileChooserNative::new(
+            Some(&gettext("Choose Statement")),
+            Some(self),
+            gtk::FileChooserAction::Open,
+            Some(&gettext("_Open")),
+            Some(&gettext("_Cancel")),
+        );
+        chooser.set_modal(true);
+
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("Bank statements")));
+        filter.add_mime_type("text/csv");
+        filter.add_mime_type("application/x-ofx");
+        for pattern in ["*.csv", "*.ofx", "*.qfx", "*.qif"] {
+            filter.add_pattern(pattern);
+        }
+        chooser.add_filter(&filter);
+
+        chooser.connect_response(
+            glib::clone!(@weak self as dialog => move |chooser, response| {
+                if response == gtk::ResponseType::Accept {
+                    if let Some(file) = chooser.file() {
+                        dialog.load_file(&file);
+                    }
+                }
+
+                dialog.imp().file_chooser.replace(None);
+            }),
+        );
+
+        chooser.show();
+
+        // Native dialogs are not kept alive by their parent
+        self.imp().file_chooser.replace(Some(chooser));
+    }
+
+    #[template_callback]
+    fn import_transactions(&self) {
+        let group = match self.selected_group() {
+            Some(group) => group,
+            None => return,
+        };
+        let mapping = self.imp().mapping.borrow().clone();
+        let (rows, _) = match self.read_rows() {
+            Some(result) => result,
+            None => return,
+        };
+
+        let application = application!(self @as crate::BeedgetApplication);
+        let mut changed = vec![group.clone()];
+        for entry in rows.into_iter().filter_map(|(_, row)| row.ok()) {
+            let target = match &entry.group_name {
+                Some(name) => match self.group_named(name, &group) {
+                    Ok(target) => target,
+                    Err(error) => {
+                        application.report_error(&error, None);
+                        continue;
+                    }
+                },
+                None => group.clone(),
+            };
+
+            target.new_transaction(Transaction::from_data(entry.transaction));
+            if !changed.contains(&target) {
+                changed.push(target);
+            }
+        }
+
+        for group in &changed {
+            application.emit_by_name::<()>("save-group", &[group]);
+        }
+
+        let profile = self.imp().profile_entry.text();
+        if self.imp().format.get() == StatementFormat::Csv && !profile.trim().is_empty() {
+            if let Err(error) = application
+                .data()
+                .save_import_profile(profile.trim(), mapping)
+            {
+                application.report_error(&error, None);
+            }
+        }
+
+        self.destroy();
+    }
+
+    /// Group called `name`, ignoring case, or a new one in the currency of `like`
+    fn group_named(&self, name: &str, like: &Group) -> anyhow::Result<Group> {
+        let application = application!(self @as crate::BeedgetApplication);
+
+        let existing = application
+            .data()
+            .groups
+            .borrow()
+            .iter()
+            .find(|group| group.name().to_lowercase() == name.to_lowercase())
+            .cloned();
+        if let Some(group) = existing {
+            return Ok(group);
+        }
+
+        let mut rng = rand::thread_rng();
+        let group = Group::new(
+            emojis::iter().choose(&mut rng).unwrap().as_str(),
+            gdk::RGBA::new(rng.gen(), rng.gen(), rng.gen(), 1.0),
+            name,
+            like.currency(),
+        );
+        application.data().new_group(group.clone())?;
+
+        Ok(group)
+    }
+
+    fn populate_group_select_dropdown(&self) {
+        let application = application!(self @as crate::BeedgetApplication);
+
+        self.imp().group_select.set_factory(Some(&Group::factory()));
+        self.imp()
+            .group_select
+            .set_model(Some(application.data().group_model()));
+        self.imp()
+            .group_select
+            .set_expression(Some(&Group::search_expression()));
+
+        self.imp().group_select.connect_selected_notify(
+            glib::clone!(@weak self as dialog => move |_| {
+                dialog.update_preview();
+            }),
+        );
+    }
+
+    /// Suggest banks with a saved mapping, and use it once one is picked
+    fn setup_profile_completion(&self) {
+        let profiles = application::instance()
+            .and_then(|application| application.data().import_profiles().ok())
+            .unwrap_or_default();
+        let names: Vec<String> = profiles.profiles.keys().cloned().collect();
+
+        let completion = gtk::EntryCompletion::new();
+        completion.set_model(Some(&string_store(&names)));
+        completion.set_text_column(0);
+        completion.set_inline_completion(true);
+
+        self.imp().profile_entry.set_completion(Some(&completion));
+
+        self.imp().profile_entry.connect_changed(
+            glib::clone!(@weak self as dialog => move |entry| {
+                if let Some(mapping) = profiles.profiles.get(entry.text().trim()) {
+                    dialog.set_mapping(mapping.clone());
+                }
+            }),
+        );
+    }
+
+    /// Write widget changes to the mapping and show their effect
+    fn connect_mapping_widgets(&self) {
+        let imp = self.imp();
+
+        for dropdown in [
+            &*imp.date_column,
+            &*imp.name_column,
+            &*imp.amount_column,
+            &*imp.type_column,
+            &*imp.sign_select,
+            &*imp.date_format_select,
+            &*imp.decimal_select,
+        ] {
+            dropdown.connect_selected_notify(glib::clone!(@weak self as dialog => move |_| {
+                dialog.store_mapping();
+            }));
+        }
+
+        imp.category_select.connect_selected_notify(
+            glib::clone!(@weak self as dialog => move |_| {
+                dialog.update_preview();
+            }),
+        );
+
+        imp.expense_marker_entry
+            .connect_changed(glib::clone!(@weak self as dialog => move |_| {
+                dialog.store_mapping();
+            }));
+
+        // Columns are read again when the way of splitting them changes
+        imp.delimiter_select.connect_selected_notify(
+            glib::clone!(@weak self as dialog => move |_| {
+                if !dialog.imp().updating.get() {
+                    dialog.store_mapping();
+                    dialog.refresh_columns();
+                }
+            }),
+        );
+        imp.header_check
+            .connect_toggled(glib::clone!(@weak self as dialog => move |_| {
+                if !dialog.imp().updating.get() {
+                    dialog.store_mapping();
+                    dialog.refresh_columns();
+                }
+            }));
+    }
+
+    fn load_file(&self, file: &gio::File) {
+        let contents = match file.load_contents(gio::Cancellable::NONE) {
+            Ok((bytes, _)) => import::decode(&bytes),
+            Err(error) => {
+                application!(self @as crate::BeedgetApplication)
+                    .report_error(&anyhow::Error::from(error), None);
+                return;
+            }
+        };
+
+        let name = file
+            .basename()
+            .map(|name| name.display().to_string())
+            .unwrap_or_default();
+        self.imp().file_label.set_label(&name);
+        self.imp().file_label.remove_css_class("dim-label");
+
+        let extension = name.rsplit('.').next().unwrap_or_default().to_lowercase();
+        let format = if matches!(&extension[..], "ofx" | "qfx") || contents.contains("<OFX>") {
+            StatementFormat::Ofx
+        } else if extension == "qif" || contents.trim_start().starts_with('!') {
+            StatementFormat::Qif
+        } else {
+            StatementFormat::Csv
+        };
+        self.imp().format.set(format);
+        self.imp()
+            .mapping_box
+            .set_visible(format == StatementFormat::Csv);
+        self.imp()
+            .format_box
+            .set_visible(format != StatementFormat::Ofx);
+        self.imp()
+            .category_select
+            .set_visible(format == StatementFormat::Qif);
+
+        // A saved profile knows better than guessing
+        let delimiter = csv::detect_delimiter(&contents);
+        self.imp().contents.replace(Some(contents));
+        if self.imp().profile_entry.text_length() == 0 {
+            self.imp().mapping.borrow_mut().delimiter = delimiter;
+        }
+
+        let mapping = self.imp().mapping.borrow().clone();
+        self.set_mapping(mapping);
+    }
+
+    /// Show `mapping` in the widgets
+    fn set_mapping(&self, mapping: CsvMapping) {
+        let imp = self.imp();
+        imp.updating.set(true);
+
+        imp.delimiter_select
+            .set_selected(position(DELIMITERS, &mapping.delimiter));
+        imp.header_check.set_active(mapping.has_header);
+        imp.sign_select
+            .set_selected(position(SIGN_CONVENTIONS, &mapping.sign));
+        imp.date_format_select
+            .set_selected(position(DATE_FORMATS, &mapping.date_format));
+        imp.decimal_select
+            .set_selected(position(DECIMAL_SEPARATORS, &mapping.decimal_separator));
+        imp.expense_marker_entry.set_text(&mapping.expense_marker);
+
+        imp.updating.set(false);
+        imp.mapping.replace(mapping);
+
+        self.refresh_columns();
+    }
+
+    /// Read the mapping back from the widgets
+    fn store_mapping(&self) {
+        let imp = self.imp();
+        if imp.updating.get() {
+            return;
+        }
+
+        {
+            let mut mapping = imp.mapping.borrow_mut();
+
+            let selected = |dropdown: &gtk::DropDown| match dropdown.selected() {
+                gtk::INVALID_LIST_POSITION => None,
+                position => Some(position as usize),
+            };
+            mapping.delimiter = DELIMITERS[selected(&imp.delimiter_select).unwrap_or(0)];
+            mapping.has_header = imp.header_check.is_active();
+            mapping.sign = SIGN_CONVENTIONS[selected(&imp.sign_select).unwrap_or(0)];
+            mapping.date_format = DATE_FORMATS[selected(&imp.date_format_select).unwrap_or(0)];
+            mapping.decimal_separator =
+                DECIMAL_SEPARATORS[selected(&imp.decimal_select).unwrap_or(0)];
+            mapping.expense_marker = imp.expense_marker_entry.text().to_string();
+
+            // Columns can only be picked once a file lists them
+            if imp.contents.borrow().is_some() {
+                mapping.date = selected(&imp.date_column).unwrap_or(mapping.date);
+                mapping.name = selected(&imp.name_column).unwrap_or(mapping.name);
+                mapping.amount = selected(&imp.amount_column).unwrap_or(mapping.amount);
+                // First entry of the type column is "None", which unsets it
+                if let Some(position) = selected(&imp.type_column) {
+                    mapping.tr_type = position.checked_sub(1);
+                }
+            }
+        }
+
+        self.update_preview();
+    }
+
+    /// Offer the columns of the chosen file, named after the header if it has one
+    fn refresh_columns(&self) {
+        let imp = self.imp();
+        let mapping = imp.mapping.borrow().clone();
+
+        let first_record = imp
+            .contents
+            .borrow()
+            .as_deref()
+            .and_then(|text| csv::parse_records(text, mapping.delimiter).ok())
+            .and_then(|records| records.into_iter().next())
+            .map(|(_, record)| record)
+            .unwrap_or_default();
+
+        let columns: Vec<String> = first_record
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                if mapping.has_header && !name.trim().is_empty() {
+                    name.trim().to_owned()
+                } else {
+                    format!("{} {}", gettext("Column"), index + 1)
+                }
+            })
+            .collect();
+        let column_refs: Vec<&str> = columns.iter().map(|name| &name[..]).collect();
+
+        let none = gettext("None");
+        let mut type_refs = vec![&none[..]];
+        type_refs.extend(&column_refs);
+
+        let select = |dropdown: &gtk::DropDown, index: Option<usize>| match index {
+            Some(index) if index < columns.len() => dropdown.set_selected(index as u32),
+            _ => dropdown.set_selected(gtk::INVALID_LIST_POSITION),
+        };
+
+        imp.updating.set(true);
+        for (dropdown, index) in [
+            (&*imp.date_column, mapping.date),
+            (&*imp.name_column, mapping.name),
+            (&*imp.amount_column, mapping.amount),
+        ] {
+            dropdown.set_model(Some(&gtk::StringList::new(&column_refs)));
+            select(dropdown, Some(index));
+        }
+        imp.type_column
+            .set_model(Some(&gtk::StringList::new(&type_refs)));
+        match mapping.tr_type {
+            Some(index) if index < columns.len() => imp.type_column.set_selected(index as u32 + 1),
+            _ => imp.type_column.set_selected(0),
+        }
+        imp.updating.set(false);
+
+        self.update_preview();
+    }
+
+    fn selected_group(&self) -> Option<Group> {
+        self.imp()
+            .group_select
+            .selected_item()
+            .map(|item| item.downcast::<Group>().unwrap())
+    }
+
+    /// Rows of the chosen file, without the transactions an earlier import
+    /// already added to the selected group, and how many those were
+    fn read_rows(&self) -> Option<(Vec<Row>, usize)> {
+        let contents = self.imp().contents.borrow();
+        let text = contents.as_deref()?;
+
+        let entry = |transaction| Entry {
+            transaction,
+            group_name: None,
+        };
+        let mapping = self.imp().mapping.borrow();
+        let result = match self.imp().format.get() {
+            StatementFormat::Csv => mapping.read(text).map(|rows| {
+                rows.into_iter()
+                    .map(|(line, row)| (line, row.map(entry)))
+                    .collect::<Vec<Row>>()
+            }),
+            StatementFormat::Ofx => ofx::parse(text).map(|transactions| {
+                (1..)
+                    .zip(transactions.into_iter().map(|t| Ok(entry(t))))
+                    .collect()
+            }),
+            StatementFormat::Qif => {
+                let as_groups = self.imp().category_select.selected() == CATEGORIES_AS_GROUPS;
+                qif::parse(text, mapping.date_format, mapping.decimal_separator).map(|rows| {
+                    rows.into_iter()
+                        .map(|(line, row)| {
+                            (
+                                line,
+                                row.map(|qif_entry| {
+                                    if as_groups {
+                                        Entry {
+                                            transaction: qif_entry.transaction,
+                                            group_name: qif_entry.category,
+                                        }
+                                    } else {
+                                        entry(qif_entry.tagged())
+                                    }
+                                }),
+                            )
+                        })
+                        .collect()
+                })
+            }
+        };
+        let mut rows = match result {
+            Ok(rows) => rows,
+            Err(error) => {
+                self.imp().summary_label.set_label(&format!("{:#}", error));
+                return None;
+            }
+        };
+
+        let existing = self
+            .selected_group()
+            .map(|group| group.data().transactions)
+            .unwrap_or_default();
+        let read: Vec<_> = rows
+            .iter()
+            .filter_map(|(_, row)| row.as_ref().ok())
+            .map(|entry| entry.transaction.clone())
+            .collect();
+        let new_ids: Vec<_> = import::skip_imported(&existing, read)
+            .iter()
+            .map(|transaction| transaction.id)
+            .collect();
+
+        let count = rows.len();
+        rows.retain(|(_, row)| match row {
+            Ok(entry) => new_ids.contains(&entry.transaction.id),
+            Err(_) => true,
+        });
+
+        Some((rows, count - rows.len()))
+    }
+
+    /// List the first transactions of the file and how many can be imported
+    fn update_preview(&self) {
+        let imp = self.imp();
+        if imp.updating.get() {
+            return;
+        }
+
+        let by_type = imp.mapping.borrow().sign == SignConvention::TypeColumn;
+        imp.type_label.set_visible(by_type);
+        imp.type_column.set_visible(by_type);
+        imp.expense_marker_entry.set_visible(by_type);
+
+        while let Some(row) = imp.preview_list.first_child() {
+            imp.preview_list.remove(&row);
+        }
+        imp.preview_list.set_visible(false);
+        imp.import_button.set_sensitive(false);
+
+        if imp.contents.borrow().is_none() {
+            imp.summary_label.set_label(&gettext(
+                "Choose a statement exported by your bank, as CSV, OFX, QFX or QIF",
+            ));
+            return;
+        }
+
+        let (rows, already_imported) = match self.read_rows() {
+            Some(result) => result,
+            None => return,
+        };
+        let currency = self
+            .selected_group()
+            .map(|group| group.currency())
+            .unwrap_or_else(application::default_currency);
+
+        for row in rows.iter().take(PREVIEW_ROWS) {
+            imp.preview_list.append(&Self::preview_row(row, currency));
+        }
+        imp.preview_list.set_visible(!rows.is_empty());
+
+        let valid = rows.iter().filter(|(_, row)| row.is_ok()).count();
+        let skipped = rows.len() - valid;
+        let mut summary = ngettext(
+            "{} transaction will be imported",
+            "{} transactions will be imported",
+            valid as u32,
+        )
+        .replace("{}", &valid.to_string());
+        if skipped > 0 {
+            summary = format!(
+                "{}, {}",
+                summary,
+                ngettext(
+                    "{} line will be skipped",
+                    "{} lines will be skipped",
+                    skipped as u32
+                )
+                .replace("{}", &skipped.to_string())
+            );
+        }
+        if already_imported > 0 {
+            summary = format!(
+                "{}, {}",
+                summary,
+                ngettext(
+                    "{} was imported before",
+                    "{} were imported before",
+                    already_imported as u32
+                )
+                .replace("{}", &already_imported.to_string())
+            );
+        }
+        imp.summary_label.set_label(&summary);
+
+        imp.import_button
+            .set_sensitive(valid > 0 && self.selected_group().is_some());
+    }
+
+    /// Transaction as it will be imported, or why its line is skipped
+    fn preview_row((line, row): &Row, currency: &Currency) -> adw::ActionRow {
+        match row {
+            Ok(Entry {
+                transaction,
+                group_name,
+            }) => {
+                let day = transaction
+                    .day()
+                    .map(|day| day.to_string())
+                    .unwrap_or_default();
+                let action_row = adw::ActionRow::builder()
+                    .title(&transaction.name)
+                    .subtitle(&match group_name {
+                        Some(name) => format!("{} · {}", day, name),
+                        None => day,
+                    })
+                    .build();
+
+                let amount = currency.format_with(transaction.amount, application::number_format());
+                let amount_label = gtk::Label::new(Some(&match transaction.tr_type {
+                    TransactionType::EXPENSE => format!("-{}", amount),
+                    _ => amount,
+                }));
+                amount_label.add_css_class(match transaction.tr_type {
+                    TransactionType::EXPENSE => "error",
+                    _ => "success",
+                });
+                action_row.add_suffix(&amount_label);
+
+                action_row
+            }
+            Err(error) => {
+                let action_row = adw::ActionRow::builder()
+                    .title(&format!("{} {}", gettext("Line"), line))
+                    .subtitle(&error.to_string())
+                    .build();
+                action_row.add_css_class("dim-label");
+
+                action_row
+            }
+        }
+    }
+}
+
+/// Position of `value` in the drop-down `choices`
+fn position<T: PartialEq>(choices: &[T], value: &T) -> u32 {
+    choices
+        .iter()
+        .position(|choice| choice == value)
+        .unwrap_or(0) as u32
+}
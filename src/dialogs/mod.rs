@@ -1,5 +1,17 @@
+pub mod export_dialog;
 pub mod group_dialog;
+pub mod import_dialog;
+pub mod preferences_window;
+pub mod recurring_dialog;
+pub mod recurring_window;
+pub mod tags_window;
 pub mod transaction_dialog;
 
+pub use export_dialog::*;
 pub use group_dialog::*;
+pub use import_dialog::*;
+pub use preferences_window::*;
+pub use recurring_dialog::*;
+pub use recurring_window::*;
+pub use tags_window::*;
 pub use transaction_dialog::*;
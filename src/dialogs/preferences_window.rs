@@ -0,0 +1,217 @@
+use std::cell::RefCell;
+
+use gettextrs::gettext;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gio, glib, CompositeTemplate};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::application;
+use crate::models::*;
+use crate::warn_on_err;
+
+/// Setting values in the order their rows list them
+const NUMBER_FORMATS: &[&str] = &["point", "comma", "space"];
+const FIRST_DAYS_OF_WEEK: &[&str] = &["locale", "monday", "sunday", "saturday"];
+const CLOCK_FORMATS: &[&str] = &["system", "12h", "24h"];
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/matbme/beedget/ui/preferences-window.ui")]
+    pub struct PreferencesWindow {
+        #[template_child]
+        pub save_path_row: TemplateChild<adw::ActionRow>,
+
+        #[template_child]
+        pub default_currency_row: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        pub number_format_row: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        pub first_day_of_week_row: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        pub clock_format_row: TemplateChild<adw::ComboRow>,
+
+        pub folder_chooser: RefCell<Option<gtk::FileChooserNative>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PreferencesWindow {
+        const NAME: &'static str = "PreferencesWindow";
+        type Type = super::PreferencesWindow;
+        type ParentType = adw::PreferencesWindow;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+            Self::Type::bind_template_callbacks(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PreferencesWindow {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            obj.populate_currency_row();
+            obj.bind_settings();
+        }
+    }
+
+    impl WidgetImpl for PreferencesWindow {}
+    impl WindowImpl for PreferencesWindow {}
+    impl AdwWindowImpl for PreferencesWindow {}
+    impl PreferencesWindowImpl for PreferencesWindow {}
+}
+
+glib::wrapper! {
+    pub struct PreferencesWindow(ObjectSubclass<imp::PreferencesWindow>)
+        @extends adw::PreferencesWindow, adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget,
+                    gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+#[gtk::template_callbacks]
+impl PreferencesWindow {
+    pub fn new(parent: &gtk::Window) -> Self {
+        glib::Object::new(&[("transient-for", &Some(parent))])
+            .expect("Failed to create `PreferencesWindow`.")
+    }
+
+    fn application() -> crate::BeedgetApplication {
+        application::instance().expect("Application is not running")
+    }
+
+    fn settings() -> gio::Settings {
+        Self::application().settings().clone()
+    }
+
+    fn bind_settings(&self) {
+        let settings = Self::settings();
+
+        self.update_save_path_row();
+        settings.connect_changed(
+            Some("save-path"),
+            glib::clone!(@weak self as win => move |_, _| {
+                win.update_save_path_row();
+            }),
+        );
+
+        // Default currency
+        let row = &self.imp().default_currency_row;
+        if let Some(idx) = Currency::all()
+            .iter()
+            .position(|c| c == application::default_currency())
+        {
+            row.set_selected(idx as u32);
+        }
+        row.connect_selected_notify(move |row| {
+            if let Some(currency) = Currency::all().get(row.selected() as usize) {
+                warn_on_err!(settings.set_string("default-currency", currency.code));
+            }
+        });
+
+        Self::bind_choice_row(
+            &self.imp().number_format_row,
+            "number-format",
+            NUMBER_FORMATS,
+        );
+        Self::bind_choice_row(
+            &self.imp().first_day_of_week_row,
+            "first-day-of-week",
+            FIRST_DAYS_OF_WEEK,
+        );
+        Self::bind_choice_row(&self.imp().clock_format_row, "clock-format", CLOCK_FORMATS);
+    }
+
+    /// Keep row selection and a setting with a fixed set of `values` in sync
+    fn bind_choice_row(row: &adw::ComboRow, key: &'static str, values: &'static [&str]) {
+        let settings = Self::settings();
+
+        let current = settings.string(key);
+        if let Some(idx) = values.iter().position(|value| *value == current.as_str()) {
+            row.set_selected(idx as u32);
+        }
+
+        row.connect_selected_notify(move |row| {
+            if let Some(value) = values.get(row.selected() as usize) {
+                warn_on_err!(settings.set_string(key, value));
+            }
+        });
+    }
+
+    fn populate_currency_row(&self) {
+        let labels: Vec<String> = Currency::all().iter().map(|c| c.label()).collect();
+        let labels: Vec<&str> = labels.iter().map(|l| l.as_str()).collect();
+
+        self.imp()
+            .default_currency_row
+            .set_model(Some(&gtk::StringList::new(&labels)));
+        self.imp()
+            .default_currency_row
+            .set_expression(Some(&gtk::PropertyExpression::new(
+                gtk::StringObject::static_type(),
+                gtk::Expression::NONE,
+                "string",
+            )));
+    }
+
+    fn update_save_path_row(&self) {
+        let save_path = Self::application().data().save_path();
+
+        self.imp()
+            .save_path_row
+            .set_subtitle(&save_path.display().to_string());
+    }
+
+    #[template_callback]
+    fn choose_save_path(&self) {
+        let chooser = gtk::FileChooserNative::new(
+            Some(&gettext("Choose Data Folder")),
+            Some(self),
+            gtk::FileChooserAction::SelectFolder,
+            Some(&gettext("_Move Here")),
+            Some(&gettext("_Cancel")),
+        );
+        chooser.set_modal(true);
+
+        chooser.connect_response(glib::clone!(@weak self as win => move |chooser, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = chooser.file().and_then(|file| file.path()) {
+                    win.move_save_path(&path);
+                }
+            }
+
+            win.imp().folder_chooser.replace(None);
+        }));
+
+        chooser.show();
+
+        // Native dialogs are not kept alive by their parent
+        self.imp().folder_chooser.replace(Some(chooser));
+    }
+
+    /// Move existing data to `path`, telling the user if that failed
+    fn move_save_path(&self, path: &std::path::Path) {
+        let toast = match Self::application().move_save_path(path) {
+            Ok(()) => adw::Toast::new(&gettext("Data moved")),
+            Err(error) => {
+                glib::g_warning!("beedget", "{:?}", error);
+                let toast =
+                    adw::Toast::new(&format!("{}: {}", gettext("Could not move data"), error));
+                toast.set_priority(adw::ToastPriority::High);
+                toast
+            }
+        };
+
+        self.add_toast(&toast);
+    }
+}
@@ -0,0 +1,295 @@
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, glib, CompositeTemplate};
+
+use adw::subclass::prelude::*;
+
+use once_cell::sync::OnceCell;
+
+use crate::application;
+use crate::models::*;
+
+/// Frequencies in the order of the frequency dropdown
+const FREQUENCIES: [Frequency; 4] = [
+    Frequency::Daily,
+    Frequency::Weekly,
+    Frequency::Monthly,
+    Frequency::Yearly,
+];
+
+const END_NEVER: u32 = 0;
+const END_DATE: u32 = 1;
+const END_COUNT: u32 = 2;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/matbme/beedget/ui/recurring-dialog.ui")]
+    pub struct RecurringDialog {
+        #[template_child]
+        pub save_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub rule_name: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub expense_check_button: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub income_check_button: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub amount_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub interval: TemplateChild<gtk::SpinButton>,
+
+        #[template_child]
+        pub frequency: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub end: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub end_date: TemplateChild<gtk::Calendar>,
+
+        #[template_child]
+        pub end_count_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub end_count: TemplateChild<gtk::SpinButton>,
+
+        pub group: OnceCell<Group>,
+        pub rule: OnceCell<RecurringTransaction>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for RecurringDialog {
+        const NAME: &'static str = "RecurringDialog";
+        type Type = super::RecurringDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+            Self::Type::bind_template_callbacks(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for RecurringDialog {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            obj.connect_key_event_controller();
+            obj.connect_widgets();
+        }
+    }
+
+    impl WidgetImpl for RecurringDialog {}
+    impl WindowImpl for RecurringDialog {}
+    impl AdwWindowImpl for RecurringDialog {}
+}
+
+glib::wrapper! {
+    pub struct RecurringDialog(ObjectSubclass<imp::RecurringDialog>)
+        @extends gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget,
+                    gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+#[gtk::template_callbacks]
+impl RecurringDialog {
+    /// Dialog editing `rule` of `group`
+    pub fn new(parent: &gtk::Window, group: &Group, rule: &RecurringTransaction) -> Self {
+        let dialog: Self = glib::Object::new(&[("transient-for", &Some(parent))])
+            .expect("Failed to create `RecurringDialog`.");
+
+        dialog.imp().group.set(group.clone()).unwrap();
+        dialog.imp().rule.set(rule.clone()).unwrap();
+        dialog.populate_rule_values();
+
+        dialog
+    }
+
+    #[template_callback]
+    fn close_window(&self) {
+        self.destroy();
+    }
+
+    #[template_callback]
+    fn confirm_rule(&self) {
+        let group = self.imp().group.get().unwrap();
+
+        if let Some(rule) = self.selected_rule() {
+            group.set_recurring(rule);
+
+            application!(self @as crate::BeedgetApplication)
+                .emit_by_name::<()>("save-group", &[group]);
+
+            self.destroy();
+        }
+    }
+
+    /// Rule with the values of the form, `None` if any is invalid
+    fn selected_rule(&self) -> Option<RecurringTransaction> {
+        let imp = self.imp();
+        let mut rule = imp.rule.get()?.clone();
+
+        if imp.rule_name.text_length() == 0 {
+            return None;
+        }
+
+        rule.name = imp.rule_name.text().to_string();
+        rule.amount = self.amount_entry_value()?;
+        rule.tr_type = if imp.expense_check_button.is_active() {
+            TransactionType::EXPENSE
+        } else {
+            TransactionType::INCOME
+        };
+        rule.interval = imp.interval.value_as_int().max(1) as u32;
+        rule.frequency = FREQUENCIES
+            .get(imp.frequency.selected() as usize)
+            .copied()
+            .unwrap_or_default();
+        rule.end = match imp.end.selected() {
+            END_DATE => {
+                let date = imp.end_date.date();
+                RecurrenceEnd::Until {
+                    date: Date::new(date.year(), date.month() as u32, date.day_of_month() as u32)
+                        .ok()?,
+                }
+            }
+            END_COUNT => RecurrenceEnd::Count {
+                occurrences: imp.end_count.value_as_int().max(1) as u32,
+            },
+            _ => RecurrenceEnd::Never,
+        };
+
+        Some(rule)
+    }
+
+    fn amount_entry_value(&self) -> Option<Money> {
+        let currency = self.imp().group.get()?.currency();
+        let amount = currency
+            .parse(
+                self.imp().amount_entry.text().as_str(),
+                application::number_format(),
+            )
+            .ok()
+            .filter(|amount| amount.is_positive());
+
+        if amount.is_some() {
+            self.imp().amount_entry.remove_css_class("error");
+        } else {
+            self.imp().amount_entry.add_css_class("error");
+        }
+
+        amount
+    }
+
+    fn update_save_button(&self) {
+        self.imp()
+            .save_button
+            .set_sensitive(self.selected_rule().is_some());
+    }
+
+    fn update_end_widgets(&self) {
+        let end = self.imp().end.selected();
+
+        self.imp().end_date.set_visible(end == END_DATE);
+        self.imp().end_count_box.set_visible(end == END_COUNT);
+    }
+
+    fn connect_widgets(&self) {
+        self.imp().rule_name.buffer().connect_length_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_save_button();
+            }),
+        );
+
+        self.imp().amount_entry.buffer().connect_text_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_save_button();
+            }),
+        );
+
+        self.imp()
+            .end
+            .connect_selected_notify(glib::clone!(@weak self as parent => move |_| {
+                parent.update_end_widgets();
+            }));
+    }
+
+    /// Fill entries with the values of the rule being edited
+    fn populate_rule_values(&self) {
+        let imp = self.imp();
+        let rule = imp.rule.get().unwrap();
+
+        imp.rule_name
+            .set_buffer(&gtk::EntryBuffer::new(Some(&rule.name)));
+        imp.amount_entry.set_buffer(&gtk::EntryBuffer::new(Some(
+            &application::number_format().apply(&rule.amount.to_string()),
+        )));
+        imp.amount_entry
+            .set_placeholder_text(Some(imp.group.get().unwrap().currency().symbol));
+
+        match rule.tr_type {
+            TransactionType::INCOME => imp.income_check_button.set_active(true),
+            _ => imp.expense_check_button.set_active(true),
+        }
+
+        imp.interval.set_value(rule.interval as f64);
+        imp.frequency.set_selected(
+            FREQUENCIES
+                .iter()
+                .position(|frequency| *frequency == rule.frequency)
+                .unwrap_or_default() as u32,
+        );
+
+        match rule.end {
+            RecurrenceEnd::Never => imp.end.set_selected(END_NEVER),
+            RecurrenceEnd::Until { date } => {
+                if let Ok(date) = glib::DateTime::from_local(
+                    date.year(),
+                    date.month() as i32,
+                    date.day() as i32,
+                    0,
+                    0,
+                    0.0,
+                ) {
+                    imp.end_date.select_day(&date);
+                }
+                imp.end.set_selected(END_DATE);
+            }
+            RecurrenceEnd::Count { occurrences } => {
+                imp.end_count.set_value(occurrences as f64);
+                imp.end.set_selected(END_COUNT);
+            }
+        }
+
+        self.update_end_widgets();
+        self.update_save_button();
+    }
+
+    /// Handle keyboard events
+    fn connect_key_event_controller(&self) {
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.connect_key_pressed(
+            glib::clone!(@strong self as parent => move |_, keyval, _, _| {
+                match keyval {
+                    gdk::Key::Escape => { // Esc closes dialog
+                        parent.destroy();
+                        gtk::Inhibit(true)
+                    }
+                    _ => { gtk::Inhibit(false) }
+                }
+            }),
+        );
+
+        self.add_controller(&key_controller);
+    }
+}
@@ -0,0 +1,231 @@
+use std::cell::RefCell;
+
+use gettextrs::{gettext, ngettext};
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::application;
+use crate::dialogs::*;
+use crate::models::*;
+
+/// How often `rule` repeats, e.g. "Every 2 weeks"
+pub fn describe_frequency(rule: &RecurringTransaction) -> String {
+    let n = rule.interval.max(1);
+    let text = match rule.frequency {
+        Frequency::Daily => ngettext("Every day", "Every {} days", n),
+        Frequency::Weekly => ngettext("Every week", "Every {} weeks", n),
+        Frequency::Monthly => ngettext("Every month", "Every {} months", n),
+        Frequency::Yearly => ngettext("Every year", "Every {} years", n),
+    };
+
+    text.replace("{}", &n.to_string())
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/matbme/beedget/ui/recurring-window.ui")]
+    pub struct RecurringWindow {
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        pub rules_list: TemplateChild<gtk::ListBox>,
+
+        /// Groups whose rules are listed, with the handler refreshing the list
+        pub group_handlers: RefCell<Vec<(Group, glib::SignalHandlerId)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for RecurringWindow {
+        const NAME: &'static str = "RecurringWindow";
+        type Type = super::RecurringWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for RecurringWindow {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            let application = application::instance().expect("Application is not running");
+            application.data().group_model().connect_items_changed(
+                glib::clone!(@weak obj as win => move |_, _, _, _| {
+                    win.connect_groups();
+                }),
+            );
+
+            obj.connect_groups();
+        }
+
+        fn dispose(&self, _obj: &Self::Type) {
+            for (group, handler) in self.group_handlers.take() {
+                group.disconnect(handler);
+            }
+        }
+    }
+
+    impl WidgetImpl for RecurringWindow {}
+    impl WindowImpl for RecurringWindow {}
+    impl AdwWindowImpl for RecurringWindow {}
+}
+
+glib::wrapper! {
+    pub struct RecurringWindow(ObjectSubclass<imp::RecurringWindow>)
+        @extends adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget,
+                    gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl RecurringWindow {
+    pub fn new(parent: &gtk::Window) -> Self {
+        glib::Object::new(&[("transient-for", &Some(parent))])
+            .expect("Failed to create `RecurringWindow`.")
+    }
+
+    fn groups() -> Vec<Group> {
+        application::instance()
+            .map(|application| application.data().groups.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    /// Follow rule changes of every group and list their rules
+    fn connect_groups(&self) {
+        for (group, handler) in self.imp().group_handlers.take() {
+            group.disconnect(handler);
+        }
+
+        let handlers = Self::groups()
+            .into_iter()
+            .map(|group| {
+                let handler = group.connect_local(
+                    "recurring-changed",
+                    false,
+                    glib::clone!(@weak self as win => @default-return None, move |_| {
+                        win.refresh_rules();
+                        None
+                    }),
+                );
+
+                (group, handler)
+            })
+            .collect();
+        self.imp().group_handlers.replace(handlers);
+
+        self.refresh_rules();
+    }
+
+    fn refresh_rules(&self) {
+        let list = &self.imp().rules_list;
+        while let Some(row) = list.first_child() {
+            list.remove(&row);
+        }
+
+        let mut count = 0;
+        for group in Self::groups() {
+            for rule in group.recurring() {
+                list.append(&self.rule_row(&group, &rule));
+                count += 1;
+            }
+        }
+
+        self.imp()
+            .stack
+            .set_visible_child_name(if count > 0 { "rules" } else { "empty" });
+    }
+
+    fn rule_row(&self, group: &Group, rule: &RecurringTransaction) -> adw::ActionRow {
+        let next = if rule.paused {
+            gettext("Paused")
+        } else {
+            match rule.next_occurrence() {
+                Some(date) => format!("{} {}", gettext("Next:"), date),
+                None => gettext("Finished"),
+            }
+        };
+
+        let row = adw::ActionRow::builder()
+            .title(&rule.name)
+            .subtitle(&format!(
+                "{} {} · {} · {}",
+                group.emoji(),
+                group.name(),
+                describe_frequency(rule),
+                next
+            ))
+            .build();
+
+        let sign = match rule.tr_type {
+            TransactionType::EXPENSE => "-",
+            TransactionType::INCOME => "+",
+            TransactionType::TRANSFER => "",
+        };
+        let amount = gtk::Label::new(Some(&format!(
+            "{}{}",
+            sign,
+            group
+                .currency()
+                .format_with(rule.amount, application::number_format())
+        )));
+        row.add_suffix(&amount);
+
+        let active = gtk::Switch::builder()
+            .active(!rule.paused)
+            .valign(gtk::Align::Center)
+            .tooltip_text(&gettext("Add transactions automatically"))
+            .build();
+        let rule_id = rule.id;
+        active.connect_state_set(
+            glib::clone!(@weak group => @default-return gtk::Inhibit(false), move |_, state| {
+                group.set_recurring_paused(rule_id, !state);
+                Self::save(&group);
+                gtk::Inhibit(false)
+            }),
+        );
+        row.add_suffix(&active);
+
+        let edit = gtk::Button::builder()
+            .icon_name("document-edit-symbolic")
+            .valign(gtk::Align::Center)
+            .tooltip_text(&gettext("Edit"))
+            .css_classes(vec![String::from("flat")])
+            .build();
+        let edit_rule = rule.clone();
+        edit.connect_clicked(glib::clone!(@weak self as win, @weak group => move |_| {
+            RecurringDialog::new(win.upcast_ref(), &group, &edit_rule).present();
+        }));
+        row.add_suffix(&edit);
+
+        let delete = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .valign(gtk::Align::Center)
+            .tooltip_text(&gettext("Delete"))
+            .css_classes(vec![String::from("flat")])
+            .build();
+        delete.connect_clicked(glib::clone!(@weak group => move |_| {
+            group.delete_recurring(rule_id);
+            Self::save(&group);
+        }));
+        row.add_suffix(&delete);
+
+        row
+    }
+
+    fn save(group: &Group) {
+        if let Some(application) = application::instance() {
+            application.emit_by_name::<()>("save-group", &[group]);
+        }
+    }
+}
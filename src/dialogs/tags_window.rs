@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+
+use gettextrs::{gettext, ngettext};
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::application;
+use crate::models::*;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/matbme/beedget/ui/tags-window.ui")]
+    pub struct TagsWindow {
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        pub tags_list: TemplateChild<gtk::ListBox>,
+
+        /// Groups whose transactions are counted, with the handlers refreshing the list
+        pub group_handlers: RefCell<Vec<(Group, glib::SignalHandlerId)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TagsWindow {
+        const NAME: &'static str = "TagsWindow";
+        type Type = super::TagsWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for TagsWindow {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            let application = application::instance().expect("Application is not running");
+            application.data().group_model().connect_items_changed(
+                glib::clone!(@weak obj as win => move |_, _, _, _| {
+                    win.connect_groups();
+                }),
+            );
+
+            obj.connect_groups();
+        }
+
+        fn dispose(&self, _obj: &Self::Type) {
+            for (group, handler) in self.group_handlers.take() {
+                group.disconnect(handler);
+            }
+        }
+    }
+
+    impl WidgetImpl for TagsWindow {}
+    impl WindowImpl for TagsWindow {}
+    impl AdwWindowImpl for TagsWindow {}
+}
+
+glib::wrapper! {
+    /// Income and expenses of every tag, summed across all groups
+    pub struct TagsWindow(ObjectSubclass<imp::TagsWindow>)
+        @extends adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget,
+                    gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl TagsWindow {
+    pub fn new(parent: &gtk::Window) -> Self {
+        glib::Object::new(&[("transient-for", &Some(parent))])
+            .expect("Failed to create `TagsWindow`.")
+    }
+
+    /// Follow transaction changes of every group and list tag totals
+    fn connect_groups(&self) {
+        for (group, handler) in self.imp().group_handlers.take() {
+            group.disconnect(handler);
+        }
+
+        let groups = application::instance()
+            .map(|application| application.data().groups.borrow().clone())
+            .unwrap_or_default();
+
+        let mut handlers = vec![];
+        for group in groups {
+            for signal in [
+                "transaction-added",
+                "transaction-removed",
+                "transaction-changed",
+            ] {
+                let handler = group.connect_local(
+                    signal,
+                    false,
+                    glib::clone!(@weak self as win => @default-return None, move |_| {
+                        win.refresh_tags();
+                        None
+                    }),
+                );
+                handlers.push((group.clone(), handler));
+            }
+        }
+        self.imp().group_handlers.replace(handlers);
+
+        self.refresh_tags();
+    }
+
+    fn refresh_tags(&self) {
+        let list = &self.imp().tags_list;
+        while let Some(row) = list.first_child() {
+            list.remove(&row);
+        }
+
+        let application = application::instance().expect("Application is not running");
+        let (totals, currency) = match application.data().tag_totals(None) {
+            Ok(result) => result,
+            Err(error) => {
+                application.report_error(&error, None);
+                self.imp().stack.set_visible_child_name("empty");
+                return;
+            }
+        };
+
+        for (tag, total) in &totals {
+            list.append(&Self::tag_row(tag, total, currency));
+        }
+
+        self.imp()
+            .stack
+            .set_visible_child_name(if totals.is_empty() { "empty" } else { "tags" });
+    }
+
+    fn tag_row(tag: &str, total: &TagTotal, currency: &Currency) -> adw::ActionRow {
+        let row = adw::ActionRow::builder()
+            .title(tag)
+            .subtitle(
+                &ngettext("{} transaction", "{} transactions", total.count as u32)
+                    .replace("{}", &total.count.to_string()),
+            )
+            .build();
+
+        let number_format = application::number_format();
+        for (amount, class, tooltip) in [
+            (total.income, "success", gettext("Income")),
+            (total.expenses, "error", gettext("Expenses")),
+        ] {
+            if amount.is_zero() {
+                continue;
+            }
+
+            let label = gtk::Label::builder()
+                .label(&currency.format_with(amount, number_format))
+                .tooltip_text(&tooltip)
+                .css_classes(vec![String::from(class)])
+                .build();
+            row.add_suffix(&label);
+        }
+
+        row
+    }
+}
@@ -1,3 +1,4 @@
+use gettextrs::{gettext, ngettext};
 use glib::{ParamFlags, ParamSpec, ParamSpecObject};
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
@@ -11,6 +12,14 @@ use crate::application;
 use crate::models::*;
 use crate::widgets::*;
 
+/// Frequencies offered by the repeat dropdown, after "Never"
+const REPEAT_FREQUENCIES: [Frequency; 4] = [
+    Frequency::Daily,
+    Frequency::Weekly,
+    Frequency::Monthly,
+    Frequency::Yearly,
+];
+
 mod imp {
     use super::*;
 
@@ -29,18 +38,66 @@ mod imp {
         #[template_child]
         pub group_select: TemplateChild<gtk::DropDown>,
 
+        #[template_child]
+        pub destination_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub destination_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub destination_select: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub tags_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub payee_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub location_entry: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        pub notes_view: TemplateChild<gtk::TextView>,
+
         #[template_child]
         pub dt_picker: TemplateChild<DateTimePicker>,
 
+        #[template_child]
+        pub type_box: TemplateChild<gtk::Box>,
+
         #[template_child]
         pub expense_check_button: TemplateChild<gtk::CheckButton>,
 
         #[template_child]
         pub income_check_button: TemplateChild<gtk::CheckButton>,
 
+        #[template_child]
+        pub transfer_check_button: TemplateChild<gtk::CheckButton>,
+
         #[template_child]
         pub amount_entry: TemplateChild<gtk::Entry>,
 
+        #[template_child]
+        pub installment_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub installment_count: TemplateChild<gtk::SpinButton>,
+
+        #[template_child]
+        pub installment_preview: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub split_check: TemplateChild<gtk::CheckButton>,
+
+        #[template_child]
+        pub split_editor: TemplateChild<SplitEditor>,
+
+        #[template_child]
+        pub repeat_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub repeat_select: TemplateChild<gtk::DropDown>,
+
         pub edit_transaction: OnceCell<Transaction>,
         pub current_group: OnceCell<Group>,
     }
@@ -114,6 +171,11 @@ mod imp {
 
             obj.connect_key_event_controller();
             obj.connect_add_button_valid();
+            obj.connect_installment_preview();
+            obj.connect_transfer_widgets();
+            obj.connect_split_widgets();
+            obj.setup_tags_completion();
+            obj.setup_payee_completion();
 
             if self.group_select.model().is_none() {
                 obj.populate_group_select_dropdown();
@@ -165,108 +227,432 @@ impl TransactionDialog {
 
     #[template_callback]
     fn confirm_transaction(&self) {
-        if self.imp().edit_transaction.get().is_some() {
-            let selected_group_id = self
-                .imp()
-                .group_select
-                .selected_item()
-                .unwrap()
-                .downcast_ref::<Group>()
-                .unwrap()
-                .id();
-
-            let current_group_id = self.imp().current_group.get().unwrap().id();
-            if selected_group_id != current_group_id {
-                // Edit transaction, change group
-                self.change_transaction_group();
-            } else {
-                // Edit transaction, same group
-                self.edit_transaction();
+        let (transaction, group) = match (
+            self.imp().edit_transaction.get(),
+            self.imp().current_group.get(),
+        ) {
+            (Some(transaction), Some(group)) => (transaction, group),
+            _ => {
+                // Create transaction
+                self.create_transaction();
+                self.destroy();
+                return;
             }
+        };
+
+        let later = group.later_installments(transaction);
+        if later.is_empty() {
+            self.edit_transaction(&[]);
+            self.destroy();
         } else {
-            // Create transaction
-            self.create_transaction();
+            ask_about_later_installments(
+                self.upcast_ref(),
+                &gettext("Edit later installments too?"),
+                later.len(),
+                glib::clone!(@weak self as dialog => move |include_later| {
+                    dialog.edit_transaction(if include_later { &later[..] } else { &[] });
+                    dialog.destroy();
+                }),
+            );
         }
-
-        let selected_group = self
-            .imp()
-            .group_select
-            .selected_item()
-            .unwrap()
-            .downcast::<Group>()
-            .unwrap();
-        application!(self @as crate::BeedgetApplication)
-            .data()
-            .save_group(&selected_group);
-
-        self.destroy();
     }
 
-    fn change_transaction_group(&self) {
+    /// Apply entered values to the edited transaction and to `later`
+    /// installments, moving them if another group was selected
+    fn edit_transaction(&self, later: &[Transaction]) {
         let transaction = self.imp().edit_transaction.get().unwrap();
+        let previous_date = transaction.date();
+        let previous_amount = transaction.amount();
 
-        let prev_group = self.imp().current_group.get().unwrap();
-        prev_group.delete_transaction(transaction.id());
+        transaction.set_name(&self.imp().transaction_name.text());
+        transaction.change_tr_type(self.selected_tr_type());
+        transaction.set_amount(self.amount_entry_value().unwrap());
+        transaction.set_date(self.selected_date());
+        self.apply_details(transaction);
+
+        let date = transaction.date();
+        let number = transaction
+            .installment()
+            .map(|installment| installment.number)
+            .unwrap_or_default();
+
+        // Amounts only follow when changed, split again so the remainder
+        // of the purchase stays where it was
+        let previous: Vec<Money> = std::iter::once(previous_amount)
+            .chain(later.iter().map(|installment| installment.amount()))
+            .collect();
+        let amounts = installment::respread_installments(
+            &previous,
+            transaction.amount(),
+            self.selected_currency().decimals,
+        );
+        transaction.set_amount(amounts[0]);
+
+        for (installment, amount) in later.iter().zip(&amounts[1..]) {
+            installment.set_name(&transaction.name());
+            installment.change_tr_type(transaction.tr_type());
+            installment.set_amount(*amount);
+            installment.copy_details(transaction);
+
+            // Keep later installments one month apart from the edited one
+            if date.to_unix() != previous_date.to_unix() {
+                let months = installment
+                    .installment()
+                    .map_or(0, |installment| installment.number - number);
+                installment.set_date(date.add_months(months as i32).expect("Invalid date"));
+            }
+        }
 
-        application!(self @as crate::BeedgetApplication)
-            .data()
-            .save_group(prev_group);
+        let application = application!(self @as crate::BeedgetApplication);
+        let current_group = self.imp().current_group.get().unwrap();
+        let selected_group = self.selected_group();
+        let edited = std::iter::once(transaction).chain(later);
+
+        // Both sides of a transfer always match
+        if let Some((group, counterpart)) = application.data().transfer_counterpart(transaction) {
+            match application.data().convert(
+                transaction.amount(),
+                current_group.currency(),
+                group.currency(),
+            ) {
+                Ok(amount) => counterpart.set_amount(amount),
+                Err(error) => application.report_error(&error, Some(&group)),
+            }
+            counterpart.set_name(&transaction.name());
+            counterpart.set_date(transaction.date());
+            counterpart.copy_details(transaction);
 
-        self.create_transaction();
-    }
+            application.emit_by_name::<()>("save-transaction", &[&group, &counterpart]);
+        }
 
-    fn edit_transaction(&self) {
-        let transaction = self.imp().edit_transaction.get().unwrap();
+        // Shares of a split keep the receipt details, and their total follows
+        // the amount of each share
+        if let Some(split) = transaction.split() {
+            let total = split.total - previous_amount + transaction.amount();
+            transaction.set_split_total(total);
 
-        transaction.set_name(&self.imp().transaction_name.text());
-        transaction.change_tr_type(if self.imp().expense_check_button.is_active() {
-            TransactionType::EXPENSE
+            for (group, sibling) in application.data().split_siblings(transaction) {
+                sibling.set_name(&transaction.name());
+                sibling.change_tr_type(transaction.tr_type());
+                sibling.set_date(transaction.date());
+                sibling.copy_details(transaction);
+                sibling.set_split_total(total);
+
+                application.emit_by_name::<()>("save-transaction", &[&group, &sibling]);
+            }
+        }
+
+        if selected_group.id() != current_group.id() {
+            for transaction in edited {
+                current_group.delete_transaction(transaction.id());
+                application.emit_by_name::<()>("delete-transaction", &[current_group, transaction]);
+
+                selected_group.new_transaction(transaction.clone());
+                application.emit_by_name::<()>("save-transaction", &[&selected_group, transaction]);
+            }
         } else {
-            TransactionType::INCOME
-        });
-        transaction.set_amount(self.amount_entry_value().unwrap());
-        transaction.set_date(
-            glib::DateTime::from_iso8601(
-                self.imp()
-                    .dt_picker
-                    .property::<glib::GString>("selected-date")
-                    .as_str(),
-                None,
-            )
-            .expect("Invalid date"),
-        );
+            for transaction in edited {
+                application.emit_by_name::<()>("save-transaction", &[current_group, transaction]);
+            }
+        }
     }
 
     fn create_transaction(&self) {
         let transaction = Transaction::new(
             &self.imp().transaction_name.text(),
-            {
-                if self.imp().expense_check_button.is_active() {
-                    TransactionType::EXPENSE
-                } else {
-                    TransactionType::INCOME
-                }
-            },
+            self.selected_tr_type(),
             self.amount_entry_value().unwrap(),
-            glib::DateTime::from_iso8601(
-                self.imp()
-                    .dt_picker
-                    .property::<glib::GString>("selected-date")
-                    .as_str(),
-                None,
-            )
-            .expect("Invalid date"),
+            self.selected_date(),
         );
+        self.apply_details(&transaction);
 
-        let selected_group = self
-            .imp()
+        let selected_group = self.selected_group();
+        let application = application!(self @as crate::BeedgetApplication);
+
+        if transaction.tr_type() == TransactionType::TRANSFER {
+            self.create_transfer(&transaction, &selected_group);
+            return;
+        }
+
+        if self.imp().split_check.is_active() {
+            self.create_split(&transaction, &selected_group);
+            return;
+        }
+
+        let installments = self.imp().installment_count.value_as_int().max(1) as u32;
+        if installments > 1 {
+            for part in installment::split_installments(
+                &transaction.data(),
+                installments,
+                selected_group.currency().decimals,
+            ) {
+                selected_group.new_transaction(Transaction::from_data(part));
+            }
+
+            application.emit_by_name::<()>("save-group", &[&selected_group]);
+            return;
+        }
+
+        selected_group.new_transaction(transaction.clone());
+
+        match self.selected_repeat() {
+            Some(frequency) => {
+                selected_group.set_recurring(RecurringTransaction::from_transaction(
+                    &transaction.data(),
+                    frequency,
+                    1,
+                    RecurrenceEnd::Never,
+                ));
+
+                application.emit_by_name::<()>("save-group", &[&selected_group]);
+
+                // A start date in the past may already have occurrences due
+                application.add_due_recurring();
+            }
+            None => {
+                application
+                    .emit_by_name::<()>("save-transaction", &[&selected_group, &transaction]);
+            }
+        }
+    }
+
+    /// Add both sides of a transfer from `source` to the selected destination
+    fn create_transfer(&self, transaction: &Transaction, source: &Group) {
+        let application = application!(self @as crate::BeedgetApplication);
+        let destination = self.selected_destination().unwrap();
+
+        let received = match application.data().convert(
+            transaction.amount(),
+            source.currency(),
+            destination.currency(),
+        ) {
+            Ok(received) => received,
+            Err(error) => {
+                application.report_error(&error, None);
+                return;
+            }
+        };
+
+        let data = transaction.data();
+        let (outgoing, incoming) = transfer::transfer_pair(
+            &data.name,
+            data.amount,
+            received,
+            &data.date,
+            source.id(),
+            destination.id(),
+        );
+
+        // Both sides keep the details of the entered transaction
+        for (group, side) in [(source, outgoing), (&destination, incoming)] {
+            let side = Transaction::from_data(beedget_core::Transaction {
+                id: side.id,
+                amount: side.amount,
+                transfer: side.transfer,
+                ..data.clone()
+            });
+            group.new_transaction(side.clone());
+            application.emit_by_name::<()>("save-transaction", &[group, &side]);
+        }
+    }
+
+    /// Add a share of `transaction` to `main_group` and each group of the
+    /// split editor
+    fn create_split(&self, transaction: &Transaction, main_group: &Group) {
+        let application = application!(self @as crate::BeedgetApplication);
+
+        let mut shares = vec![(main_group.clone(), self.main_share().unwrap())];
+        shares.extend(self.imp().split_editor.shares().unwrap());
+
+        let amounts: Vec<_> = shares
+            .iter()
+            .map(|(group, amount)| (group.id(), *amount))
+            .collect();
+
+        match split::split_transaction(&transaction.data(), &amounts) {
+            Ok(parts) => {
+                for ((group, _), (_, part)) in shares.iter().zip(parts) {
+                    let part = Transaction::from_data(part);
+                    group.new_transaction(part.clone());
+                    application.emit_by_name::<()>("save-transaction", &[group, &part]);
+                }
+            }
+            Err(error) => application.report_error(&error, None),
+        }
+    }
+
+    fn selected_group(&self) -> Group {
+        self.imp()
             .group_select
             .selected_item()
             .unwrap()
             .downcast::<Group>()
-            .unwrap();
+            .unwrap()
+    }
+
+    fn selected_tr_type(&self) -> TransactionType {
+        if self.imp().expense_check_button.is_active() {
+            TransactionType::EXPENSE
+        } else if self.imp().transfer_check_button.is_active() {
+            TransactionType::TRANSFER
+        } else {
+            TransactionType::INCOME
+        }
+    }
+
+    fn selected_tags(&self) -> Vec<String> {
+        tags::parse_tags(&self.imp().tags_entry.text())
+    }
+
+    /// Set tags, payee, location and notes of `transaction` to the entered ones
+    fn apply_details(&self, transaction: &Transaction) {
+        let imp = self.imp();
+        let notes = imp.notes_view.buffer();
+
+        transaction.set_tags(self.selected_tags());
+        transaction.set_payee(&imp.payee_entry.text());
+        transaction.set_location(&imp.location_entry.text());
+        transaction.set_notes(&notes.text(&notes.start_iter(), &notes.end_iter(), false));
+    }
+
+    /// Suggest payees of earlier transactions
+    fn setup_payee_completion(&self) {
+        let payees = application::instance()
+            .map(|application| application.data().payees())
+            .unwrap_or_default();
+
+        let completion = gtk::EntryCompletion::new();
+        completion.set_model(Some(&string_store(&payees)));
+        completion.set_text_column(0);
+        completion.set_inline_completion(true);
+
+        self.imp().payee_entry.set_completion(Some(&completion));
+    }
+
+    /// Suggest tags already in use for the one being typed, which is the
+    /// text after the last comma
+    fn setup_tags_completion(&self) {
+        let tags = application::instance()
+            .map(|application| application.data().all_tags())
+            .unwrap_or_default();
+
+        let completion = gtk::EntryCompletion::new();
+        completion.set_model(Some(&string_store(&tags)));
+        completion.set_text_column(0);
+        completion.set_minimum_key_length(0);
+        completion.set_match_func(|completion, key, iter| {
+            let typed = key.rsplit(',').next().unwrap_or_default().trim();
+            let tag = completion.model().unwrap().get::<String>(iter, 0);
+
+            !typed.is_empty() && tag.starts_with(&typed.to_lowercase())
+        });
+        completion.connect_match_selected(
+            glib::clone!(@weak self as parent => @default-return gtk::Inhibit(false), move |_, model, iter| {
+                let entry = &parent.imp().tags_entry;
+                let text = entry.text();
+                let done = text.rfind(',').map_or("", |comma| &text[..=comma]);
+                let tag = model.get::<String>(iter, 0);
+
+                let text = if done.is_empty() {
+                    format!("{}, ", tag)
+                } else {
+                    format!("{} {}, ", done, tag)
+                };
+                entry.set_text(&text);
+                entry.set_position(-1);
 
-        selected_group.new_transaction(transaction);
+                gtk::Inhibit(true)
+            }),
+        );
+
+        self.imp().tags_entry.set_completion(Some(&completion));
+    }
+
+    /// Group receiving a transfer, `None` unless one is selected
+    fn selected_destination(&self) -> Option<Group> {
+        self.imp()
+            .destination_select
+            .selected_item()
+            .and_then(|item| item.downcast::<Group>().ok())
+    }
+
+    fn selected_date(&self) -> glib::DateTime {
+        glib::DateTime::from_iso8601(
+            self.imp()
+                .dt_picker
+                .property::<glib::GString>("selected-date")
+                .as_str(),
+            None,
+        )
+        .expect("Invalid date")
+    }
+
+    /// Show the amount of each installment, or nothing for a single payment
+    fn update_installment_preview(&self) {
+        let count = self.imp().installment_count.value_as_int().max(1) as u32;
+
+        self.update_exclusive_options();
+
+        let amount = if self.imp().amount_entry.text_length() > 0 {
+            self.amount_entry_value()
+        } else {
+            None
+        };
+
+        let preview = match amount {
+            Some(amount) if count > 1 => {
+                let currency = self.selected_currency();
+                let purchase = beedget_core::Transaction {
+                    amount,
+                    ..Default::default()
+                };
+                let parts = installment::split_installments(&purchase, count, currency.decimals);
+
+                format!(
+                    "{} × {}",
+                    count,
+                    currency.format_with(
+                        parts[count as usize - 1].amount,
+                        application::number_format()
+                    )
+                )
+            }
+            _ => String::new(),
+        };
+
+        self.imp().installment_preview.set_label(&preview);
+    }
+
+    /// Installments, repeating, transfers and splits cannot be combined
+    fn update_exclusive_options(&self) {
+        let imp = self.imp();
+        let installments = imp.installment_count.value_as_int() > 1;
+        let repeats = self.selected_repeat().is_some();
+        let transfer = imp.transfer_check_button.is_active();
+        let split = imp.split_check.is_active();
+
+        imp.installment_box
+            .set_sensitive(!repeats && !transfer && !split);
+        imp.repeat_box
+            .set_sensitive(!installments && !transfer && !split);
+        imp.split_check
+            .set_sensitive(!installments && !repeats && !transfer);
+
+        // Edited transactions keep whether they are a transfer
+        if imp.edit_transaction.get().is_none() {
+            imp.transfer_check_button
+                .set_sensitive(!installments && !repeats && !split);
+        }
+    }
+
+    /// How often the new transaction repeats, `None` if it does not
+    fn selected_repeat(&self) -> Option<Frequency> {
+        let selected = self.imp().repeat_select.selected() as usize;
+
+        selected
+            .checked_sub(1)
+            .and_then(|index| REPEAT_FREQUENCIES.get(index))
+            .copied()
     }
 
     /// Disables button if name and/or amount entries are empty
@@ -279,26 +665,207 @@ impl TransactionDialog {
         // Subscribe to changes
         self.imp().transaction_name.buffer().connect_length_notify(
             glib::clone!(@weak self as parent => move |_| {
-                    parent.imp().add_button.set_sensitive(
-                        parent.imp().transaction_name.text_length() > 0 &&
-                        parent.amount_entry_value().is_some()
-                    );
+                    parent.update_add_button();
             }),
         );
 
         self.imp().amount_entry.buffer().connect_text_notify(
             glib::clone!(@weak self as parent => move |_| {
-                    parent.imp().add_button.set_sensitive(
-                        parent.amount_entry_value().is_some() &&
-                        parent.imp().transaction_name.text_length() > 0
+                    parent.update_add_button();
+            }),
+        );
+
+        // Allowed decimal places depend on the group currency
+        self.imp().group_select.connect_selected_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                    parent.imp().amount_entry.set_placeholder_text(
+                        Some(parent.selected_currency().symbol)
                     );
+
+                    if parent.imp().amount_entry.text_length() > 0 {
+                        parent.update_add_button();
+                    }
+            }),
+        );
+    }
+
+    fn update_add_button(&self) {
+        let transfer_valid = !self.imp().transfer_check_button.is_active()
+            || self.selected_destination().map(|group| group.id())
+                != self
+                    .imp()
+                    .group_select
+                    .selected_item()
+                    .map(|item| item.downcast_ref::<Group>().unwrap().id());
+
+        let split_valid = !self.imp().split_check.is_active() || self.main_share().is_ok();
+
+        self.imp().add_button.set_sensitive(
+            self.amount_entry_value().is_some()
+                && self.imp().transaction_name.text_length() > 0
+                && transfer_valid
+                && split_valid,
+        );
+    }
+
+    fn connect_split_widgets(&self) {
+        self.imp().split_check.connect_toggled(
+            glib::clone!(@weak self as parent => move |button| {
+                let editor = &parent.imp().split_editor;
+                editor.set_visible(button.is_active());
+                if button.is_active() && editor.shares().is_none_or(|shares| shares.is_empty()) {
+                    editor.add_share();
+                }
+
+                parent.update_exclusive_options();
+                parent.update_split_remaining();
+            }),
+        );
+
+        self.imp().split_editor.connect_local(
+            "changed",
+            false,
+            glib::clone!(@weak self as parent => @default-return None, move |_| {
+                parent.update_split_remaining();
+                None
+            }),
+        );
+
+        self.imp().repeat_select.connect_selected_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_exclusive_options();
+            }),
+        );
+
+        self.imp().amount_entry.buffer().connect_text_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_split_remaining();
+            }),
+        );
+
+        self.imp().group_select.connect_selected_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.imp().split_editor.set_currency(parent.selected_currency());
+                parent.update_split_remaining();
+            }),
+        );
+    }
+
+    /// Share left for the selected group after the other groups took theirs.
+    ///
+    /// `Err` explains why the split cannot be made, and is empty while
+    /// entries are incomplete.
+    fn main_share(&self) -> Result<Money, String> {
+        let total = self.amount_entry_value().ok_or_else(String::new)?;
+        let shares = self.imp().split_editor.shares().ok_or_else(String::new)?;
+        let main_group = self.selected_group();
+
+        for (index, (group, _)) in shares.iter().enumerate() {
+            let repeated = shares[..index]
+                .iter()
+                .any(|(other, _)| other.id() == group.id());
+            if repeated || group.id() == main_group.id() {
+                return Err(gettext("Each group can only have one share"));
+            }
+
+            if group.currency().code != main_group.currency().code {
+                return Err(gettext("Groups must use the same currency"));
+            }
+        }
+
+        let others: Money = shares.iter().map(|(_, amount)| *amount).sum();
+        let share = total - others;
+        if share.is_positive() {
+            Ok(share)
+        } else {
+            Err(gettext("Shares add up to more than the total"))
+        }
+    }
+
+    fn update_split_remaining(&self) {
+        if !self.imp().split_check.is_active() {
+            return;
+        }
+
+        let text = match self.main_share() {
+            Ok(share) => {
+                let group = self.selected_group();
+                format!(
+                    "{} {} {}",
+                    group.emoji(),
+                    group.name(),
+                    gettext("keeps {}").replace(
+                        "{}",
+                        &group
+                            .currency()
+                            .format_with(share, application::number_format())
+                    )
+                )
+            }
+            Err(problem) => problem,
+        };
+        self.imp().split_editor.set_remaining_text(&text);
+
+        if self.imp().amount_entry.text_length() > 0 {
+            self.update_add_button();
+        }
+    }
+
+    /// Show destination of transfers, which must differ from the source group
+    fn connect_transfer_widgets(&self) {
+        self.imp().transfer_check_button.connect_toggled(
+            glib::clone!(@weak self as parent => move |button| {
+                parent.imp().destination_box.set_visible(button.is_active());
+                parent.update_installment_preview();
+
+                if parent.imp().amount_entry.text_length() > 0 {
+                    parent.update_add_button();
+                }
+            }),
+        );
+
+        self.imp().destination_select.connect_selected_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                if parent.imp().amount_entry.text_length() > 0 {
+                    parent.update_add_button();
+                }
             }),
         );
     }
 
-    fn amount_entry_value(&self) -> Option<f32> {
-        if let Ok(amount) = self.imp().amount_entry.text().as_str().parse::<f32>() {
-            if amount > 0.0 {
+    fn connect_installment_preview(&self) {
+        self.imp().installment_count.connect_value_changed(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_installment_preview();
+            }),
+        );
+
+        self.imp().amount_entry.buffer().connect_text_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_installment_preview();
+            }),
+        );
+
+        self.imp().group_select.connect_selected_notify(
+            glib::clone!(@weak self as parent => move |_| {
+                parent.update_installment_preview();
+            }),
+        );
+    }
+
+    /// Currency of the group currently selected in the dropdown
+    fn selected_currency(&self) -> &'static Currency {
+        match self.imp().group_select.selected_item() {
+            Some(item) => item.downcast_ref::<Group>().unwrap().currency(),
+            None => application::default_currency(),
+        }
+    }
+
+    fn amount_entry_value(&self) -> Option<Money> {
+        let currency = self.selected_currency();
+        let text = self.imp().amount_entry.text();
+        if let Ok(amount) = currency.parse(text.as_str(), application::number_format()) {
+            if amount.is_positive() {
                 self.imp().amount_entry.remove_css_class("error");
                 Some(amount)
             } else {
@@ -340,6 +907,16 @@ impl TransactionDialog {
         self.imp()
             .group_select
             .set_expression(Some(&Group::search_expression()));
+
+        self.imp()
+            .destination_select
+            .set_factory(Some(&Group::factory()));
+        self.imp()
+            .destination_select
+            .set_model(Some(application.data().group_model()));
+        self.imp()
+            .destination_select
+            .set_expression(Some(&Group::search_expression()));
     }
 
     /// Fill entries with transaction values for edit
@@ -348,22 +925,58 @@ impl TransactionDialog {
 
         let transaction = self.imp().edit_transaction.get().unwrap();
 
+        // Recurring rules are managed from the recurring transactions window,
+        // installments are only created along with the purchase
+        // and splits along with the receipt
+        self.imp().repeat_box.set_visible(false);
+        self.imp().installment_box.set_visible(false);
+        self.imp().split_check.set_visible(false);
+
+        // Each share of a split stays in its group
+        if transaction.split().is_some() {
+            self.imp().group_select.set_sensitive(false);
+        }
+
         self.imp()
             .transaction_name
             .set_buffer(&gtk::EntryBuffer::new(Some(&transaction.name())));
 
         self.imp()
             .amount_entry
-            .set_buffer(&gtk::EntryBuffer::new(Some(&format!(
-                "{:.2}",
-                transaction.amount()
-            ))));
+            .set_buffer(&gtk::EntryBuffer::new(Some(
+                &application::number_format().apply(&transaction.amount().to_string()),
+            )));
 
         match transaction.tr_type() {
             TransactionType::EXPENSE => self.imp().expense_check_button.set_active(true),
             TransactionType::INCOME => self.imp().income_check_button.set_active(true),
+            TransactionType::TRANSFER => self.imp().transfer_check_button.set_active(true),
+        }
+
+        // Transfers stay transfers between the same groups, and other
+        // transactions cannot become one
+        if transaction.tr_type() == TransactionType::TRANSFER {
+            self.imp().type_box.set_sensitive(false);
+            self.imp().group_select.set_sensitive(false);
+            self.imp().destination_select.set_sensitive(false);
+        } else {
+            self.imp().transfer_check_button.set_sensitive(false);
         }
 
+        self.imp()
+            .tags_entry
+            .set_text(&transaction.tags().join(", "));
+        self.imp()
+            .payee_entry
+            .set_text(&transaction.payee().unwrap_or_default());
+        self.imp()
+            .location_entry
+            .set_text(&transaction.location().unwrap_or_default());
+        self.imp()
+            .notes_view
+            .buffer()
+            .set_text(&transaction.notes().unwrap_or_default());
+
         self.imp().dt_picker.set_property(
             "selected-date",
             transaction.date().format_iso8601().expect("Invalid date"),
@@ -378,7 +991,31 @@ impl TransactionDialog {
             self.populate_group_select_dropdown();
         }
 
-        self.set_group_dropdown_selection(self.imp().current_group.get().unwrap())
+        self.set_group_dropdown_selection(self.imp().current_group.get().unwrap());
+
+        // Show the other side of an edited transfer
+        let transfer = self
+            .imp()
+            .edit_transaction
+            .get()
+            .and_then(|transaction| transaction.transfer());
+        if let Some(transfer) = transfer {
+            let application = application!(self @as crate::BeedgetApplication);
+            let model = application.data().group_model();
+
+            if let Some(idx) = application
+                .data()
+                .group_by_id(transfer.group)
+                .and_then(|group| model.find(&group))
+            {
+                self.imp().destination_select.set_selected(idx);
+            }
+
+            if transfer.incoming {
+                self.imp().destination_label.set_label(&gettext("From:"));
+            }
+            self.imp().destination_box.set_visible(true);
+        }
     }
 
     #[inline(always)]
@@ -396,3 +1033,56 @@ impl TransactionDialog {
         self.imp().group_select.set_selected(group_idx);
     }
 }
+
+/// Single column list of `values`, used for entry completion
+pub fn string_store(values: &[String]) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[String::static_type()]);
+    for value in values {
+        store.set(&store.append(), &[(0, value)]);
+    }
+
+    store
+}
+
+/// Ask whether a change to an installment also applies to the `later` ones
+/// of the same purchase. `on_choice` gets `true` to include them and is not
+/// called if the user cancels.
+pub fn ask_about_later_installments(
+    parent: &gtk::Window,
+    question: &str,
+    later: usize,
+    on_choice: impl Fn(bool) + 'static,
+) {
+    let details = ngettext(
+        "There is {} more installment of this purchase.",
+        "There are {} more installments of this purchase.",
+        later as u32,
+    )
+    .replace("{}", &later.to_string());
+
+    let dialog = gtk::MessageDialog::builder()
+        .transient_for(parent)
+        .modal(true)
+        .message_type(gtk::MessageType::Question)
+        .text(question)
+        .secondary_text(&details)
+        .build();
+
+    dialog.add_buttons(&[
+        (&gettext("Cancel"), gtk::ResponseType::Cancel),
+        (&gettext("Only This One"), gtk::ResponseType::No),
+        (&gettext("All Remaining"), gtk::ResponseType::Yes),
+    ]);
+
+    dialog.connect_response(move |dialog, response| {
+        dialog.destroy();
+
+        match response {
+            gtk::ResponseType::Yes => on_choice(true),
+            gtk::ResponseType::No => on_choice(false),
+            _ => {}
+        }
+    });
+
+    dialog.present();
+}
@@ -7,6 +7,9 @@ mod dialogs;
 mod models;
 mod widgets;
 
+#[cfg(test)]
+mod test_util;
+
 use self::application::BeedgetApplication;
 use self::window::BeedgetWindow;
 
@@ -1,5 +1,3 @@
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use glib::{subclass::Signal, ParamSpec, ParamSpecString};
@@ -11,32 +9,20 @@ use gtk::{gio, glib, ClosureExpression, SignalListItemFactory};
 use once_cell::sync::{Lazy, OnceCell};
 
 use std::cell::RefCell;
-use std::path::Path;
 
+use crate::application;
 use crate::models::*;
 use crate::widgets::*;
 
 mod imp {
     use super::*;
 
-    #[derive(Debug, Default, Serialize, Deserialize)]
-    pub struct GroupInner {
-        pub id: Uuid,
-        pub name: String,
-        pub emoji: String,
-        pub color: Vec<f32>,
-        pub transactions: RefCell<Vec<Transaction>>,
-    }
-
-    impl DataObject for GroupInner {
-        fn filename(&self) -> String {
-            self.id.to_string() + &String::from(".json")
-        }
-    }
-
     #[derive(Default)]
     pub struct Group {
-        pub inner: RefCell<GroupInner>,
+        /// Group data. Its `transactions` stay empty, they are held as
+        /// GObjects in `transactions` so rows can bind to them.
+        pub inner: RefCell<beedget_core::Group>,
+        pub transactions: RefCell<Vec<Transaction>>,
         pub transaction_list_store: OnceCell<gio::ListStore>,
     }
 
@@ -55,6 +41,7 @@ mod imp {
                     ParamSpecString::builder("emoji").build(),
                     ParamSpecString::builder("color").build(),
                     ParamSpecString::builder("name").build(),
+                    ParamSpecString::builder("currency").build(),
                 ]
             });
 
@@ -79,6 +66,7 @@ mod imp {
                         vec![color.red(), color.green(), color.blue(), color.alpha()];
                 }
                 "name" => self.inner.borrow_mut().name = value.get().unwrap(),
+                "currency" => self.inner.borrow_mut().currency = value.get().unwrap(),
                 _ => unimplemented!(),
             }
         }
@@ -89,6 +77,7 @@ mod imp {
                 "emoji" => self.inner.borrow().emoji.to_value(),
                 "color" => obj.rgba_color().to_str().to_value(),
                 "name" => self.inner.borrow().name.to_value(),
+                "currency" => self.inner.borrow().currency.to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -108,6 +97,15 @@ mod imp {
                         <()>::static_type().into(),
                     )
                     .build(),
+                    // Amount, type, date or tags of a transaction in the group changed
+                    Signal::builder(
+                        "transaction-changed",
+                        &[Transaction::static_type().into()],
+                        <()>::static_type().into(),
+                    )
+                    .build(),
+                    Signal::builder("budget-changed", &[], <()>::static_type().into()).build(),
+                    Signal::builder("recurring-changed", &[], <()>::static_type().into()).build(),
                 ]
             });
             SIGNALS.as_ref()
@@ -116,6 +114,7 @@ mod imp {
 }
 
 glib::wrapper! {
+    /// GObject wrapper around `beedget_core::Group` for use in list models and bindings
     pub struct Group(ObjectSubclass<imp::Group>);
 }
 
@@ -126,12 +125,13 @@ impl Default for Group {
 }
 
 impl Group {
-    pub fn new(emoji: &str, color: RGBA, name: &str) -> Self {
+    pub fn new(emoji: &str, color: RGBA, name: &str, currency: &Currency) -> Self {
         glib::Object::new(&[
             ("uid", &Uuid::new_v4().to_string()),
             ("emoji", &emoji),
             ("color", &color.to_str()),
             ("name", &name),
+            ("currency", &currency.code),
         ])
         .expect("Failed to create Group")
     }
@@ -173,27 +173,47 @@ impl Group {
         })
     }
 
-    pub fn load_from_file(path: &Path) -> Result<Self> {
-        let group: Self = glib::Object::new(&[]).expect("Failed to create group");
+    /// Whether `query` is part of the group name or matches any of its
+    /// transactions, ignoring case
+    pub fn matches(&self, query: &str) -> bool {
+        self.name().to_lowercase().contains(&query.to_lowercase())
+            || self
+                .transactions()
+                .iter()
+                .any(|transaction| transaction.matches(query))
+    }
 
-        group
-            .imp()
-            .inner
-            .replace(imp::GroupInner::load_from_file(path)?);
+    /// Wrap group data loaded from storage
+    pub fn from_data(mut data: beedget_core::Group) -> Self {
+        let group = Self::empty();
 
-        Ok(group)
-    }
+        let transactions: Vec<Transaction> = data
+            .transactions
+            .drain(..)
+            .map(Transaction::from_data)
+            .collect();
+        for transaction in &transactions {
+            group.watch_transaction(transaction);
+        }
 
-    pub fn save_to_file(&self, path: &Path) -> Result<()> {
-        self.imp().inner.borrow().save_to_file(path)?;
+        group.imp().transactions.replace(transactions);
+        group.imp().inner.replace(data);
 
-        Ok(())
+        group
     }
 
-    pub fn delete_file(&self, base_path: &Path) -> Result<()> {
-        self.imp().inner.borrow().delete_file(base_path)?;
+    /// Copy of the group data, including all of its transactions
+    pub fn data(&self) -> beedget_core::Group {
+        let mut data = self.imp().inner.borrow().clone();
+        data.transactions = self
+            .imp()
+            .transactions
+            .borrow()
+            .iter()
+            .map(|transaction| transaction.data())
+            .collect();
 
-        Ok(())
+        data
     }
 
     pub fn id(&self) -> Uuid {
@@ -212,6 +232,113 @@ impl Group {
         self.rgba_color().to_str().to_string()
     }
 
+    pub fn transactions(&self) -> Vec<Transaction> {
+        self.imp().transactions.borrow().clone()
+    }
+
+    pub fn currency(&self) -> &'static Currency {
+        self.imp().inner.borrow().currency()
+    }
+
+    /// Sum of all signed transaction amounts, in the group's currency
+    pub fn balance(&self) -> Money {
+        self.data().balance()
+    }
+
+    pub fn budget(&self) -> Option<Budget> {
+        self.imp().inner.borrow().budget
+    }
+
+    pub fn set_budget(&self, budget: Option<Budget>) {
+        self.imp().inner.borrow_mut().budget = budget;
+        self.emit_by_name::<()>("budget-changed", &[]);
+    }
+
+    /// Recurring transaction rules of the group
+    pub fn recurring(&self) -> Vec<RecurringTransaction> {
+        self.imp().inner.borrow().recurring.clone()
+    }
+
+    /// Add recurring rule, or replace the one with the same id
+    pub fn set_recurring(&self, rule: RecurringTransaction) {
+        {
+            let mut inner = self.imp().inner.borrow_mut();
+            match inner.recurring.iter_mut().find(|r| r.id == rule.id) {
+                Some(existing) => *existing = rule,
+                None => inner.recurring.push(rule),
+            }
+        }
+
+        self.emit_by_name::<()>("recurring-changed", &[]);
+    }
+
+    /// Remove recurring rule. Transactions it already added stay in the group.
+    pub fn delete_recurring(&self, rule_id: Uuid) {
+        self.imp()
+            .inner
+            .borrow_mut()
+            .recurring
+            .retain(|rule| rule.id != rule_id);
+
+        self.emit_by_name::<()>("recurring-changed", &[]);
+    }
+
+    /// Pause or resume recurring rule. Occurrences missed while paused are skipped.
+    pub fn set_recurring_paused(&self, rule_id: Uuid, paused: bool) {
+        if let Some(rule) = self
+            .imp()
+            .inner
+            .borrow_mut()
+            .recurring
+            .iter_mut()
+            .find(|rule| rule.id == rule_id)
+        {
+            rule.set_paused(paused, application::today());
+        }
+
+        self.emit_by_name::<()>("recurring-changed", &[]);
+    }
+
+    /// Add transactions of recurring rules that came due up to `today`.
+    ///
+    /// Returns how many were added.
+    pub fn add_due_recurring(&self, today: Date) -> usize {
+        let due = self.imp().inner.borrow_mut().take_due_recurring(today);
+        let count = due.len();
+
+        for transaction in due {
+            self.new_transaction(Transaction::from_data(transaction));
+        }
+
+        if count > 0 {
+            self.emit_by_name::<()>("recurring-changed", &[]);
+        }
+
+        count
+    }
+
+    /// Spending against the budget in the current period
+    pub fn budget_status(&self) -> Option<BudgetStatus> {
+        self.data()
+            .budget_status(application::today(), application::first_day_of_week())
+    }
+
+    /// Whether the group spent more than its limit this period
+    pub fn is_over_budget(&self) -> bool {
+        self.data()
+            .is_over_budget(application::today(), application::first_day_of_week())
+    }
+
+    /// Income, expenses and closing balance within `period`, or overall
+    pub fn summary(&self, period: Option<DateRange>) -> Summary {
+        self.data().summary(period)
+    }
+
+    /// Balance right after each transaction, in chronological order
+    pub fn running_balances(&self) -> Vec<(Uuid, Money)> {
+        self.data().running_balances()
+    }
+
     pub fn set_name(&self, name: &str) {
         self.imp().inner.borrow_mut().name = name.to_string();
     }
@@ -236,51 +363,82 @@ impl Group {
 
     pub fn new_transaction(&self, transaction: Transaction) {
         self.imp()
-            .inner
-            .borrow()
             .transactions
             .borrow_mut()
-            .push(transaction);
-        self.emit_by_name::<()>(
-            "transaction-added",
-            &[&self
-                .imp()
-                .inner
-                .borrow()
-                .transactions
-                .borrow()
-                .last()
-                .unwrap()],
+            .push(transaction.clone());
+        self.watch_transaction(&transaction);
+        self.emit_by_name::<()>("transaction-added", &[&transaction]);
+    }
+
+    /// Re-emit changes to values that affect totals or filtering as `transaction-changed`
+    fn watch_transaction(&self, transaction: &Transaction) {
+        transaction.connect_notify_local(
+            None,
+            glib::clone!(@weak self as group => move |transaction, pspec| {
+                if !matches!(pspec.name(), "amount" | "tr-type" | "date" | "tags") {
+                    return;
+                }
+
+                // Transactions moved to another group keep this handler
+                let id = transaction.id();
+                if group.transactions().iter().any(|t| t.id() == id) {
+                    group.emit_by_name::<()>("transaction-changed", &[transaction]);
+                }
+            }),
         );
     }
 
-    pub fn delete_transaction(&self, transaction_id: Uuid) {
-        let mut idx = 0;
+    pub fn transaction(&self, transaction_id: Uuid) -> Option<Transaction> {
+        self.imp()
+            .transactions
+            .borrow()
+            .iter()
+            .find(|transaction| transaction.id() == transaction_id)
+            .cloned()
+    }
 
-        for transaction in self.imp().inner.borrow().transactions.borrow().iter() {
-            if transaction.id() == transaction_id {
-                break;
-            } else {
-                idx += 1;
-            }
-        }
+    /// Installments of the same purchase that come after `transaction`, in order
+    pub fn later_installments(&self, transaction: &Transaction) -> Vec<Transaction> {
+        let installment = match transaction.installment() {
+            Some(installment) => installment,
+            None => return vec![],
+        };
+
+        let mut later: Vec<Transaction> = self
+            .transactions()
+            .into_iter()
+            .filter(|other| {
+                other
+                    .installment()
+                    .is_some_and(|other| installment.is_followed_by(&other))
+            })
+            .collect();
+        later.sort_by_key(|other| other.installment().map(|other| other.number));
+
+        later
+    }
 
-        let removed = self
+    pub fn delete_transaction(&self, transaction_id: Uuid) {
+        let idx = self
             .imp()
-            .inner
-            .borrow()
             .transactions
-            .borrow_mut()
-            .remove(idx);
-        self.emit_by_name::<()>("transaction-removed", &[&removed]);
+            .borrow()
+            .iter()
+            .position(|transaction| transaction.id() == transaction_id);
+
+        if let Some(idx) = idx {
+            let removed = self.imp().transactions.borrow_mut().remove(idx);
+            self.emit_by_name::<()>("transaction-removed", &[&removed]);
+        }
     }
 
     pub fn transaction_model(&self) -> &gio::ListStore {
         self.imp().transaction_list_store.get_or_init(|| {
             let ls = gio::ListStore::new(TransactionRow::static_type());
 
-            for transaction in self.imp().inner.borrow().transactions.borrow().iter() {
+            for transaction in self.imp().transactions.borrow().iter() {
                 let row = TransactionRow::new(transaction);
+                self.bind_row_currency(&row);
                 ls.append(&row);
             }
 
@@ -289,6 +447,7 @@ impl Group {
                 false,
                 glib::closure_local!(move |group: Group, transaction: &Transaction| {
                     let row = TransactionRow::new(&transaction);
+                    group.bind_row_currency(&row);
 
                     let list_store = group.imp().transaction_list_store.get().unwrap();
                     list_store.append(&row);
@@ -322,4 +481,205 @@ impl Group {
             ls
         })
     }
+
+    /// Keep row amount formatting in sync with the group currency
+    fn bind_row_currency(&self, row: &TransactionRow) {
+        self.bind_property("currency", row, "currency")
+            .flags(glib::BindingFlags::SYNC_CREATE)
+            .build();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::test_util::{group, transaction};
+
+    /// Count emissions of `signal` on `group`
+    fn count_emissions(group: &Group, signal: &str) -> Rc<Cell<u32>> {
+        let count = Rc::new(Cell::new(0));
+
+        let counter = count.clone();
+        group.connect_local(signal, false, move |_| {
+            counter.set(counter.get() + 1);
+            None
+        });
+
+        count
+    }
+
+    fn transaction_ids(group: &Group) -> Vec<Uuid> {
+        group.transactions().iter().map(|t| t.id()).collect()
+    }
+
+    #[test]
+    fn new_transaction_is_added() {
+        let group = group("Wallet");
+        let added = count_emissions(&group, "transaction-added");
+
+        let expense = transaction(TransactionType::EXPENSE, "12.50");
+        let income = transaction(TransactionType::INCOME, "100");
+        group.new_transaction(expense.clone());
+        group.new_transaction(income.clone());
+
+        assert_eq!(transaction_ids(&group), vec![expense.id(), income.id()]);
+        assert_eq!(added.get(), 2);
+        assert_eq!(group.balance(), "87.50".parse().unwrap());
+    }
+
+    #[test]
+    fn delete_transaction_removes_only_that_transaction() {
+        let group = group("Wallet");
+        let removed = count_emissions(&group, "transaction-removed");
+
+        let first = transaction(TransactionType::EXPENSE, "1");
+        let second = transaction(TransactionType::EXPENSE, "2");
+        let third = transaction(TransactionType::EXPENSE, "3");
+        group.new_transaction(first.clone());
+        group.new_transaction(second.clone());
+        group.new_transaction(third.clone());
+
+        group.delete_transaction(second.id());
+
+        assert_eq!(transaction_ids(&group), vec![first.id(), third.id()]);
+        assert_eq!(removed.get(), 1);
+        assert_eq!(group.balance(), "-4".parse().unwrap());
+    }
+
+    #[test]
+    fn transaction_moves_between_groups() {
+        let source = group("Wallet");
+        let destination = group("Savings");
+
+        let moved = transaction(TransactionType::INCOME, "50");
+        source.new_transaction(transaction(TransactionType::EXPENSE, "10"));
+        source.new_transaction(moved.clone());
+
+        source.delete_transaction(moved.id());
+        destination.new_transaction(moved.clone());
+
+        assert_eq!(source.transactions().len(), 1);
+        assert_eq!(transaction_ids(&destination), vec![moved.id()]);
+        assert_eq!(source.balance(), "-10".parse().unwrap());
+        assert_eq!(destination.balance(), "50".parse().unwrap());
+    }
+
+    #[test]
+    fn later_installments_are_linked_ones() {
+        let group = group("Wallet");
+        let purchase = transaction(TransactionType::EXPENSE, "300");
+        for part in installment::split_installments(&purchase.data(), 3, 2) {
+            group.new_transaction(Transaction::from_data(part));
+        }
+        group.new_transaction(transaction(TransactionType::EXPENSE, "10"));
+
+        let transactions = group.transactions();
+        let later = group.later_installments(&transactions[0]);
+
+        assert_eq!(
+            later.iter().map(|t| t.id()).collect::<Vec<Uuid>>(),
+            vec![transactions[1].id(), transactions[2].id()]
+        );
+        assert!(group.later_installments(&transactions[2]).is_empty());
+        assert!(group.later_installments(&transactions[3]).is_empty());
+        assert_eq!(group.balance(), "-310".parse().unwrap());
+    }
+
+    #[test]
+    fn data_round_trip() {
+        let original = group("Wallet");
+        original.new_transaction(transaction(TransactionType::EXPENSE, "0.1"));
+        original.new_transaction(transaction(TransactionType::INCOME, "1234.5678"));
+
+        let data = original.data();
+        let restored = Group::from_data(data.clone());
+
+        assert_eq!(data.transactions.len(), 2);
+        assert_eq!(restored.id(), original.id());
+        assert_eq!(restored.name(), "Wallet");
+        assert_eq!(restored.emoji(), "💰");
+        assert_eq!(restored.currency().code, "BRL");
+        assert_eq!(restored.color(), original.color());
+        assert_eq!(transaction_ids(&restored), transaction_ids(&original));
+        assert_eq!(restored.balance(), original.balance());
+        assert_eq!(restored.data(), data);
+    }
+
+    #[test]
+    fn transaction_edits_reach_group_data() {
+        let group = group("Wallet");
+        let transaction = transaction(TransactionType::EXPENSE, "10");
+        group.new_transaction(transaction.clone());
+
+        transaction.set_amount("25".parse().unwrap());
+
+        assert_eq!(group.data().transactions[0].amount, "25".parse().unwrap());
+        assert_eq!(group.balance(), "-25".parse().unwrap());
+    }
+
+    #[test]
+    fn transaction_edits_are_reported() {
+        let source = group("Wallet");
+        let destination = group("Savings");
+        let source_changed = count_emissions(&source, "transaction-changed");
+        let destination_changed = count_emissions(&destination, "transaction-changed");
+
+        let transaction = transaction(TransactionType::EXPENSE, "10");
+        source.new_transaction(transaction.clone());
+
+        transaction.set_amount("25".parse().unwrap());
+        transaction.set_name("Rent");
+        assert_eq!(source_changed.get(), 1);
+
+        source.delete_transaction(transaction.id());
+        destination.new_transaction(transaction.clone());
+        transaction.change_tr_type(TransactionType::INCOME);
+
+        assert_eq!(source_changed.get(), 1);
+        assert_eq!(destination_changed.get(), 1);
+    }
+
+    #[test]
+    fn budget_survives_data_round_trip() {
+        let group = group("Groceries");
+        let changed = count_emissions(&group, "budget-changed");
+
+        let budget = Budget {
+            limit: "300".parse().unwrap(),
+            period: BudgetPeriod::Monthly,
+            rollover: Rollover::Surplus,
+            since: None,
+        };
+        group.set_budget(Some(budget));
+
+        assert_eq!(changed.get(), 1);
+        assert_eq!(Group::from_data(group.data()).budget(), Some(budget));
+    }
+
+    #[test]
+    fn due_recurring_transactions_are_added() {
+        let group = group("Home");
+        let added = count_emissions(&group, "transaction-added");
+
+        let rent = transaction(TransactionType::EXPENSE, "1200");
+        group.set_recurring(RecurringTransaction::from_transaction(
+            &rent.data(),
+            Frequency::Weekly,
+            1,
+            RecurrenceEnd::Never,
+        ));
+        group.new_transaction(rent);
+
+        let today = Date::new(2022, 7, 20).unwrap();
+        assert_eq!(group.add_due_recurring(today), 2);
+        assert_eq!(group.add_due_recurring(today), 0);
+
+        assert_eq!(added.get(), 3);
+        assert_eq!(group.recurring()[0].generated, 3);
+        assert_eq!(group.balance(), "-3600".parse().unwrap());
+    }
 }
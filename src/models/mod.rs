@@ -1,9 +1,17 @@
-pub mod data_object;
 pub mod group;
 pub mod save_data;
 pub mod transaction;
 
-pub use data_object::DataObject;
+pub use beedget_core::{
+    currency, exchange_rates, export, import, installment, migrations, split, storage, tags,
+    transfer,
+};
+pub use beedget_core::{
+    Budget, BudgetPeriod, BudgetStatus, CsvMapping, Currency, Date, DateRange, ExchangeRates,
+    Frequency, ImportProfiles, Installment, LoadProblem, Money, NumberFormat, RecurrenceEnd,
+    RecurringTransaction, Rollover, Split, Storage, StorageBackend, Summary, TagTotal,
+    TransactionType, Transfer, Weekday,
+};
 pub use group::Group;
 pub use save_data::SaveData;
-pub use transaction::{Transaction, TransactionType};
+pub use transaction::Transaction;
@@ -1,59 +1,130 @@
 use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use anyhow::{Error, Result};
+use anyhow::Result;
 use once_cell::sync::OnceCell;
+use uuid::Uuid;
 
-use gtk::gio;
 use gtk::prelude::*;
+use gtk::{gio, glib};
 
-use crate::models::Group;
+use crate::models::exchange_rates::EXCHANGE_RATES_FILE;
+use crate::models::storage;
+use crate::models::{
+    CsvMapping, Currency, Date, DateRange, ExchangeRates, Group, ImportProfiles, LoadProblem,
+    Money, Storage, StorageBackend, TagTotal, Transaction,
+};
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct SaveData {
     pub groups: RefCell<Vec<Group>>,
-    save_path: PathBuf,
+    save_path: RefCell<PathBuf>,
+    storage: RefCell<Box<dyn Storage>>,
 
     pub group_model: OnceCell<gio::ListStore>,
+
+    /// Exchange rate table with the modification time of its file when read
+    exchange_rates: RefCell<Option<(Option<SystemTime>, ExchangeRates)>>,
+
+    /// Problems found while loading, waiting to be shown to the user
+    load_problems: RefCell<Vec<LoadProblem>>,
 }
 
 impl SaveData {
-    pub fn new(pb: &PathBuf) -> Self {
-        match SaveData::load_groups(pb) {
-            Ok(groups) => Self {
-                groups: RefCell::new(groups),
-                save_path: pb.to_owned(),
-                group_model: OnceCell::new(),
-            },
-            Err(_) => {
-                panic!("Could not access save data directory");
+    /// Load save data. Items that cannot be read are skipped and reported
+    /// through `take_load_problems` instead of failing the whole load.
+    pub fn new(pb: &PathBuf, backend: StorageBackend) -> Self {
+        let (storage, open_problems) = storage::open(pb, backend);
+
+        let data = SaveData::with_storage(pb, storage);
+        data.load_problems.borrow_mut().splice(0..0, open_problems);
+
+        data
+    }
+
+    /// Load save data from an already opened storage backend
+    pub fn with_storage(pb: &Path, storage: Box<dyn Storage>) -> Self {
+        let mut load_problems = vec![];
+
+        let groups = match storage.load_groups() {
+            Ok((groups, problems)) => {
+                load_problems.extend(problems);
+                groups.into_iter().map(Group::from_data).collect()
+            }
+            Err(error) => {
+                load_problems.push(LoadProblem {
+                    path: pb.to_owned(),
+                    error,
+                    quarantined_to: None,
+                });
+                vec![]
             }
+        };
+
+        Self {
+            groups: RefCell::new(groups),
+            save_path: RefCell::new(pb.to_owned()),
+            storage: RefCell::new(storage),
+            group_model: OnceCell::new(),
+            exchange_rates: RefCell::new(None),
+            load_problems: RefCell::new(load_problems),
         }
     }
 
-    /// Loads all groups from path
-    fn load_groups(pb: &PathBuf) -> Result<Vec<Group>> {
-        let mut loaded_groups: Vec<Group> = vec![];
+    pub fn save_path(&self) -> PathBuf {
+        self.save_path.borrow().clone()
+    }
 
-        match fs::read_dir(pb.as_path().join(r"groups")) {
-            Ok(group_files) => {
-                for file in group_files {
-                    let group = Group::load_from_file(&file?.path())?;
-                    loaded_groups.push(group);
-                }
+    /// Move all data to `path` and keep saving there.
+    ///
+    /// Data is copied first and only removed from the old location once the
+    /// copy opens correctly and `confirm` succeeds, so a failure leaves
+    /// everything where it was. `confirm` runs with the new location in use,
+    /// e.g. to remember it.
+    pub fn relocate(
+        &self,
+        path: &Path,
+        backend: StorageBackend,
+        confirm: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let old_path = self.save_path();
+        if old_path == path {
+            return Ok(());
+        }
 
-                Ok(loaded_groups)
-            }
-            Err(error) => match error.kind() {
-                ErrorKind::NotFound => {
-                    fs::create_dir_all(pb.as_path().join(r"groups"))?;
-                    Ok(vec![])
-                }
-                _ => Err(Error::new(error)),
-            },
+        storage::copy_data(&old_path, path)?;
+
+        let (new_storage, problems) = storage::open(path, backend);
+        if let Some(problem) = problems.into_iter().next() {
+            let _ = storage::remove_data(path);
+            return Err(problem.error);
+        }
+
+        let old_storage = self.storage.replace(new_storage);
+        self.save_path.replace(path.to_owned());
+        self.exchange_rates.replace(None);
+
+        if let Err(error) = confirm() {
+            self.storage.replace(old_storage);
+            self.save_path.replace(old_path);
+            self.exchange_rates.replace(None);
+            let _ = storage::remove_data(path);
+            return Err(error);
+        }
+
+        if let Err(error) = storage::remove_data(&old_path) {
+            glib::g_warning!("beedget", "Could not remove old data: {:?}", error);
         }
+
+        Ok(())
+    }
+
+    /// Problems found while loading. Each problem is only returned once.
+    pub fn take_load_problems(&self) -> Vec<LoadProblem> {
+        self.load_problems.take()
     }
 
     /// Get groups model based on data loaded from storage
@@ -69,33 +140,189 @@ impl SaveData {
         })
     }
 
-    /// Add new group to groups list.
+    /// Add new group to groups list. The group is only added once it is saved.
     pub fn new_group(&self, group: Group) -> Result<()> {
+        self.storage.borrow().save_group(&group.data())?;
+
+        self.group_model().append(&group);
         self.groups.borrow_mut().push(group);
 
-        let stored_groups = self.groups.borrow();
-        stored_groups
-            .last()
-            .unwrap()
-            .save_to_file(self.save_path.as_path().join(r"groups").as_path())?;
+        Ok(())
+    }
 
-        self.group_model().append(stored_groups.last().unwrap());
+    /// Save group and all of its transactions after changes
+    pub fn save_group(&self, group: &Group) -> Result<()> {
+        self.storage.borrow().save_group(&group.data())
+    }
 
-        Ok(())
+    /// Save a new or edited transaction of `group`
+    pub fn save_transaction(&self, group: &Group, transaction: &Transaction) -> Result<()> {
+        self.storage
+            .borrow()
+            .save_transaction(&group.data(), &transaction.data())
+    }
+
+    /// Persist removal of a transaction already deleted from `group`
+    pub fn delete_transaction(&self, group: &Group, transaction: &Transaction) -> Result<()> {
+        self.storage
+            .borrow()
+            .delete_transaction(&group.data(), transaction.id())
+    }
+
+    /// Add transactions of recurring rules that came due up to `today` and
+    /// save the groups that got any. Groups that failed to save are returned
+    /// with their error.
+    pub fn add_due_recurring(&self, today: Date) -> Vec<(Group, anyhow::Error)> {
+        let mut failed = vec![];
+
+        for group in self.groups.borrow().iter() {
+            if group.add_due_recurring(today) > 0 {
+                if let Err(error) = self.save_group(group) {
+                    failed.push((group.clone(), error));
+                }
+            }
+        }
+
+        failed
+    }
+
+    /// Find loaded group by id
+    pub fn group_by_id(&self, id: Uuid) -> Option<Group> {
+        self.groups.borrow().iter().find(|g| g.id() == id).cloned()
+    }
+
+    /// Other side of a transfer and the group holding it
+    pub fn transfer_counterpart(&self, transaction: &Transaction) -> Option<(Group, Transaction)> {
+        let transfer = transaction.transfer()?;
+        let group = self.group_by_id(transfer.group)?;
+        let counterpart = group.transaction(transfer.counterpart)?;
+
+        Some((group, counterpart))
+    }
+
+    /// Other shares of the split `transaction` is part of, with their groups
+    pub fn split_siblings(&self, transaction: &Transaction) -> Vec<(Group, Transaction)> {
+        let receipt = match transaction.split() {
+            Some(split) => split.receipt,
+            None => return vec![],
+        };
+
+        self.groups
+            .borrow()
+            .iter()
+            .flat_map(|group| {
+                group
+                    .transactions()
+                    .into_iter()
+                    .filter(|other| {
+                        other.id() != transaction.id()
+                            && other.split().is_some_and(|split| split.receipt == receipt)
+                    })
+                    .map(move |other| (group.clone(), other))
+            })
+            .collect()
+    }
+
+    /// Every tag in use, sorted
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .groups
+            .borrow()
+            .iter()
+            .flat_map(|group| group.transactions())
+            .flat_map(|transaction| transaction.tags())
+            .collect();
+        tags.sort();
+        tags.dedup();
+
+        tags
+    }
+
+    /// Payees of earlier transactions, most used first
+    pub fn payees(&self) -> Vec<String> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for group in self.groups.borrow().iter() {
+            for payee in group.transactions().iter().filter_map(|t| t.payee()) {
+                *counts.entry(payee).or_default() += 1;
+            }
+        }
+
+        let mut payees: Vec<(String, usize)> = counts.into_iter().collect();
+        payees.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+
+        payees.into_iter().map(|(payee, _)| payee).collect()
+    }
+
+    /// Totals of every tag across all groups, converted to the base currency
+    /// of the exchange rate table
+    pub fn tag_totals(
+        &self,
+        period: Option<DateRange>,
+    ) -> Result<(BTreeMap<String, TagTotal>, &'static Currency)> {
+        let rates = self.exchange_rates()?;
+        let groups: Vec<_> = self.groups.borrow().iter().map(|g| g.data()).collect();
+
+        rates.tag_totals(&groups, period)
+    }
+
+    /// CSV mappings remembered for each bank
+    pub fn import_profiles(&self) -> Result<ImportProfiles> {
+        ImportProfiles::load(&self.save_path.borrow())
+    }
+
+    /// Remember `mapping` as the profile called `name`, replacing any earlier one
+    pub fn save_import_profile(&self, name: &str, mapping: CsvMapping) -> Result<()> {
+        let mut profiles = self.import_profiles()?;
+        profiles.profiles.insert(name.to_owned(), mapping);
+
+        profiles.save(&self.save_path.borrow())
+    }
+
+    /// Convert `amount` with the exchange rate table, rounded for `to`
+    pub fn convert(&self, amount: Money, from: &Currency, to: &Currency) -> Result<Money> {
+        if from.code == to.code {
+            return Ok(amount);
+        }
+
+        let rates = self.exchange_rates()?;
+
+        Ok(rates
+            .convert(amount, from.code, to.code)?
+            .round_to(to.decimals))
+    }
+
+    /// Exchange rate table, read from disk again only once its file changes
+    fn exchange_rates(&self) -> Result<ExchangeRates> {
+        let path = self.save_path().join(EXCHANGE_RATES_FILE);
+        let modified = || fs::metadata(&path).and_then(|m| m.modified()).ok();
+
+        if let Some((read_at, rates)) = &*self.exchange_rates.borrow() {
+            if read_at.is_some() && *read_at == modified() {
+                return Ok(rates.clone());
+            }
+        }
+
+        let rates = ExchangeRates::load(&self.save_path())?;
+        // Loading writes a template when there is no file yet
+        self.exchange_rates
+            .replace(Some((modified(), rates.clone())));
+
+        Ok(rates)
     }
 
-    /// Save group file after changes
-    pub fn save_group(&self, group: &Group) {
-        group
-            .save_to_file(self.save_path.as_path().join(r"groups").as_path())
-            .expect("Could not save group into file");
+    /// Sum of every group balance, converted to the base currency of the exchange rate table
+    pub fn total_balance(&self) -> Result<(Money, &'static Currency)> {
+        let rates = self.exchange_rates()?;
+        let groups: Vec<_> = self.groups.borrow().iter().map(|g| g.data()).collect();
+
+        rates.total_balance(&groups)
     }
 
     /// Delete group and all transactions
-    pub fn delete_group(&self, group: &Group) {
-        group
-            .delete_file(self.save_path.as_path().join(r"groups").as_path())
-            .expect("Could not delete group file");
+    pub fn delete_group(&self, group: &Group) -> Result<()> {
+        self.storage.borrow().delete_group(&group.data())?;
+
+        self.groups.borrow_mut().retain(|g| g.id() != group.id());
 
         let list_store = self.group_model();
         for i in 0..list_store.n_items() {
@@ -111,5 +338,298 @@ impl SaveData {
                 break;
             }
         }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::storage::MemoryStorage;
+    use crate::models::{
+        split, tags, transfer, Frequency, RecurrenceEnd, RecurringTransaction, TransactionType,
+    };
+    use crate::test_util::{group, transaction};
+
+    fn save_data(storage: &MemoryStorage) -> SaveData {
+        SaveData::with_storage(Path::new("/nonexistent"), Box::new(storage.clone()))
+    }
+
+    fn stored_transaction_count(storage: &MemoryStorage, group: &Group) -> usize {
+        storage.group_document(group.id()).unwrap()["transactions"]
+            .as_array()
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn new_group_is_stored() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let group = group("Wallet");
+        data.new_group(group.clone()).unwrap();
+
+        assert_eq!(storage.len(), 1);
+        assert_eq!(data.groups.borrow().len(), 1);
+        assert_eq!(data.group_model().n_items(), 1);
+        assert_eq!(data.group_by_id(group.id()), Some(group));
+    }
+
+    #[test]
+    fn saved_groups_are_loaded_again() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let group = group("Wallet");
+        data.new_group(group.clone()).unwrap();
+        group.new_transaction(transaction(TransactionType::INCOME, "100"));
+        group.new_transaction(transaction(TransactionType::EXPENSE, "0.01"));
+        data.save_group(&group).unwrap();
+
+        let reloaded = save_data(&storage);
+        let loaded = reloaded.group_by_id(group.id()).unwrap();
+
+        assert!(reloaded.take_load_problems().is_empty());
+        assert_eq!(loaded.name(), "Wallet");
+        assert_eq!(loaded.currency().code, "BRL");
+        assert_eq!(loaded.transactions().len(), 2);
+        assert_eq!(loaded.balance(), "99.99".parse().unwrap());
+    }
+
+    #[test]
+    fn delete_group_removes_it_everywhere() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let kept = group("Wallet");
+        let deleted = group("Savings");
+        data.new_group(kept.clone()).unwrap();
+        data.new_group(deleted.clone()).unwrap();
+
+        data.delete_group(&deleted).unwrap();
+
+        assert_eq!(storage.len(), 1);
+        assert!(storage.group_document(deleted.id()).is_none());
+        assert!(data.group_by_id(deleted.id()).is_none());
+        assert_eq!(data.group_model().n_items(), 1);
+        assert!(save_data(&storage).group_by_id(kept.id()).is_some());
+    }
+
+    #[test]
+    fn transaction_changes_are_stored() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let group = group("Wallet");
+        data.new_group(group.clone()).unwrap();
+
+        let transaction = transaction(TransactionType::EXPENSE, "5");
+        group.new_transaction(transaction.clone());
+        data.save_transaction(&group, &transaction).unwrap();
+        assert_eq!(stored_transaction_count(&storage, &group), 1);
+
+        group.delete_transaction(transaction.id());
+        data.delete_transaction(&group, &transaction).unwrap();
+        assert_eq!(stored_transaction_count(&storage, &group), 0);
+    }
+
+    #[test]
+    fn moved_transaction_is_stored_in_new_group() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let source = group("Wallet");
+        let destination = group("Savings");
+        data.new_group(source.clone()).unwrap();
+        data.new_group(destination.clone()).unwrap();
+
+        let transaction = transaction(TransactionType::EXPENSE, "5");
+        source.new_transaction(transaction.clone());
+        data.save_transaction(&source, &transaction).unwrap();
+
+        source.delete_transaction(transaction.id());
+        data.delete_transaction(&source, &transaction).unwrap();
+        destination.new_transaction(transaction.clone());
+        data.save_transaction(&destination, &transaction).unwrap();
+
+        let reloaded = save_data(&storage);
+        let reloaded_source = reloaded.group_by_id(source.id()).unwrap();
+        let reloaded_destination = reloaded.group_by_id(destination.id()).unwrap();
+
+        assert!(reloaded_source.transactions().is_empty());
+        assert_eq!(
+            reloaded_destination.transactions()[0].id(),
+            transaction.id()
+        );
+    }
+
+    #[test]
+    fn unreadable_groups_are_reported() {
+        let storage = MemoryStorage::new();
+        storage.insert_document(Uuid::new_v4(), serde_json::json!({ "name": 42 }));
+
+        let data = save_data(&storage);
+        data.new_group(group("Wallet")).unwrap();
+
+        let problems = data.take_load_problems();
+        assert_eq!(problems.len(), 1);
+        assert!(data.take_load_problems().is_empty());
+        assert_eq!(data.groups.borrow().len(), 1);
+    }
+
+    #[test]
+    fn due_recurring_transactions_are_stored() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let group = group("Home");
+        data.new_group(group.clone()).unwrap();
+
+        let rent = transaction(TransactionType::EXPENSE, "800");
+        group.set_recurring(RecurringTransaction::from_transaction(
+            &rent.data(),
+            Frequency::Monthly,
+            1,
+            RecurrenceEnd::Never,
+        ));
+        group.new_transaction(rent.clone());
+        data.save_group(&group).unwrap();
+
+        let today = Date::new(2022, 9, 4).unwrap();
+        assert!(data.add_due_recurring(today).is_empty());
+        assert_eq!(stored_transaction_count(&storage, &group), 3);
+
+        // Reloading on the same day adds nothing new
+        let reloaded = save_data(&storage);
+        assert!(reloaded.add_due_recurring(today).is_empty());
+        let loaded = reloaded.group_by_id(group.id()).unwrap();
+        assert_eq!(loaded.transactions().len(), 3);
+    }
+
+    #[test]
+    fn transfers_find_their_counterpart() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let savings = group("Savings");
+        let vacation = group("Vacation");
+        data.new_group(savings.clone()).unwrap();
+        data.new_group(vacation.clone()).unwrap();
+
+        let amount: Money = "300".parse().unwrap();
+        let (outgoing, incoming) = transfer::transfer_pair(
+            "Trip",
+            amount,
+            data.convert(amount, savings.currency(), vacation.currency())
+                .unwrap(),
+            "2022-07-04T12:30:00Z",
+            savings.id(),
+            vacation.id(),
+        );
+        let outgoing = Transaction::from_data(outgoing);
+        let incoming = Transaction::from_data(incoming);
+        savings.new_transaction(outgoing.clone());
+        vacation.new_transaction(incoming.clone());
+
+        let (group, counterpart) = data.transfer_counterpart(&outgoing).unwrap();
+        assert_eq!(group.id(), vacation.id());
+        assert_eq!(counterpart.id(), incoming.id());
+
+        let (group, counterpart) = data.transfer_counterpart(&incoming).unwrap();
+        assert_eq!(group.id(), savings.id());
+        assert_eq!(counterpart.id(), outgoing.id());
+
+        assert_eq!(savings.balance(), -amount);
+        assert_eq!(vacation.summary(None).income, Money::ZERO);
+        assert!(data
+            .transfer_counterpart(&transaction(TransactionType::EXPENSE, "1"))
+            .is_none());
+    }
+
+    #[test]
+    fn split_shares_find_each_other() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let groceries = group("Groceries");
+        let pets = group("Pets");
+        data.new_group(groceries.clone()).unwrap();
+        data.new_group(pets.clone()).unwrap();
+
+        let receipt = transaction(TransactionType::EXPENSE, "120");
+        let shares = split::split_transaction(
+            &receipt.data(),
+            &[
+                (groceries.id(), "90".parse().unwrap()),
+                (pets.id(), "30".parse().unwrap()),
+            ],
+        )
+        .unwrap();
+        for (group_id, share) in shares {
+            data.group_by_id(group_id)
+                .unwrap()
+                .new_transaction(Transaction::from_data(share));
+        }
+
+        let share = &groceries.transactions()[0];
+        let siblings = data.split_siblings(share);
+        assert_eq!(siblings.len(), 1);
+        assert_eq!(siblings[0].0.id(), pets.id());
+        assert_eq!(siblings[0].1.amount(), "30".parse().unwrap());
+
+        assert_eq!(groceries.balance(), "-90".parse().unwrap());
+        assert_eq!(share.property::<i64>("split-total"), 1_200_000);
+        assert!(data.split_siblings(&receipt).is_empty());
+    }
+
+    #[test]
+    fn tags_of_every_group_are_listed_once() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let wallet = group("Wallet");
+        let card = group("Card");
+        data.new_group(wallet.clone()).unwrap();
+        data.new_group(card.clone()).unwrap();
+
+        for (group, tags) in [(&wallet, "work, trip 2026"), (&card, "Work")] {
+            let transaction = transaction(TransactionType::EXPENSE, "10");
+            transaction.set_tags(tags::parse_tags(tags));
+            group.new_transaction(transaction);
+        }
+
+        assert_eq!(data.all_tags(), vec!["trip-2026", "work"]);
+        assert_eq!(
+            wallet.transactions()[0].property::<String>("tags"),
+            "work, trip-2026"
+        );
+    }
+
+    #[test]
+    fn frequent_payees_come_first() {
+        let storage = MemoryStorage::new();
+        let data = save_data(&storage);
+
+        let wallet = group("Wallet");
+        let card = group("Card");
+        data.new_group(wallet.clone()).unwrap();
+        data.new_group(card.clone()).unwrap();
+
+        for (group, payee) in [
+            (&wallet, "Bakery"),
+            (&card, "Market"),
+            (&wallet, "Market"),
+            (&card, ""),
+            (&card, "Cinema"),
+        ] {
+            let transaction = transaction(TransactionType::EXPENSE, "10");
+            transaction.set_payee(payee);
+            group.new_transaction(transaction);
+        }
+
+        assert_eq!(data.payees(), vec!["Market", "Bakery", "Cinema"]);
     }
 }
@@ -1,57 +1,25 @@
-use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use glib::{DateTime, ParamSpec, ParamSpecFloat, ParamSpecString};
+use glib::{DateTime, ParamFlags, ParamSpec, ParamSpecInt64, ParamSpecString};
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
 use once_cell::sync::Lazy;
 
-use derivative::Derivative;
-
 use std::cell::RefCell;
 
-use crate::application::CLOCK_FORMAT;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub enum TransactionType {
-    EXPENSE,
-    INCOME,
-}
-
-pub fn transaction_type_to_string(tr_type: &TransactionType) -> String {
-    match tr_type {
-        TransactionType::EXPENSE => String::from("EXPENSE"),
-        TransactionType::INCOME => String::from("INCOME"),
-    }
-}
+use beedget_core::transaction::{transaction_type_from_string, transaction_type_to_string};
 
-pub fn transaction_type_from_string(tr_str: &str) -> TransactionType {
-    match tr_str {
-        "EXPENSE" => TransactionType::EXPENSE,
-        "INCOME" => TransactionType::INCOME,
-        _ => unimplemented!(),
-    }
-}
+use crate::application;
+use crate::models::{Date, Installment, Money, Split, TransactionType, Transfer};
 
 mod imp {
     use super::*;
 
-    #[derive(Derivative, Debug, Serialize, Deserialize)]
-    #[derivative(Default)]
-    pub struct TransactionInner {
-        pub id: Uuid,
-        pub name: String,
-        #[derivative(Default(value = "TransactionType::EXPENSE"))]
-        pub tr_type: TransactionType,
-        pub amount: f32,
-        pub date: String,
-    }
-
     #[derive(Default)]
     pub struct Transaction {
-        pub inner: RefCell<TransactionInner>,
+        pub inner: RefCell<beedget_core::Transaction>,
     }
 
     #[glib::object_subclass]
@@ -68,8 +36,26 @@ mod imp {
                     ParamSpecString::builder("uid").build(),
                     ParamSpecString::builder("name").build(),
                     ParamSpecString::builder("tr-type").build(),
-                    ParamSpecFloat::builder("amount").build(),
+                    ParamSpecInt64::builder("amount").build(),
                     ParamSpecString::builder("date").build(),
+                    // Total of the split this transaction is a share of, 0 if none
+                    ParamSpecInt64::builder("split-total")
+                        .flags(ParamFlags::READABLE)
+                        .build(),
+                    // Tags separated by commas
+                    ParamSpecString::builder("tags")
+                        .flags(ParamFlags::READABLE)
+                        .build(),
+                    // Optional details, empty when not set
+                    ParamSpecString::builder("payee")
+                        .flags(ParamFlags::READABLE)
+                        .build(),
+                    ParamSpecString::builder("location")
+                        .flags(ParamFlags::READABLE)
+                        .build(),
+                    ParamSpecString::builder("notes")
+                        .flags(ParamFlags::READABLE)
+                        .build(),
                 ]
             });
 
@@ -92,7 +78,9 @@ mod imp {
                     self.inner.borrow_mut().tr_type =
                         transaction_type_from_string(value.get().unwrap())
                 }
-                "amount" => self.inner.borrow_mut().amount = value.get().unwrap(),
+                "amount" => {
+                    self.inner.borrow_mut().amount = Money::from_units(value.get().unwrap())
+                }
                 "date" => self.inner.borrow_mut().date = value.get().unwrap(),
                 _ => unimplemented!(),
             }
@@ -103,15 +91,50 @@ mod imp {
                 "uid" => self.inner.borrow().id.to_string().to_value(),
                 "name" => self.inner.borrow().name.to_value(),
                 "tr-type" => transaction_type_to_string(&self.inner.borrow().tr_type).to_value(),
-                "amount" => self.inner.borrow().amount.to_value(),
+                "amount" => self.inner.borrow().amount.units().to_value(),
                 "date" => self.inner.borrow().date.to_value(),
+                "split-total" => self
+                    .inner
+                    .borrow()
+                    .split
+                    .map_or(0, |split| split.total.units())
+                    .to_value(),
+                "tags" => self.inner.borrow().tags.join(", ").to_value(),
+                "payee" => self
+                    .inner
+                    .borrow()
+                    .payee
+                    .clone()
+                    .unwrap_or_default()
+                    .to_value(),
+                "location" => self
+                    .inner
+                    .borrow()
+                    .location
+                    .clone()
+                    .unwrap_or_default()
+                    .to_value(),
+                "notes" => self
+                    .inner
+                    .borrow()
+                    .notes
+                    .clone()
+                    .unwrap_or_default()
+                    .to_value(),
                 _ => unimplemented!(),
             }
         }
     }
 }
 
+/// Trimmed `text`, `None` if nothing is left
+fn non_blank(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
 glib::wrapper! {
+    /// GObject wrapper around `beedget_core::Transaction` for use in list models and bindings
     pub struct Transaction(ObjectSubclass<imp::Transaction>);
 }
 
@@ -121,36 +144,13 @@ impl Default for Transaction {
     }
 }
 
-impl Serialize for Transaction {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        self.imp().inner.serialize(serializer)
-    }
-}
-
-impl<'de> Deserialize<'de> for Transaction {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let inner = imp::TransactionInner::deserialize(deserializer)?;
-        let transaction: Self = glib::Object::new(&[]).expect("Failed to create Transaction");
-
-        transaction.imp().inner.replace(inner);
-
-        Ok(transaction)
-    }
-}
-
 impl Transaction {
-    pub fn new(name: &str, tr_type: TransactionType, amount: f32, date: DateTime) -> Self {
+    pub fn new(name: &str, tr_type: TransactionType, amount: Money, date: DateTime) -> Self {
         glib::Object::new(&[
             ("uid", &Uuid::new_v4().to_string()),
             ("name", &name),
             ("tr-type", &transaction_type_to_string(&tr_type)),
-            ("amount", &amount),
+            ("amount", &amount.units()),
             ("date", &date.format_iso8601().unwrap().as_str()),
         ])
         .expect("Failed to create Transaction")
@@ -160,6 +160,19 @@ impl Transaction {
         glib::Object::new(&[]).expect("Failed to create Transaction")
     }
 
+    /// Wrap transaction data loaded from storage
+    pub fn from_data(data: beedget_core::Transaction) -> Self {
+        let transaction = Self::empty();
+        transaction.imp().inner.replace(data);
+
+        transaction
+    }
+
+    /// Copy of the wrapped transaction data
+    pub fn data(&self) -> beedget_core::Transaction {
+        self.imp().inner.borrow().clone()
+    }
+
     pub fn id(&self) -> Uuid {
         self.imp().inner.borrow().id
     }
@@ -168,21 +181,108 @@ impl Transaction {
         self.imp().inner.borrow().name.clone()
     }
 
-    pub fn amount(&self) -> f32 {
+    pub fn amount(&self) -> Money {
         self.imp().inner.borrow().amount
     }
 
     pub fn tr_type(&self) -> TransactionType {
-        match self.imp().inner.borrow().tr_type {
-            TransactionType::EXPENSE => TransactionType::EXPENSE,
-            TransactionType::INCOME => TransactionType::INCOME,
-        }
+        self.imp().inner.borrow().tr_type
     }
 
     pub fn date(&self) -> DateTime {
         DateTime::from_iso8601(&self.imp().inner.borrow().date, None).unwrap()
     }
 
+    /// ISO 8601 date as stored, cheaper than `date` when only comparing
+    pub fn iso_date(&self) -> String {
+        self.imp().inner.borrow().date.clone()
+    }
+
+    /// Calendar date of the transaction, in local time
+    pub fn day(&self) -> Option<Date> {
+        self.imp().inner.borrow().day()
+    }
+
+    /// Position within a purchase paid in installments, if it is one
+    pub fn installment(&self) -> Option<Installment> {
+        self.imp().inner.borrow().installment
+    }
+
+    /// Link to the other side when this is a transfer
+    pub fn transfer(&self) -> Option<Transfer> {
+        self.imp().inner.borrow().transfer
+    }
+
+    /// Share of a transaction split across groups, if it is one
+    pub fn split(&self) -> Option<Split> {
+        self.imp().inner.borrow().split
+    }
+
+    /// Change the total of the split this transaction is a share of
+    pub fn set_split_total(&self, total: Money) {
+        if let Some(split) = self.imp().inner.borrow_mut().split.as_mut() {
+            split.total = total;
+        }
+        self.notify("split-total");
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.imp().inner.borrow().tags.clone()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.imp().inner.borrow().has_tag(tag)
+    }
+
+    /// Replace tags, which must already be normalized
+    pub fn set_tags(&self, tags: Vec<String>) {
+        self.imp().inner.borrow_mut().tags = tags;
+        self.notify("tags");
+    }
+
+    pub fn payee(&self) -> Option<String> {
+        self.imp().inner.borrow().payee.clone()
+    }
+
+    pub fn location(&self) -> Option<String> {
+        self.imp().inner.borrow().location.clone()
+    }
+
+    pub fn notes(&self) -> Option<String> {
+        self.imp().inner.borrow().notes.clone()
+    }
+
+    /// Set payee, cleared if `payee` is blank
+    pub fn set_payee(&self, payee: &str) {
+        self.imp().inner.borrow_mut().payee = non_blank(payee);
+        self.notify("payee");
+    }
+
+    /// Set location, cleared if `location` is blank
+    pub fn set_location(&self, location: &str) {
+        self.imp().inner.borrow_mut().location = non_blank(location);
+        self.notify("location");
+    }
+
+    /// Set notes, cleared if `notes` is blank
+    pub fn set_notes(&self, notes: &str) {
+        self.imp().inner.borrow_mut().notes = non_blank(notes);
+        self.notify("notes");
+    }
+
+    /// Copy tags, payee, location and notes of `other`
+    pub fn copy_details(&self, other: &Transaction) {
+        self.set_tags(other.tags());
+        self.set_payee(&other.payee().unwrap_or_default());
+        self.set_location(&other.location().unwrap_or_default());
+        self.set_notes(&other.notes().unwrap_or_default());
+    }
+
+    /// Whether `query` is part of any text of the transaction, ignoring case
+    pub fn matches(&self, query: &str) -> bool {
+        self.imp().inner.borrow().matches(query)
+    }
+
     pub fn set_name(&self, name: &str) {
         self.imp().inner.borrow_mut().name = name.to_string();
         self.notify("name");
@@ -193,7 +293,7 @@ impl Transaction {
         self.notify("tr-type");
     }
 
-    pub fn set_amount(&self, amount: f32) {
+    pub fn set_amount(&self, amount: Money) {
         self.imp().inner.borrow_mut().amount = amount;
         self.notify("amount");
     }
@@ -203,11 +303,8 @@ impl Transaction {
         self.notify("date");
     }
 
-    pub fn signed_amount(&self) -> f32 {
-        match self.imp().inner.borrow().tr_type {
-            TransactionType::EXPENSE => -self.imp().inner.borrow().amount,
-            TransactionType::INCOME => self.imp().inner.borrow().amount,
-        }
+    pub fn signed_amount(&self) -> Money {
+        self.imp().inner.borrow().signed_amount()
     }
 
     pub fn relative_date(&self) -> String {
@@ -220,7 +317,7 @@ impl Transaction {
             _ => date_obj.format("%x").unwrap().to_string(),
         };
 
-        let time_component = if CLOCK_FORMAT.as_str() == "12h" {
+        let time_component = if application::clock_format() == "12h" {
             date_obj.format("%r").unwrap().to_string()
         } else {
             date_obj.format("%R").unwrap().to_string()
@@ -229,3 +326,59 @@ impl Transaction {
         day_component + ", " + &time_component
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::transaction;
+
+    #[test]
+    fn signed_amount_follows_changes() {
+        let transaction = transaction(TransactionType::EXPENSE, "10");
+        assert_eq!(transaction.signed_amount(), "-10".parse().unwrap());
+
+        transaction.set_amount("0.3".parse().unwrap());
+        assert_eq!(transaction.signed_amount(), "-0.3".parse().unwrap());
+
+        transaction.change_tr_type(TransactionType::INCOME);
+        assert_eq!(transaction.signed_amount(), "0.3".parse().unwrap());
+    }
+
+    #[test]
+    fn properties_read_wrapped_data() {
+        let transaction = transaction(TransactionType::INCOME, "1234.5678");
+
+        assert_eq!(
+            transaction.property::<String>("uid"),
+            transaction.id().to_string()
+        );
+        assert_eq!(transaction.property::<String>("name"), "Groceries");
+        assert_eq!(transaction.property::<String>("tr-type"), "INCOME");
+        assert_eq!(transaction.property::<i64>("amount"), 12_345_678);
+    }
+
+    #[test]
+    fn blank_details_are_cleared() {
+        let transaction = transaction(TransactionType::EXPENSE, "10");
+
+        transaction.set_payee("  Corner Bakery ");
+        transaction.set_notes("Birthday cake\n");
+        assert_eq!(transaction.payee().as_deref(), Some("Corner Bakery"));
+        assert_eq!(transaction.property::<String>("notes"), "Birthday cake");
+
+        transaction.set_payee(" ");
+        assert_eq!(transaction.payee(), None);
+        assert_eq!(transaction.property::<String>("payee"), "");
+        assert!(transaction.matches("cake"));
+    }
+
+    #[test]
+    fn data_round_trip() {
+        let original = transaction(TransactionType::INCOME, "1234.5678");
+
+        let restored = Transaction::from_data(original.data());
+
+        assert_eq!(restored.data(), original.data());
+        assert_eq!(restored.date().to_unix(), original.date().to_unix());
+    }
+}
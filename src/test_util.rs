@@ -0,0 +1,25 @@
+//! Fixtures shared by the unit tests
+
+use gtk::gdk::RGBA;
+use gtk::glib;
+
+use crate::models::{Currency, Group, Transaction, TransactionType};
+
+pub fn group(name: &str) -> Group {
+    Group::new(
+        "💰",
+        RGBA::new(0.2, 0.4, 0.6, 1.0),
+        name,
+        Currency::from_code("BRL").unwrap(),
+    )
+}
+
+/// Transaction named Groceries on 2022-07-04
+pub fn transaction(tr_type: TransactionType, amount: &str) -> Transaction {
+    Transaction::new(
+        "Groceries",
+        tr_type,
+        amount.parse().unwrap(),
+        glib::DateTime::from_iso8601("2022-07-04T12:30:00Z", None).unwrap(),
+    )
+}
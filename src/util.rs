@@ -1,11 +1,11 @@
-/// Run expression with `Result<(), Error>` return type and panic if it returns error.
+/// Run expression with `Result<(), Error>` return type and log a warning if it returns error.
 #[macro_export]
-macro_rules! force {
+macro_rules! warn_on_err {
     ( $x:expr ) => {
         match $x {
             Ok(()) => {}
             Err(error) => {
-                panic!("{:?}", error);
+                gtk::glib::g_warning!("beedget", "{:?}", error);
             }
         }
     };
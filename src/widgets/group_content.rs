@@ -1,3 +1,4 @@
+use gettextrs::gettext;
 use glib::{ParamFlags, ParamSpec, ParamSpecObject};
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
@@ -5,19 +6,104 @@ use gtk::{glib, CompositeTemplate};
 
 use once_cell::sync::{Lazy, OnceCell};
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::application;
 use crate::models::*;
 use crate::widgets::*;
 
+/// Short localized form of `date`, e.g. "Jul 4"
+fn format_day(date: Date) -> String {
+    glib::DateTime::from_local(
+        date.year(),
+        date.month() as i32,
+        date.day() as i32,
+        0,
+        0,
+        0.0,
+    )
+    .and_then(|datetime| datetime.format("%b %-d"))
+    .map(|text| text.to_string())
+    .unwrap_or_else(|_| date.to_string())
+}
+
+/// Periods offered in the period drop-down, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    AllTime,
+    ThisMonth,
+    LastMonth,
+    ThisYear,
+}
+
+pub const PERIODS: &[Period] = &[
+    Period::AllTime,
+    Period::ThisMonth,
+    Period::LastMonth,
+    Period::ThisYear,
+];
+
+impl Period {
+    pub fn range(&self, today: Date) -> Option<DateRange> {
+        match self {
+            Period::AllTime => None,
+            Period::ThisMonth => Some(DateRange::month_of(today)),
+            Period::LastMonth => Some(DateRange::month_of(today.add_months(-1))),
+            Period::ThisYear => Some(DateRange::year_of(today)),
+        }
+    }
+}
+
 mod imp {
     use super::*;
 
     #[derive(Debug, Default, CompositeTemplate)]
     #[template(resource = "/com/github/matbme/beedget/ui/group-content.ui")]
     pub struct GroupContent {
+        #[template_child]
+        pub balance_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub income_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub expenses_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub budget_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub budget_period_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub budget_progress: TemplateChild<gtk::ProgressBar>,
+
+        #[template_child]
+        pub budget_carried_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub budget_spent_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub budget_remaining_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub tag_dropdown: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        pub period_dropdown: TemplateChild<gtk::DropDown>,
+
         #[template_child]
         pub transaction_history: TemplateChild<gtk::ListBox>,
 
         pub group: OnceCell<Group>,
+        /// "All tags" followed by the tags used in the group
+        pub tag_list: OnceCell<gtk::StringList>,
+        pub history_filter: OnceCell<gtk::CustomFilter>,
+        /// Text searched for from the sidebar
+        pub search_query: RefCell<String>,
+        pub date_sorter: OnceCell<gtk::CustomSorter>,
     }
 
     #[glib::object_subclass]
@@ -89,12 +175,283 @@ impl GroupContent {
     fn init_transaction_history(&self) {
         let group = self.imp().group.get().expect("Group property is not set");
 
+        // Show transactions within the selected period, with the selected tag
+        // and matching the search, newest first
+        let filter = gtk::CustomFilter::new(
+            glib::clone!(@weak self as content => @default-return true, move |item| {
+                let transaction = match item
+                    .downcast_ref::<TransactionRow>()
+                    .and_then(|row| row.transaction())
+                {
+                    Some(transaction) => transaction,
+                    None => return true,
+                };
+
+                let in_period = match (content.period(), transaction.day()) {
+                    (Some(period), Some(day)) => period.contains(day),
+                    _ => true,
+                };
+                let has_tag = content
+                    .selected_tag()
+                    .is_none_or(|tag| transaction.has_tag(&tag));
+
+                in_period && has_tag && content.matches_search(transaction)
+            }),
+        );
+        let sorter = gtk::CustomSorter::new(|first, second| {
+            let date = |item: &glib::Object| {
+                item.downcast_ref::<TransactionRow>()
+                    .and_then(|row| row.transaction())
+                    .map(|transaction| transaction.iso_date())
+                    .unwrap_or_default()
+            };
+
+            date(second).cmp(&date(first)).into()
+        });
+
+        let filtered = gtk::FilterListModel::new(Some(group.transaction_model()), Some(&filter));
+        let sorted = gtk::SortListModel::new(Some(&filtered), Some(&sorter));
+
         self.imp()
             .transaction_history
-            .bind_model(Some(group.transaction_model()), move |item| {
+            .bind_model(Some(&sorted), move |item| {
                 let row = item.downcast_ref::<TransactionRow>().unwrap().clone();
                 row.upcast::<gtk::Widget>()
             });
+
+        let tag_list = gtk::StringList::new(&[&gettext("All tags")[..]]);
+        self.imp().tag_dropdown.set_model(Some(&tag_list));
+        self.imp().tag_list.set(tag_list).unwrap();
+        self.refresh_tags();
+
+        self.imp().history_filter.set(filter).unwrap();
+        self.imp().date_sorter.set(sorter).unwrap();
+
+        // Keep totals, running balances and ordering up to date
+        for signal in [
+            "transaction-added",
+            "transaction-removed",
+            "transaction-changed",
+        ] {
+            group.connect_local(
+                signal,
+                false,
+                glib::clone!(@weak self as content => @default-return None, move |_| {
+                    content.refresh_tags();
+                    content.refresh_history();
+                    None
+                }),
+            );
+        }
+        group.connect_notify_local(
+            Some("currency"),
+            glib::clone!(@weak self as content => move |_, _| {
+                content.refresh_summary();
+            }),
+        );
+        group.connect_local(
+            "budget-changed",
+            false,
+            glib::clone!(@weak self as content => @default-return None, move |_| {
+                content.refresh_budget();
+                None
+            }),
+        );
+        for dropdown in [&*self.imp().period_dropdown, &*self.imp().tag_dropdown] {
+            dropdown.connect_selected_notify(glib::clone!(@weak self as content => move |_| {
+                content.refresh_history();
+            }));
+        }
+        if let Some(settings) = application::settings() {
+            settings.connect_changed(
+                Some("number-format"),
+                glib::clone!(@weak self as content => move |_, _| {
+                    content.refresh_summary();
+                }),
+            );
+        }
+
+        self.refresh_summary();
+    }
+
+    /// Date range of the selected period, `None` for all time
+    fn period(&self) -> Option<DateRange> {
+        PERIODS
+            .get(self.imp().period_dropdown.selected() as usize)
+            .and_then(|period| period.range(application::today()))
+    }
+
+    /// Only show transactions matching `query`, unless the group name does
+    pub fn set_search(&self, query: &str) {
+        self.imp().search_query.replace(query.to_string());
+
+        if let Some(filter) = self.imp().history_filter.get() {
+            filter.changed(gtk::FilterChange::Different);
+        }
+    }
+
+    fn matches_search(&self, transaction: &Transaction) -> bool {
+        let query = self.imp().search_query.borrow();
+
+        query.is_empty()
+            || transaction.matches(&query)
+            || self
+                .group()
+                .name()
+                .to_lowercase()
+                .contains(&query.to_lowercase())
+    }
+
+    /// Tag selected in the tag drop-down, `None` for all tags
+    fn selected_tag(&self) -> Option<String> {
+        match self.imp().tag_dropdown.selected() {
+            0 | gtk::INVALID_LIST_POSITION => None,
+            position => self
+                .imp()
+                .tag_list
+                .get()?
+                .string(position)
+                .map(|tag| tag.to_string()),
+        }
+    }
+
+    /// Offer the tags currently used in the group, keeping the selected one
+    fn refresh_tags(&self) {
+        let tag_list = match self.imp().tag_list.get() {
+            Some(tag_list) => tag_list,
+            None => return,
+        };
+
+        let mut tags: Vec<String> = self
+            .group()
+            .transactions()
+            .iter()
+            .flat_map(|transaction| transaction.tags())
+            .collect();
+        tags.sort();
+        tags.dedup();
+
+        let selected = self.selected_tag();
+        let tag_refs: Vec<&str> = tags.iter().map(String::as_str).collect();
+        tag_list.splice(1, tag_list.n_items() - 1, &tag_refs);
+
+        let position = selected
+            .and_then(|selected| tags.iter().position(|tag| *tag == selected))
+            .map_or(0, |index| index as u32 + 1);
+        self.imp().tag_dropdown.set_selected(position);
+        self.imp().tag_dropdown.set_visible(!tags.is_empty());
+    }
+
+    fn refresh_history(&self) {
+        if let Some(filter) = self.imp().history_filter.get() {
+            filter.changed(gtk::FilterChange::Different);
+        }
+        if let Some(sorter) = self.imp().date_sorter.get() {
+            sorter.changed(gtk::SorterChange::Different);
+        }
+
+        self.refresh_summary();
+    }
+
+    /// Update header totals for the selected period and each row's running balance
+    fn refresh_summary(&self) {
+        let group = self.group();
+        let currency = group.currency();
+        let number_format = application::number_format();
+
+        let summary = group.summary(self.period());
+        self.imp()
+            .balance_label
+            .set_label(&currency.format_with(summary.balance, number_format));
+        self.imp()
+            .income_label
+            .set_label(&currency.format_with(summary.income, number_format));
+        self.imp()
+            .expenses_label
+            .set_label(&currency.format_with(summary.expenses, number_format));
+
+        let balances: HashMap<_, _> = group.running_balances().into_iter().collect();
+        let model = group.transaction_model();
+        for i in 0..model.n_items() {
+            let row = model.item(i).unwrap().downcast::<TransactionRow>().unwrap();
+            let balance = row
+                .transaction()
+                .and_then(|transaction| balances.get(&transaction.id()));
+
+            if let Some(balance) = balance {
+                row.set_running_balance(*balance);
+            }
+        }
+
+        self.refresh_budget();
+    }
+
+    /// Show spending against the limit for the current budget period
+    fn refresh_budget(&self) {
+        let imp = self.imp();
+        let group = self.group();
+
+        let status = match group.budget_status() {
+            Some(status) => status,
+            None => {
+                imp.budget_box.set_visible(false);
+                return;
+            }
+        };
+
+        let currency = group.currency();
+        let number_format = application::number_format();
+        let format = |amount| currency.format_with(amount, number_format);
+
+        imp.budget_period_label.set_label(&format!(
+            "{} – {}",
+            format_day(status.range.start),
+            format_day(status.range.end.add_days(-1))
+        ));
+        imp.budget_progress.set_fraction(status.fraction().min(1.0));
+
+        imp.budget_carried_label
+            .set_visible(!status.carried.is_zero());
+        if status.carried.is_negative() {
+            imp.budget_carried_label.set_label(&format!(
+                "{} {}",
+                gettext("Deficit from last period:"),
+                format(-status.carried)
+            ));
+        } else {
+            imp.budget_carried_label.set_label(&format!(
+                "{} {}",
+                gettext("Carried from last period:"),
+                format(status.carried)
+            ));
+        }
+
+        imp.budget_spent_label.set_label(&format!(
+            "{} {} / {}",
+            gettext("Spent"),
+            format(status.spent),
+            format(status.available())
+        ));
+
+        if status.is_over() {
+            imp.budget_remaining_label.set_label(&format!(
+                "{} {}",
+                gettext("Over by"),
+                format(-status.remaining())
+            ));
+            imp.budget_remaining_label.add_css_class("error");
+            imp.budget_progress.add_css_class("over-budget");
+        } else {
+            imp.budget_remaining_label.set_label(&format!(
+                "{} {}",
+                gettext("Left:"),
+                format(status.remaining())
+            ));
+            imp.budget_remaining_label.remove_css_class("error");
+            imp.budget_progress.remove_css_class("over-budget");
+        }
+
+        imp.budget_box.set_visible(true);
     }
 
     pub fn group(&self) -> &Group {
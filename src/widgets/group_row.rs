@@ -1,4 +1,5 @@
 use gdk::RGBA;
+use gettextrs::gettext;
 use glib::{ParamSpec, ParamSpecString};
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
@@ -6,15 +7,15 @@ use gtk::{gdk, gio, glib, CompositeTemplate};
 
 use gtk::cairo::{LineJoin, LinearGradient};
 
-use once_cell::sync::{Lazy, OnceCell};
+use once_cell::sync::Lazy;
 
 use std::cell::RefCell;
 use std::f64::consts::PI;
 
 use crate::application;
 use crate::dialogs::*;
-use crate::force;
 use crate::models::*;
+use crate::warn_on_err;
 
 mod imp {
     use super::*;
@@ -34,12 +35,20 @@ mod imp {
         #[template_child]
         pub name: TemplateChild<gtk::Label>,
 
+        #[template_child]
+        pub over_budget_icon: TemplateChild<gtk::Image>,
+
+        #[template_child]
+        pub balance_badge: TemplateChild<gtk::Label>,
+
         #[template_child]
         pub options_menu: TemplateChild<gtk::PopoverMenu>,
 
-        pub group: OnceCell<Group>,
+        pub group: RefCell<Option<Group>>,
 
         pub bindings: RefCell<Vec<glib::Binding>>,
+        pub group_handlers: RefCell<Vec<glib::SignalHandlerId>>,
+        pub export_chooser: RefCell<Option<gtk::FileChooserNative>>,
     }
 
     #[glib::object_subclass]
@@ -106,6 +115,15 @@ mod imp {
 
             obj.add_controller(&click_event_controller);
 
+            if let Some(settings) = application::settings() {
+                settings.connect_changed(
+                    Some("number-format"),
+                    glib::clone!(@weak obj as parent => move |_, _| {
+                        parent.refresh_balance();
+                    }),
+                );
+            }
+
             obj.setup_gactions();
         }
     }
@@ -155,15 +173,79 @@ impl GroupRow {
 
         bindings.push(group_color_binding);
 
-        if self.imp().group.get().is_none() {
-            self.imp().group.set(group.to_owned()).unwrap();
+        // Follow the balance of the group even while another one is open
+        let mut handlers = self.imp().group_handlers.borrow_mut();
+        for signal in [
+            "transaction-added",
+            "transaction-removed",
+            "transaction-changed",
+            "budget-changed",
+        ] {
+            handlers.push(group.connect_local(
+                signal,
+                false,
+                glib::clone!(@weak self as parent => @default-return None, move |_| {
+                    parent.refresh_balance();
+                    None
+                }),
+            ));
         }
+        handlers.push(group.connect_notify_local(
+            Some("currency"),
+            glib::clone!(@weak self as parent => move |_, _| {
+                parent.refresh_balance();
+            }),
+        ));
+
+        self.imp().group.replace(Some(group.to_owned()));
+        self.refresh_balance();
     }
 
     pub fn unbind(&self) {
         for binding in self.imp().bindings.borrow_mut().drain(..) {
             binding.unbind();
         }
+
+        if let Some(group) = self.imp().group.take() {
+            for handler in self.imp().group_handlers.borrow_mut().drain(..) {
+                group.disconnect(handler);
+            }
+        }
+    }
+
+    fn group(&self) -> Group {
+        self.imp()
+            .group
+            .borrow()
+            .clone()
+            .expect("No Group bound to GroupRow")
+    }
+
+    /// Show the group balance, styled by its sign, and the over-budget indicator
+    fn refresh_balance(&self) {
+        let group = match self.imp().group.borrow().clone() {
+            Some(group) => group,
+            None => return,
+        };
+
+        let balance = group.balance();
+        let badge = &self.imp().balance_badge;
+        badge.set_label(
+            &group
+                .currency()
+                .format_with(balance, application::number_format()),
+        );
+        if balance.is_negative() {
+            badge.remove_css_class("income");
+            badge.add_css_class("expense");
+        } else {
+            badge.remove_css_class("expense");
+            badge.add_css_class("income");
+        }
+
+        self.imp()
+            .over_budget_icon
+            .set_visible(group.is_over_budget());
     }
 
     fn setup_gactions(&self) {
@@ -173,7 +255,7 @@ impl GroupRow {
         edit_action.connect_activate(glib::clone!(@weak self as parent => move |_, _| {
             let dialog = GroupDialog::edit(
                 parent.root().unwrap().downcast_ref::<gtk::Window>().unwrap(),
-                &parent.imp().group.get().unwrap()
+                &parent.group()
             );
             dialog.present();
         }));
@@ -185,6 +267,12 @@ impl GroupRow {
         }));
         group_action_group.add_action(&delete_action);
 
+        let export_qif_action = gio::SimpleAction::new("export-qif", None);
+        export_qif_action.connect_activate(glib::clone!(@weak self as parent => move |_, _| {
+            parent.export_qif();
+        }));
+        group_action_group.add_action(&export_qif_action);
+
         self.insert_action_group("group", Some(&group_action_group));
     }
 
@@ -229,18 +317,18 @@ impl GroupRow {
                     (color.green() + (color.green() * 0.2)) as f64,
                     (color.blue() + (color.blue() * 0.2)) as f64
                 );
-                force!(ctx.set_source(&gradient));
+                warn_on_err!(ctx.set_source(&gradient));
 
-                force!(ctx.save());
+                warn_on_err!(ctx.save());
 
                 ctx.arc(width / 2.0,     // x
                         height / 2.0,    // y
                         height / 2.0,    // radius
                         0.0,
                         2.0 * PI);
-                force!(ctx.fill());
+                warn_on_err!(ctx.fill());
 
-                force!(ctx.restore());
+                warn_on_err!(ctx.restore());
             }));
 
         self.imp().icon.queue_draw();
@@ -253,9 +341,57 @@ impl GroupRow {
             .add_overlay(self.imp().icon_emoji.upcast_ref::<gtk::Label>());
     }
 
+    /// Ask where to save the group as QIF and write it there
+    fn export_qif(&self) {
+        let group = self.group();
+        let window = self
+            .root()
+            .and_then(|root| root.downcast::<gtk::Window>().ok());
+
+        let chooser = gtk::FileChooserNative::new(
+            Some(&gettext("Export Group")),
+            window.as_ref(),
+            gtk::FileChooserAction::Save,
+            Some(&gettext("_Export")),
+            Some(&gettext("_Cancel")),
+        );
+        chooser.set_modal(true);
+        chooser.set_current_name(&format!("{}.qif", group.name()));
+
+        // The row may show another group by the time the user answers
+        chooser.connect_response(
+            glib::clone!(@weak self as parent => move |chooser, response| {
+                if response == gtk::ResponseType::Accept {
+                    if let Some(file) = chooser.file() {
+                        let qif = export::qif::write(&group.data());
+                        if let Err(error) = file.replace_contents(
+                            qif.as_bytes(),
+                            None,
+                            false,
+                            gio::FileCreateFlags::REPLACE_DESTINATION,
+                            gio::Cancellable::NONE,
+                        ) {
+                            application!(parent @as crate::BeedgetApplication)
+                                .report_error(&anyhow::Error::from(error), None);
+                        }
+                    }
+                }
+
+                parent.imp().export_chooser.replace(None);
+            }),
+        );
+
+        chooser.show();
+
+        // Native dialogs are not kept alive by their parent
+        self.imp().export_chooser.replace(Some(chooser));
+    }
+
     fn delete_group(&self) {
-        application!(self @as crate::BeedgetApplication)
-            .data()
-            .delete_group(&self.imp().group.get().unwrap());
+        let application = application!(self @as crate::BeedgetApplication);
+
+        if let Err(error) = application.data().delete_group(&self.group()) {
+            application.report_error(&error, None);
+        }
     }
 }
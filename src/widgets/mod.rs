@@ -1,9 +1,11 @@
 pub mod date_time_picker;
 pub mod group_content;
 pub mod group_row;
+pub mod split_editor;
 pub mod transaction_row;
 
 pub use date_time_picker::*;
 pub use group_content::*;
 pub use group_row::*;
+pub use split_editor::*;
 pub use transaction_row::*;
@@ -0,0 +1,220 @@
+use gettextrs::gettext;
+use glib::subclass::Signal;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+
+use once_cell::sync::Lazy;
+
+use std::cell::{Cell, RefCell};
+
+use crate::application;
+use crate::models::*;
+
+/// Widgets of one share in the editor
+#[derive(Debug)]
+pub struct ShareRow {
+    pub row: gtk::ListBoxRow,
+    pub group_select: gtk::DropDown,
+    pub amount_entry: gtk::Entry,
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/matbme/beedget/ui/split-editor.ui")]
+    pub struct SplitEditor {
+        #[template_child]
+        pub split_list: TemplateChild<gtk::ListBox>,
+
+        #[template_child]
+        pub remaining_label: TemplateChild<gtk::Label>,
+
+        pub rows: RefCell<Vec<ShareRow>>,
+        pub currency: Cell<Option<&'static Currency>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SplitEditor {
+        const NAME: &'static str = "SplitEditor";
+        type Type = super::SplitEditor;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+            Self::Type::bind_template_callbacks(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for SplitEditor {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![Signal::builder("changed", &[], <()>::static_type().into()).build()]
+            });
+
+            SIGNALS.as_ref()
+        }
+    }
+
+    impl WidgetImpl for SplitEditor {}
+    impl BoxImpl for SplitEditor {}
+}
+
+glib::wrapper! {
+    /// List of groups and amounts a transaction is split between
+    pub struct SplitEditor(ObjectSubclass<imp::SplitEditor>)
+        @extends gtk::Box, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+#[gtk::template_callbacks]
+impl SplitEditor {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create `SplitEditor`.")
+    }
+
+    /// Currency amounts are entered in
+    pub fn set_currency(&self, currency: &'static Currency) {
+        self.imp().currency.set(Some(currency));
+
+        for row in self.imp().rows.borrow().iter() {
+            row.amount_entry.set_placeholder_text(Some(currency.symbol));
+            self.update_amount_style(&row.amount_entry);
+        }
+    }
+
+    fn currency(&self) -> &'static Currency {
+        self.imp()
+            .currency
+            .get()
+            .unwrap_or_else(application::default_currency)
+    }
+
+    /// Group and amount of every share, `None` while any of them is incomplete
+    pub fn shares(&self) -> Option<Vec<(Group, Money)>> {
+        self.imp()
+            .rows
+            .borrow()
+            .iter()
+            .map(|row| {
+                let group = row
+                    .group_select
+                    .selected_item()
+                    .and_then(|item| item.downcast::<Group>().ok());
+
+                Some((group?, self.amount(&row.amount_entry)?))
+            })
+            .collect()
+    }
+
+    /// Positive amount typed in `entry`, if any
+    fn amount(&self, entry: &gtk::Entry) -> Option<Money> {
+        self.currency()
+            .parse(entry.text().as_str(), application::number_format())
+            .ok()
+            .filter(|amount| amount.is_positive())
+    }
+
+    /// Mark `entry` while it holds something that is not a valid amount
+    fn update_amount_style(&self, entry: &gtk::Entry) {
+        if entry.text_length() == 0 || self.amount(entry).is_some() {
+            entry.remove_css_class("error");
+        } else {
+            entry.add_css_class("error");
+        }
+    }
+
+    /// Explain what is left for the group the transaction was entered in
+    pub fn set_remaining_text(&self, text: &str) {
+        self.imp().remaining_label.set_label(text);
+    }
+
+    /// Append an empty share
+    #[template_callback]
+    pub fn add_share(&self) {
+        let group_select = gtk::DropDown::builder()
+            .hexpand(true)
+            .enable_search(true)
+            .factory(&Group::factory())
+            .expression(&Group::search_expression())
+            .build();
+        if let Some(application) = application::instance() {
+            group_select.set_model(Some(application.data().group_model()));
+        }
+
+        let amount_entry = gtk::Entry::builder()
+            .width_chars(8)
+            .placeholder_text(self.currency().symbol)
+            .build();
+
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(&gettext("Remove"))
+            .css_classes(vec![String::from("flat")])
+            .build();
+
+        let content = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(10)
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .build();
+        content.append(&group_select);
+        content.append(&amount_entry);
+        content.append(&remove_button);
+
+        let row = gtk::ListBoxRow::builder()
+            .activatable(false)
+            .child(&content)
+            .build();
+
+        group_select.connect_selected_notify(glib::clone!(@weak self as editor => move |_| {
+            editor.emit_by_name::<()>("changed", &[]);
+        }));
+        amount_entry.buffer().connect_text_notify(
+            glib::clone!(@weak self as editor, @weak amount_entry => move |_| {
+                editor.update_amount_style(&amount_entry);
+                editor.emit_by_name::<()>("changed", &[]);
+            }),
+        );
+        remove_button.connect_clicked(glib::clone!(@weak self as editor, @weak row => move |_| {
+            editor.remove_share(&row);
+        }));
+
+        self.imp().split_list.append(&row);
+        self.imp().split_list.set_visible(true);
+        self.imp().rows.borrow_mut().push(ShareRow {
+            row,
+            group_select,
+            amount_entry,
+        });
+
+        self.emit_by_name::<()>("changed", &[]);
+    }
+
+    fn remove_share(&self, row: &gtk::ListBoxRow) {
+        self.imp()
+            .rows
+            .borrow_mut()
+            .retain(|share| &share.row != row);
+        self.imp().split_list.remove(row);
+        self.imp()
+            .split_list
+            .set_visible(!self.imp().rows.borrow().is_empty());
+
+        self.emit_by_name::<()>("changed", &[]);
+    }
+}
+
+impl Default for SplitEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,4 +1,5 @@
-use glib::{ParamFlags, ParamSpec, ParamSpecObject};
+use gettextrs::gettext;
+use glib::{ParamFlags, ParamSpec, ParamSpecObject, ParamSpecString};
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::{gio, glib, CompositeTemplate};
@@ -8,8 +9,9 @@ use adw::subclass::prelude::*;
 
 use once_cell::sync::{Lazy, OnceCell};
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
+use crate::application;
 use crate::dialogs::*;
 use crate::models::*;
 use crate::widgets::*;
@@ -23,19 +25,50 @@ mod imp {
         #[template_child]
         pub amount_label: TemplateChild<gtk::Label>,
 
+        #[template_child]
+        pub balance_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub installment_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub tags_box: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub transfer_label: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub split_label: TemplateChild<gtk::Label>,
+
         #[template_child]
         pub options_button: TemplateChild<gtk::MenuButton>,
 
+        #[template_child]
+        pub payee_row: TemplateChild<adw::ActionRow>,
+
+        #[template_child]
+        pub location_row: TemplateChild<adw::ActionRow>,
+
+        #[template_child]
+        pub notes_row: TemplateChild<gtk::ListBoxRow>,
+
+        #[template_child]
+        pub notes_label: TemplateChild<gtk::Label>,
+
         pub transaction: OnceCell<Transaction>,
+        pub currency: RefCell<String>,
+        pub running_balance: Cell<Option<Money>>,
 
         pub bindings: RefCell<Vec<glib::Binding>>,
+        /// Application settings and the handler following display preferences
+        pub settings_handler: RefCell<Option<(gio::Settings, glib::SignalHandlerId)>>,
     }
 
     #[glib::object_subclass]
     impl ObjectSubclass for TransactionRow {
         const NAME: &'static str = "TransactionRow";
         type Type = super::TransactionRow;
-        type ParentType = adw::ActionRow;
+        type ParentType = adw::ExpanderRow;
 
         fn class_init(klass: &mut Self::Class) {
             Self::bind_template(klass);
@@ -53,6 +86,7 @@ mod imp {
                     ParamSpecObject::builder("transaction", Transaction::static_type())
                         .flags(ParamFlags::CONSTRUCT | ParamFlags::READWRITE)
                         .build(),
+                    ParamSpecString::builder("currency").build(),
                 ]
             });
 
@@ -61,7 +95,7 @@ mod imp {
 
         fn set_property(
             &self,
-            _obj: &Self::Type,
+            obj: &Self::Type,
             _id: usize,
             value: &glib::Value,
             pspec: &ParamSpec,
@@ -74,6 +108,12 @@ mod imp {
                             .expect("Transaction pointer was already set!");
                     }
                 }
+                "currency" => {
+                    if let Ok(input) = value.get::<String>() {
+                        self.currency.replace(input);
+                        obj.update_amount_label();
+                    }
+                }
                 _ => unimplemented!(),
             }
         }
@@ -88,19 +128,39 @@ mod imp {
                 .get()
                 .expect("No transaction assigned to row");
 
-            // Bind transaction amount to label
-            let amount_binding = transaction
-                .bind_property("amount", &obj.imp().amount_label.get(), "label")
-                .transform_to(|_, value| {
-                    if let Ok(amount) = value.get::<f32>() {
-                        Some(format!("{:.2}", amount.abs()).to_value())
-                    } else {
-                        None
-                    }
-                })
-                .flags(glib::BindingFlags::SYNC_CREATE)
-                .build();
-            bindings.push(amount_binding);
+            // Listen for transaction amount changes
+            transaction.connect_notify_local(
+                Some("amount"),
+                glib::clone!(@weak obj as parent => move |_, _| {
+                    parent.update_amount_label();
+                }),
+            );
+
+            // Listen for changes to the total of a split
+            transaction.connect_notify_local(
+                Some("split-total"),
+                glib::clone!(@weak obj as parent => move |_, _| {
+                    parent.update_amount_label();
+                }),
+            );
+
+            // Listen for tag changes
+            transaction.connect_notify_local(
+                Some("tags"),
+                glib::clone!(@weak obj as parent => move |_, _| {
+                    parent.update_tags();
+                }),
+            );
+
+            // Listen for changes to the details shown when expanded
+            for property in ["payee", "location", "notes"] {
+                transaction.connect_notify_local(
+                    Some(property),
+                    glib::clone!(@weak obj as parent => move |_, _| {
+                        parent.update_details();
+                    }),
+                );
+            }
 
             // Listen for transaction type changes
             transaction.connect_notify_local(
@@ -125,22 +185,58 @@ mod imp {
                 }),
             );
 
+            // Follow display preferences
+            if let Some(settings) = application::settings() {
+                let handler = settings.connect_changed(
+                    None,
+                    glib::clone!(@weak obj as parent => move |_, key| match key {
+                        "number-format" => parent.update_amount_label(),
+                        "clock-format" => {
+                            let transaction = parent.imp().transaction.get().unwrap();
+                            parent.set_subtitle(&transaction.relative_date());
+                        }
+                        _ => {}
+                    }),
+                );
+                self.settings_handler.replace(Some((settings, handler)));
+            }
+
+            if let Some(transfer) = transaction.transfer() {
+                obj.show_transfer_group(transfer);
+            }
+
+            if let Some(installment) = transaction.installment() {
+                obj.imp()
+                    .installment_label
+                    .set_label(&installment.to_string());
+                obj.imp().installment_label.set_visible(true);
+            }
+
             obj.apply_css(transaction.tr_type());
+            obj.update_amount_label();
+            obj.update_tags();
+            obj.update_details();
             obj.set_subtitle(&transaction.relative_date());
 
             obj.setup_gactions();
         }
+
+        fn dispose(&self, _obj: &Self::Type) {
+            if let Some((settings, handler)) = self.settings_handler.take() {
+                settings.disconnect(handler);
+            }
+        }
     }
 
     impl WidgetImpl for TransactionRow {}
     impl ListBoxRowImpl for TransactionRow {}
     impl PreferencesRowImpl for TransactionRow {}
-    impl ActionRowImpl for TransactionRow {}
+    impl ExpanderRowImpl for TransactionRow {}
 }
 
 glib::wrapper! {
     pub struct TransactionRow(ObjectSubclass<imp::TransactionRow>)
-        @extends adw::ActionRow, adw::PreferencesRow, gtk::ListBoxRow, gtk::Widget,
+        @extends adw::ExpanderRow, adw::PreferencesRow, gtk::ListBoxRow, gtk::Widget,
         @implements gtk::Accessible, gtk::Actionable, gtk::Buildable, gtk::ConstraintTarget;
 }
 
@@ -154,6 +250,12 @@ impl TransactionRow {
         self.imp().transaction.get()
     }
 
+    /// Show group balance right after this transaction
+    pub fn set_running_balance(&self, balance: Money) {
+        self.imp().running_balance.set(Some(balance));
+        self.update_amount_label();
+    }
+
     fn setup_gactions(&self) {
         let transaction_action_group = gio::SimpleActionGroup::new();
 
@@ -181,15 +283,175 @@ impl TransactionRow {
                 .downcast_ref::<GroupContent>().unwrap()
                 .imp().group.get().unwrap();
 
-            let application = parent.root().unwrap().downcast_ref::<gtk::Window>().unwrap().application().unwrap();
-            group.delete_transaction(parent.imp().transaction.get().unwrap().id());
-            application.emit_by_name::<()>("save-group", &[&group]);
+            let transaction = parent.imp().transaction.get().unwrap();
+            let later = group.later_installments(transaction);
+            if transaction.transfer().is_some() {
+                Self::delete_transfer(group, transaction);
+            } else if transaction.split().is_some() {
+                Self::delete_split_share(group, transaction);
+            } else if later.is_empty() {
+                Self::delete_transactions(group, &[transaction.clone()]);
+            } else {
+                let group = group.clone();
+                let mut all = vec![transaction.clone()];
+                all.extend(later.iter().cloned());
+
+                ask_about_later_installments(
+                    parent.root().unwrap().downcast_ref::<gtk::Window>().unwrap(),
+                    &gettext("Delete later installments too?"),
+                    later.len(),
+                    glib::clone!(@weak group, @strong transaction => move |include_later| {
+                        if include_later {
+                            Self::delete_transactions(&group, &all);
+                        } else {
+                            Self::delete_transactions(&group, &[transaction.clone()]);
+                        }
+                    }),
+                );
+            }
         }));
         transaction_action_group.add_action(&delete_action);
 
         self.insert_action_group("transaction", Some(&transaction_action_group));
     }
 
+    /// Delete both sides of a transfer
+    fn delete_transfer(group: &Group, transaction: &Transaction) {
+        let application = application::instance().expect("Application is not running");
+
+        if let Some((other_group, counterpart)) =
+            application.data().transfer_counterpart(transaction)
+        {
+            Self::delete_transactions(&other_group, &[counterpart]);
+        }
+        Self::delete_transactions(group, &[transaction.clone()]);
+    }
+
+    /// Delete one share of a split, leaving the other groups' shares
+    fn delete_split_share(group: &Group, transaction: &Transaction) {
+        let application = application::instance().expect("Application is not running");
+
+        for (other_group, sibling) in application.data().split_siblings(transaction) {
+            if let Some(split) = sibling.split() {
+                sibling.set_split_total(split.total - transaction.amount());
+            }
+            application.emit_by_name::<()>("save-transaction", &[&other_group, &sibling]);
+        }
+        Self::delete_transactions(group, &[transaction.clone()]);
+    }
+
+    /// Name the group on the other side of a transfer
+    fn show_transfer_group(&self, transfer: Transfer) {
+        let other_group = application::instance()
+            .and_then(|application| application.data().group_by_id(transfer.group));
+        let name = match other_group {
+            Some(group) => format!("{} {}", group.emoji(), group.name()),
+            None => gettext("Deleted group"),
+        };
+
+        let (label, tooltip) = if transfer.incoming {
+            (format!("← {}", name), gettext("Transfer from"))
+        } else {
+            (format!("→ {}", name), gettext("Transfer to"))
+        };
+
+        self.imp().transfer_label.set_label(&label);
+        self.imp().transfer_label.set_tooltip_text(Some(&tooltip));
+        self.imp().transfer_label.set_visible(true);
+    }
+
+    /// Show a chip for each tag of the transaction
+    fn update_tags(&self) {
+        let tags_box = &self.imp().tags_box;
+        while let Some(chip) = tags_box.first_child() {
+            tags_box.remove(&chip);
+        }
+
+        let tags = self
+            .imp()
+            .transaction
+            .get()
+            .map(|transaction| transaction.tags())
+            .unwrap_or_default();
+        for tag in &tags {
+            let chip = gtk::Label::new(Some(tag));
+            chip.add_css_class("tag");
+            tags_box.append(&chip);
+        }
+
+        tags_box.set_visible(!tags.is_empty());
+    }
+
+    /// Show payee, location and notes when expanded, only allowing expansion
+    /// if there are any
+    fn update_details(&self) {
+        let imp = self.imp();
+        let transaction = match imp.transaction.get() {
+            Some(transaction) => transaction,
+            None => return,
+        };
+
+        let payee = transaction.payee();
+        imp.payee_row.set_visible(payee.is_some());
+        imp.payee_row
+            .set_subtitle(payee.as_deref().unwrap_or_default());
+
+        let location = transaction.location();
+        imp.location_row.set_visible(location.is_some());
+        imp.location_row
+            .set_subtitle(location.as_deref().unwrap_or_default());
+
+        let notes = transaction.notes();
+        imp.notes_row.set_visible(notes.is_some());
+        imp.notes_label
+            .set_label(notes.as_deref().unwrap_or_default());
+
+        let has_details = payee.is_some() || location.is_some() || notes.is_some();
+        self.set_enable_expansion(has_details);
+        if !has_details {
+            self.set_expanded(false);
+        }
+    }
+
+    fn delete_transactions(group: &Group, transactions: &[Transaction]) {
+        let application = application::instance().expect("Application is not running");
+
+        for transaction in transactions {
+            group.delete_transaction(transaction.id());
+            application.emit_by_name::<()>("delete-transaction", &[group, transaction]);
+        }
+    }
+
+    /// Format transaction amount and running balance using the currency of its group
+    fn update_amount_label(&self) {
+        let currency = Currency::from_code_or_default(&self.imp().currency.borrow());
+        let number_format = application::number_format();
+
+        if let Some(transaction) = self.imp().transaction.get() {
+            self.imp()
+                .amount_label
+                .set_label(&currency.format_with(transaction.amount(), number_format));
+
+            let split = transaction.split();
+            self.imp().split_label.set_visible(split.is_some());
+            if let Some(split) = split {
+                self.imp().split_label.set_label(&format!(
+                    "{} {}",
+                    gettext("Share of"),
+                    currency.format_with(split.total, number_format)
+                ));
+            }
+        }
+
+        let balance = self.imp().running_balance.get();
+        self.imp().balance_label.set_visible(balance.is_some());
+        if let Some(balance) = balance {
+            self.imp()
+                .balance_label
+                .set_label(&currency.format_with(balance, number_format));
+        }
+    }
+
     fn apply_css(&self, tr_type: TransactionType) {
         if let Some(cls) = self.imp().amount_label.css_classes().last() {
             self.imp().amount_label.remove_css_class(cls);
@@ -198,6 +460,7 @@ impl TransactionRow {
         match tr_type {
             TransactionType::EXPENSE => self.imp().amount_label.add_css_class("expense"),
             TransactionType::INCOME => self.imp().amount_label.add_css_class("income"),
+            TransactionType::TRANSFER => self.imp().amount_label.add_css_class("transfer"),
         }
     }
 }
@@ -1,3 +1,4 @@
+use gettextrs::gettext;
 use glib::clone;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
@@ -6,6 +7,10 @@ use gtk::{gio, glib, CompositeTemplate};
 use adw::prelude::BinExt;
 use adw::subclass::application_window::AdwApplicationWindowImpl;
 
+use once_cell::sync::OnceCell;
+
+use std::cell::RefCell;
+
 use crate::application;
 use crate::dialogs::*;
 use crate::models::*;
@@ -20,6 +25,9 @@ mod imp {
         #[template_child]
         pub main_headerbar: TemplateChild<adw::HeaderBar>,
 
+        #[template_child]
+        pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+
         #[template_child]
         pub pane: TemplateChild<adw::Leaflet>,
 
@@ -29,11 +37,18 @@ mod imp {
         #[template_child]
         pub sidebar: TemplateChild<gtk::ListView>,
 
+        #[template_child]
+        pub total_label: TemplateChild<gtk::Label>,
+
         #[template_child]
         pub content_pane: TemplateChild<gtk::Box>,
 
         #[template_child]
         pub content: TemplateChild<adw::Bin>,
+
+        /// Text of the search entry, matched against groups and transactions
+        pub search_query: RefCell<String>,
+        pub group_filter: OnceCell<gtk::CustomFilter>,
     }
 
     #[glib::object_subclass]
@@ -86,21 +101,18 @@ impl BeedgetWindow {
 
     #[template_callback]
     fn filter_group_list(&self, entry: &gtk::SearchEntry) {
-        self.imp()
-            .sidebar
-            .model()
-            .unwrap()
-            .downcast_ref::<gtk::SingleSelection>()
-            .unwrap()
-            .model()
-            .unwrap()
-            .downcast_ref::<gtk::FilterListModel>()
-            .unwrap()
-            .filter()
-            .unwrap()
-            .downcast_ref::<gtk::StringFilter>()
-            .unwrap()
-            .set_search(Some(&entry.text()));
+        self.imp().search_query.replace(entry.text().to_string());
+
+        if let Some(filter) = self.imp().group_filter.get() {
+            filter.changed(gtk::FilterChange::Different);
+        }
+
+        if let Some(content) = self.imp().content.child() {
+            content
+                .downcast_ref::<GroupContent>()
+                .unwrap()
+                .set_search(&entry.text());
+        }
     }
 
     #[template_callback]
@@ -120,6 +132,32 @@ impl BeedgetWindow {
         dialog.present();
     }
 
+    fn open_import_dialog(&self) {
+        let dialog = match self.imp().content.child() {
+            Some(content) => ImportDialog::new(
+                self.upcast_ref(),
+                Some(content.downcast_ref::<GroupContent>().unwrap().group()),
+            ),
+            None => ImportDialog::new(self.upcast_ref(), None),
+        };
+        dialog.present();
+    }
+
+    fn open_export_dialog(&self) {
+        let dialog = ExportDialog::new(self.upcast_ref());
+        dialog.present();
+    }
+
+    fn open_recurring_window(&self) {
+        let window = RecurringWindow::new(self.upcast_ref());
+        window.present();
+    }
+
+    fn open_tags_window(&self) {
+        let window = TagsWindow::new(self.upcast_ref());
+        window.present();
+    }
+
     fn setup_gactions(&self) {
         let open_group_dialog_action = gio::SimpleAction::new("open-group-dialog", None);
         open_group_dialog_action.connect_activate(clone!(@weak self as win => move |_, _| {
@@ -140,6 +178,59 @@ impl BeedgetWindow {
             win.imp().search_bar.set_search_mode(!search_mode);
         }));
         self.add_action(&start_group_search_action);
+
+        let show_recurring_action = gio::SimpleAction::new("show-recurring", None);
+        show_recurring_action.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.open_recurring_window();
+        }));
+        self.add_action(&show_recurring_action);
+
+        let show_tags_action = gio::SimpleAction::new("show-tags", None);
+        show_tags_action.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.open_tags_window();
+        }));
+        self.add_action(&show_tags_action);
+
+        let import_statement_action = gio::SimpleAction::new("import-statement", None);
+        import_statement_action.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.open_import_dialog();
+        }));
+        self.add_action(&import_statement_action);
+
+        let export_action = gio::SimpleAction::new("export", None);
+        export_action.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.open_export_dialog();
+        }));
+        self.add_action(&export_action);
+
+        let retry_save_group_action =
+            gio::SimpleAction::new("retry-save-group", Some(&String::static_variant_type()));
+        retry_save_group_action.connect_activate(clone!(@weak self as win => move |_, parameter| {
+            let group_id = parameter
+                .and_then(|p| p.get::<String>())
+                .and_then(|id| uuid::Uuid::parse_str(&id).ok());
+
+            let application = application!(win @as crate::BeedgetApplication);
+            if let Some(group) = group_id.and_then(|id| application.data().group_by_id(id)) {
+                application.emit_by_name::<()>("save-group", &[&group]);
+            }
+        }));
+        self.add_action(&retry_save_group_action);
+    }
+
+    /// Show error toast, with a retry button when a group failed to save
+    pub fn show_error(&self, message: &str, retry_group: Option<&Group>) {
+        let toast = adw::Toast::new(message);
+        toast.set_priority(adw::ToastPriority::High);
+
+        if let Some(group) = retry_group {
+            toast.set_timeout(0);
+            toast.set_button_label(Some(&gettext("Retry")));
+            toast.set_action_name(Some("win.retry-save-group"));
+            toast.set_action_target_value(Some(&group.id().to_string().to_variant()));
+        }
+
+        self.imp().toast_overlay.add_toast(&toast);
     }
 
     /// Initialize sidebar with groups from application data
@@ -147,9 +238,17 @@ impl BeedgetWindow {
         let application = application!(self @as crate::BeedgetApplication);
         let model = application.data().group_model();
 
-        let filter = gtk::StringFilter::new(Some(Group::search_expression()));
+        // Groups show up when their name or any of their transactions match
+        let filter = gtk::CustomFilter::new(
+            clone!(@weak self as win => @default-return true, move |item| {
+                let query = win.imp().search_query.borrow();
+
+                query.is_empty() || item.downcast_ref::<Group>().unwrap().matches(&query)
+            }),
+        );
         let filter_model = gtk::FilterListModel::new(Some(model), Some(&filter));
         let selection_model = gtk::SingleSelection::new(Some(&filter_model));
+        self.imp().group_filter.set(filter).unwrap();
 
         selection_model.set_autoselect(false);
         selection_model.connect_selected_notify(clone!(@weak self as win => move |model| {
@@ -159,6 +258,39 @@ impl BeedgetWindow {
         self.imp().sidebar.set_model(Some(&selection_model));
         self.imp().sidebar.set_factory(Some(&Group::factory()));
 
+        // Keep total across groups up to date
+        model.connect_items_changed(clone!(@weak self as win => move |_, _, _, _| {
+            win.refresh_total();
+        }));
+        for signal in ["save-group", "save-transaction", "delete-transaction"] {
+            application.connect_local(
+                signal,
+                true,
+                clone!(@weak self as win => @default-return None, move |_| {
+                    win.refresh_total();
+                    None
+                }),
+            );
+        }
+        // Number format changes how the total reads, moving data may change exchange rates
+        for key in ["number-format", "save-path"] {
+            application.settings().connect_changed(
+                Some(key),
+                clone!(@weak self as win => move |_, _| {
+                    win.refresh_total();
+                }),
+            );
+        }
+        self.refresh_total();
+
+        let load_problems = application.data().take_load_problems();
+        if !load_problems.is_empty() {
+            // Wait for the window to be presented before showing the dialog
+            glib::idle_add_local_once(clone!(@weak self as win => move || {
+                win.report_load_problems(&load_problems);
+            }));
+        }
+
         // Fill content with element selected by default
         self.set_content_page(
             &self
@@ -171,6 +303,52 @@ impl BeedgetWindow {
         );
     }
 
+    /// Tell the user which files were skipped while loading save data
+    fn report_load_problems(&self, problems: &[LoadProblem]) {
+        let details = problems
+            .iter()
+            .map(|problem| match &problem.quarantined_to {
+                Some(destination) => format!(
+                    "• {}: {}\n  {} {}",
+                    problem.path.display(),
+                    problem.error,
+                    gettext("Moved to"),
+                    destination.display()
+                ),
+                None => format!("• {}: {}", problem.path.display(), problem.error),
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let dialog = gtk::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .message_type(gtk::MessageType::Warning)
+            .buttons(gtk::ButtonsType::Close)
+            .text(&gettext("Some groups could not be loaded"))
+            .secondary_text(&details)
+            .build();
+
+        dialog.connect_response(|dialog, _| dialog.destroy());
+        dialog.present();
+    }
+
+    /// Show sum of all groups converted with the exchange rate table
+    fn refresh_total(&self) {
+        let application = application!(self @as crate::BeedgetApplication);
+
+        let label = match application.data().total_balance() {
+            Ok((total, currency)) => format!(
+                "{} {}",
+                gettext("Total:"),
+                currency.format_with(total, application::number_format())
+            ),
+            Err(error) => format!("{} {}", gettext("Total unavailable:"), error),
+        };
+
+        self.imp().total_label.set_label(&label);
+    }
+
     /// Creates content page for selected group
     fn set_content_page(&self, model: &gtk::SingleSelection) {
         if model.selected() != gtk::INVALID_LIST_POSITION {
@@ -179,6 +357,7 @@ impl BeedgetWindow {
             let selected_group = selected_object.downcast_ref::<Group>().unwrap();
 
             let content_page = GroupContent::new(&selected_group);
+            content_page.set_search(&self.imp().search_query.borrow());
             self.imp().content.set_child(Some(&content_page));
         }
     }